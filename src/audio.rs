@@ -13,13 +13,15 @@ use std::path;
 use std::time;
 
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use crate::context::Has;
 use crate::error::GameError;
 use crate::error::GameResult;
 use crate::filesystem::Filesystem;
 use crate::filesystem::InternalClone;
+use crate::filesystem::ReloadAsset;
+use crate::graphics::GraphicsContext;
 
 /// A struct that contains all information for tracking sound info.
 ///
@@ -27,8 +29,8 @@ use crate::filesystem::InternalClone;
 /// of your `Context` object.
 pub struct AudioContext {
     fs: Filesystem,
-    _stream: rodio::OutputStream,
-    stream_handle: rodio::OutputStreamHandle,
+    // `None` for headless contexts on machines without an output device.
+    output: Option<(rodio::OutputStream, rodio::OutputStreamHandle)>,
 }
 
 impl AudioContext {
@@ -41,16 +43,40 @@ impl AudioContext {
         })?;
         Ok(Self {
             fs: InternalClone::clone(fs),
-            _stream: stream,
-            stream_handle,
+            output: Some((stream, stream_handle)),
         })
     }
+
+    /// Creates an `AudioContext` without an output device, in which sources can't be created.
+    pub(crate) fn without_output(fs: &Filesystem) -> Self {
+        Self {
+            fs: InternalClone::clone(fs),
+            output: None,
+        }
+    }
 }
 
 impl AudioContext {
     /// Returns the audio device.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no output device, see [`AudioContext::try_device`].
     pub fn device(&self) -> &rodio::OutputStreamHandle {
-        &self.stream_handle
+        self.try_device()
+            .expect("the audio context has no output device")
+    }
+
+    /// Returns the audio device, or `None` for a headless context created on a machine
+    /// without one.
+    pub fn try_device(&self) -> Option<&rodio::OutputStreamHandle> {
+        self.output.as_ref().map(|(_, handle)| handle)
+    }
+
+    fn device_or_err(&self) -> GameResult<&rodio::OutputStreamHandle> {
+        self.try_device().ok_or_else(|| {
+            GameError::AudioError(String::from("the audio context has no output device"))
+        })
     }
 }
 
@@ -63,20 +89,35 @@ impl fmt::Debug for AudioContext {
 /// Static sound data stored in memory.
 /// It is `Arc`'ed, so cheap to clone.
 #[derive(Clone, Debug)]
-pub struct SoundData(Arc<[u8]>);
+pub struct SoundData {
+    data: Arc<[u8]>,
+    // The file the data was loaded from, for sounds loaded while asset hot reloading is on.
+    watched: Option<Arc<WatchedSound>>,
+}
 
 impl SoundData {
     /// Load the file at the given path and create a new `SoundData` from it.
+    ///
+    /// With [asset hot reloading](Filesystem::set_asset_hot_reload) on, the data is reloaded
+    /// when the file changes, which sources playing it pick up the next time they start.
     pub fn new<P: AsRef<path::Path>>(fs: &impl Has<Filesystem>, path: P) -> GameResult<Self> {
         let fs = fs.retrieve();
         let path = path.as_ref();
         let file = &mut fs.open(path)?;
-        SoundData::from_read(file)
+        let mut data = SoundData::from_read(file)?;
+        if fs.is_asset_hot_reload_enabled() {
+            let watched = Arc::new(WatchedSound {
+                data: RwLock::new(data.data.clone()),
+            });
+            fs.watch_asset(path, Arc::downgrade(&watched) as _);
+            data.watched = Some(watched);
+        }
+        Ok(data)
     }
 
     /// Copies the data in the given slice into a new `SoundData` object.
     pub fn from_bytes(data: &[u8]) -> Self {
-        SoundData(Arc::from(data))
+        SoundData::from(Arc::from(data))
     }
 
     /// Creates a `SoundData` from any `Read` object; this involves
@@ -96,31 +137,65 @@ impl SoundData {
         let cursor = io::Cursor::new(self.clone());
         rodio::Decoder::new(cursor).is_ok()
     }
+
+    /// Returns this data as it was last (re)loaded.
+    fn current(&self) -> SoundData {
+        match self
+            .watched
+            .as_ref()
+            .and_then(|watched| watched.data.read().ok())
+        {
+            Some(data) => SoundData::from(data.clone()),
+            None => self.clone(),
+        }
+    }
 }
 
 impl From<Arc<[u8]>> for SoundData {
     #[inline]
     fn from(arc: Arc<[u8]>) -> Self {
-        SoundData(arc)
+        SoundData {
+            data: arc,
+            watched: None,
+        }
     }
 }
 
 impl From<Vec<u8>> for SoundData {
     fn from(v: Vec<u8>) -> Self {
-        SoundData(Arc::from(v))
+        SoundData::from(Arc::from(v))
     }
 }
 
 impl From<Box<[u8]>> for SoundData {
     fn from(b: Box<[u8]>) -> Self {
-        SoundData(Arc::from(b))
+        SoundData::from(Arc::from(b))
     }
 }
 
 impl AsRef<[u8]> for SoundData {
     #[inline]
     fn as_ref(&self) -> &[u8] {
-        self.0.as_ref()
+        self.data.as_ref()
+    }
+}
+
+/// The file a [`SoundData`] was loaded from, see [`SoundData::new`].
+#[derive(Debug)]
+struct WatchedSound {
+    data: RwLock<Arc<[u8]>>,
+}
+
+impl ReloadAsset for WatchedSound {
+    fn reload(&self, _gfx: &GraphicsContext, bytes: Vec<u8>) -> GameResult {
+        let data = SoundData::from(bytes);
+        if !data.can_play() {
+            return Err(GameError::AudioError(
+                "Could not decode the given audio data".to_string(),
+            ));
+        }
+        *self.data.write().map_err(|_| GameError::LockError)? = data.data;
+        Ok(())
     }
 }
 
@@ -301,7 +376,7 @@ impl Source {
                 "Could not decode the given audio data".to_string(),
             ));
         }
-        let sink = rodio::Sink::try_new(audio.device())?;
+        let sink = rodio::Sink::try_new(audio.device_or_err()?)?;
         let cursor = io::Cursor::new(data);
         Ok(Source {
             sink,
@@ -317,7 +392,7 @@ impl SoundSource for Source {
         // redundant, but it's not super expensive.
         // See https://github.com/ggez/ggez/issues/98 for discussion
         use rodio::Source;
-        let cursor = self.state.data.clone();
+        let cursor = io::Cursor::new(self.state.data.get_ref().current());
 
         let counter = self.state.play_time.clone();
         let period_mus = self.state.query_interval.as_secs() as usize * 1_000_000
@@ -352,7 +427,7 @@ impl SoundSource for Source {
         self.stop(audio)?;
         self.play_later()?;
 
-        let new_sink = rodio::Sink::try_new(audio.device())?;
+        let new_sink = rodio::Sink::try_new(audio.device_or_err()?)?;
         let old_sink = mem::replace(&mut self.sink, new_sink);
         old_sink.detach();
 
@@ -397,7 +472,7 @@ impl SoundSource for Source {
         // We also need to carry over information from the previous sink.
         let volume = self.volume();
 
-        let device = audio.device();
+        let device = audio.device_or_err()?;
         self.sink = rodio::Sink::try_new(device)?;
         self.state.play_time.store(0, Ordering::SeqCst);
 
@@ -472,7 +547,7 @@ impl SpatialSource {
             ));
         }
         let sink = rodio::SpatialSink::try_new(
-            audio.device(),
+            audio.device_or_err()?,
             [0.0, 0.0, 0.0],
             [-1.0, 0.0, 0.0],
             [1.0, 0.0, 0.0],
@@ -498,7 +573,7 @@ impl SoundSource for SpatialSource {
         // redundant, but it's not super expensive.
         // See https://github.com/ggez/ggez/issues/98 for discussion
         use rodio::Source;
-        let cursor = self.state.data.clone();
+        let cursor = io::Cursor::new(self.state.data.get_ref().current());
 
         let counter = self.state.play_time.clone();
         let period_mus = self.state.query_interval.as_secs() as usize * 1_000_000
@@ -533,7 +608,7 @@ impl SoundSource for SpatialSource {
         self.stop(audio)?;
         self.play_later()?;
 
-        let device = audio.device();
+        let device = audio.device_or_err()?;
         let new_sink = rodio::SpatialSink::try_new(
             device,
             self.emitter_position.into(),
@@ -590,7 +665,7 @@ impl SoundSource for SpatialSource {
         // We also need to carry over information from the previous sink.
         let volume = self.volume();
 
-        let device = audio.device();
+        let device = audio.device_or_err()?;
         self.sink = rodio::SpatialSink::try_new(
             device,
             self.emitter_position.into(),
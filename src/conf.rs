@@ -18,6 +18,7 @@ use std::io;
 use winit::dpi::PhysicalSize;
 
 use crate::error::{GameError, GameResult};
+use crate::input::InputMap;
 
 /// Possible fullscreen modes.
 #[derive(Debug, Copy, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
@@ -378,6 +379,84 @@ impl From<NumSamples> for u8 {
     }
 }
 
+/// Settings for running [`EventHandler::update()`](../event/trait.EventHandler.html#tymethod.update)
+/// on a fixed timestep in [`event::run()`](../event/fn.run.html).
+///
+/// When enabled, `update` is called as many times per frame as needed to keep up with
+/// `updates_per_second`, independently of the render rate, and the fraction of a step left over is
+/// available in `draw` through [`TimeContext::alpha()`](../timer/struct.TimeContext.html#method.alpha)
+/// for interpolating between the previous and current state.
+/// See <http://gafferongames.com/game-physics/fix-your-timestep/> for the idea behind this.
+///
+/// Defaults:
+///
+/// ```rust
+/// # use ggez::conf::*;
+/// # fn main() { assert_eq!(
+/// FixedTimestep {
+///     enabled: false,
+///     updates_per_second: 60,
+///     max_steps_per_frame: 5,
+///     max_frame_time: 0.25,
+/// }
+/// # , FixedTimestep::default()); }
+/// ```
+#[derive(
+    Debug, Copy, Clone, smart_default::SmartDefault, serde::Serialize, serde::Deserialize, PartialEq,
+)]
+pub struct FixedTimestep {
+    /// Whether or not to use a fixed timestep. If `false`, `update` is called once per frame.
+    #[default = false]
+    pub enabled: bool,
+    /// How many times per second `update` is called.
+    #[default = 60]
+    pub updates_per_second: u32,
+    /// The maximum number of `update` calls in a single frame. If the game falls further
+    /// behind than this, the remaining time is dropped instead of being caught up on later.
+    #[default = 5]
+    pub max_steps_per_frame: u32,
+    /// The longest frame time, in seconds, that is taken into account. Longer frames
+    /// (for example while the window is being dragged) are clamped to this, so that the game
+    /// doesn't spiral into running more and more updates to catch up.
+    #[default = 0.25]
+    pub max_frame_time: f32,
+}
+
+impl FixedTimestep {
+    /// Set whether or not to use a fixed timestep.
+    #[must_use]
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Set how many times per second `update` is called.
+    #[must_use]
+    pub fn updates_per_second(mut self, updates_per_second: u32) -> Self {
+        self.updates_per_second = updates_per_second;
+        self
+    }
+
+    /// Set the maximum number of `update` calls in a single frame.
+    #[must_use]
+    pub fn max_steps_per_frame(mut self, max_steps_per_frame: u32) -> Self {
+        self.max_steps_per_frame = max_steps_per_frame;
+        self
+    }
+
+    /// Set the longest frame time, in seconds, that is taken into account.
+    #[must_use]
+    pub fn max_frame_time(mut self, max_frame_time: f32) -> Self {
+        self.max_frame_time = max_frame_time;
+        self
+    }
+
+    /// Returns the duration of a single step, i.e. the time each `update` call simulates.
+    pub fn step_duration(&self) -> std::time::Duration {
+        std::time::Duration::from_secs_f64(1.0 / f64::from(self.updates_per_second.max(1)))
+    }
+}
+
 /// A structure containing configuration data
 /// for the game engine.
 ///
@@ -385,11 +464,14 @@ impl From<NumSamples> for u8 {
 ///
 /// ```rust
 /// # use ggez::conf::*;
+/// # use ggez::input::InputMap;
 /// # fn main() { assert_eq!(
 /// Conf {
 ///     window_mode: WindowMode::default(),
 ///     window_setup: WindowSetup::default(),
 ///     backend: Backend::default(),
+///     fixed_timestep: FixedTimestep::default(),
+///     input_map: InputMap::default(),
 /// }
 /// # , Conf::default()); }
 /// ```
@@ -403,6 +485,12 @@ pub struct Conf {
     pub window_setup: WindowSetup,
     /// Graphics backend configuration
     pub backend: Backend,
+    /// Fixed timestep configuration for the game loop
+    #[serde(default)]
+    pub fixed_timestep: FixedTimestep,
+    /// Action and axis bindings, available as [`Context::input_map`](crate::Context::input_map)
+    #[serde(default)]
+    pub input_map: InputMap,
 }
 
 impl Conf {
@@ -441,6 +529,20 @@ impl Conf {
         self.backend = backend;
         self
     }
+
+    /// Sets the fixed timestep configuration
+    #[must_use]
+    pub fn fixed_timestep(mut self, fixed_timestep: FixedTimestep) -> Self {
+        self.fixed_timestep = fixed_timestep;
+        self
+    }
+
+    /// Sets the action and axis bindings
+    #[must_use]
+    pub fn input_map(mut self, input_map: InputMap) -> Self {
+        self.input_map = input_map;
+        self
+    }
 }
 
 #[cfg(test)]
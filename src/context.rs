@@ -8,7 +8,7 @@ pub use winit;
 #[cfg(feature = "audio")]
 use crate::audio;
 use crate::conf;
-use crate::error::GameResult;
+use crate::error::{GameError, GameResult};
 use crate::filesystem::Filesystem;
 use crate::graphics;
 use crate::graphics::GraphicsContext;
@@ -52,6 +52,10 @@ pub struct Context {
     /// Gamepad input context.
     #[cfg(feature = "gamepad")]
     pub gamepad: input::gamepad::GamepadContext,
+    /// Action and axis bindings, initialized from the [`Conf`](crate::conf::Conf).
+    pub input_map: input::InputMap,
+    /// Input recording and replay state.
+    pub recorder: input::recording::InputRecorder,
 
     /// The Conf object the Context was created with.
     /// It's here just so that we can see the original settings,
@@ -172,15 +176,52 @@ impl Context {
         conf: conf::Conf,
         fs: Filesystem,
     ) -> GameResult<(Context, winit::event_loop::EventLoop<()>)> {
-        #[cfg(feature = "audio")]
-        let audio_context = audio::AudioContext::new(&fs)?;
         let events_loop = winit::event_loop::EventLoop::new();
-        let timer_context = timer::TimeContext::new();
         let graphics_context =
             graphics::context::GraphicsContext::new(game_id, &events_loop, &conf, &fs)?;
+        let ctx = Context::from_graphics(conf, fs, graphics_context)?;
 
-        let ctx = Context {
-            conf,
+        Ok((ctx, events_loop))
+    }
+
+    /// Tries to create a new headless Context, which has no window and no event loop.
+    /// Usually called by [`ContextBuilder::build_headless()`](struct.ContextBuilder.html#method.build_headless).
+    fn from_conf_headless(conf: conf::Conf, fs: Filesystem) -> GameResult<Context> {
+        let graphics_context = graphics::context::GraphicsContext::new_headless(&conf, &fs)?;
+        Context::from_graphics(conf, fs, graphics_context)
+    }
+
+    fn from_graphics(
+        conf: conf::Conf,
+        fs: Filesystem,
+        graphics_context: GraphicsContext,
+    ) -> GameResult<Context> {
+        // Headless contexts are meant to run where there may be no sound card or gamepad
+        // support, like CI runners, so they do without rather than fail.
+        #[cfg(any(feature = "audio", feature = "gamepad"))]
+        let headless = graphics_context.is_headless();
+        #[cfg(feature = "audio")]
+        let audio_context = match audio::AudioContext::new(&fs) {
+            Ok(audio) => audio,
+            Err(e) if headless => {
+                warn!("Creating a headless context without audio output: {e}");
+                audio::AudioContext::without_output(&fs)
+            }
+            Err(e) => return Err(e),
+        };
+        #[cfg(feature = "gamepad")]
+        let gamepad_context = match input::gamepad::GamepadContext::new() {
+            Ok(gamepad) => gamepad,
+            Err(e) if headless => {
+                warn!("Creating a headless context without gamepad support: {e}");
+                input::gamepad::GamepadContext::without_gilrs()
+            }
+            Err(e) => return Err(e),
+        };
+        let mut timer_context = timer::TimeContext::new();
+        timer_context.set_fixed_timestep(conf.fixed_timestep);
+
+        Ok(Context {
             fs,
             gfx: graphics_context,
             continuing: true,
@@ -191,10 +232,11 @@ impl Context {
             keyboard: input::keyboard::KeyboardContext::new(),
             mouse: input::mouse::MouseContext::new(),
             #[cfg(feature = "gamepad")]
-            gamepad: input::gamepad::GamepadContext::new()?,
-        };
-
-        Ok((ctx, events_loop))
+            gamepad: gamepad_context,
+            input_map: conf.input_map.clone(),
+            recorder: input::recording::InputRecorder::new(),
+            conf,
+        })
     }
 }
 
@@ -212,6 +254,7 @@ pub struct ContextBuilder {
     pub(crate) paths: Vec<path::PathBuf>,
     pub(crate) memory_zip_files: Vec<Cow<'static, [u8]>>,
     pub(crate) load_conf_file: bool,
+    pub(crate) headless: Option<(u32, u32)>,
 }
 
 impl ContextBuilder {
@@ -226,6 +269,7 @@ impl ContextBuilder {
             paths: vec![],
             memory_zip_files: vec![],
             load_conf_file: true,
+            headless: None,
         }
     }
 
@@ -250,10 +294,26 @@ impl ContextBuilder {
         self
     }
 
+    /// Sets the fixed timestep settings of the game loop.
+    #[must_use]
+    pub fn fixed_timestep(mut self, fixed_timestep: conf::FixedTimestep) -> Self {
+        self.conf.fixed_timestep = fixed_timestep;
+        self
+    }
+
+    /// Sets the default action and axis bindings.
+    #[must_use]
+    pub fn input_map(mut self, input_map: input::InputMap) -> Self {
+        self.conf.input_map = input_map;
+        self
+    }
+
     /// Sets all the config options, overriding any previous
     /// ones from [`window_setup()`](#method.window_setup),
-    /// [`window_mode()`](#method.window_mode), and
-    /// [`backend()`](#method.backend).  These are used as
+    /// [`window_mode()`](#method.window_mode),
+    /// [`backend()`](#method.backend),
+    /// [`fixed_timestep()`](#method.fixed_timestep), and
+    /// [`input_map()`](#method.input_map).  These are used as
     /// defaults and are overridden by any external config
     /// file found.
     #[must_use]
@@ -319,8 +379,52 @@ impl ContextBuilder {
         self
     }
 
+    /// Makes the `Context` headless: no window or event loop is created and everything is
+    /// rendered to an offscreen frame image of the given size (in physical pixels), which
+    /// can be read back with [`Image::to_pixels`](crate::graphics::Image::to_pixels) or
+    /// [`Image::encode`](crate::graphics::Image::encode).
+    ///
+    /// This is useful for rendering on machines without a display, such as in CI.
+    /// The `Context` must then be created with [`build_headless()`](#method.build_headless).
+    #[must_use]
+    pub fn headless(mut self, width: u32, height: u32) -> Self {
+        self.headless = Some((width, height));
+        self
+    }
+
     /// Build the `Context`.
+    ///
+    /// Returns an error if [`headless()`](#method.headless) was set; use
+    /// [`build_headless()`](#method.build_headless) instead.
     pub fn build(self) -> GameResult<(Context, winit::event_loop::EventLoop<()>)> {
+        if self.headless.is_some() {
+            return Err(GameError::ConfigError(String::from(
+                "a headless context has no event loop; use `ContextBuilder::build_headless` instead",
+            )));
+        }
+        let game_id = self.game_id.clone();
+        let (config, fs) = self.build_fs()?;
+        Context::from_conf(game_id.as_ref(), config, fs)
+    }
+
+    /// Build a headless `Context`, without a window or event loop.
+    ///
+    /// Its size is taken from [`headless()`](#method.headless) if set, or from the window mode otherwise.
+    /// Since there's no event loop, [`event::run()`](crate::event::run) can't be used;
+    /// call `update`/`draw` yourself, wrapping drawing in
+    /// [`begin_frame()`](crate::graphics::GraphicsContext::begin_frame) and
+    /// [`end_frame()`](crate::graphics::GraphicsContext::end_frame).
+    pub fn build_headless(self) -> GameResult<Context> {
+        let headless = self.headless;
+        let (mut config, fs) = self.build_fs()?;
+        if let Some((width, height)) = headless {
+            config.window_mode = config.window_mode.dimensions(width as f32, height as f32);
+            config.window_mode.logical_size = None;
+        }
+        Context::from_conf_headless(config, fs)
+    }
+
+    fn build_fs(self) -> GameResult<(conf::Conf, Filesystem)> {
         let fs = Filesystem::new(
             self.game_id.as_ref(),
             self.author.as_ref(),
@@ -342,10 +446,19 @@ impl ContextBuilder {
             self.conf
         };
 
-        Context::from_conf(self.game_id.as_ref(), config, fs)
+        Ok((config, fs))
     }
 }
 
+/// Builds a headless [`Context`] of the given size for tests, see [`ContextBuilder::headless`].
+#[cfg(test)]
+pub(crate) fn headless_ctx_for_tests(width: u32, height: u32) -> Context {
+    ContextBuilder::new("test", "ggez")
+        .headless(width, height)
+        .build_headless()
+        .unwrap()
+}
+
 /// Terminates the [`ggez::event::run()`](crate::event::run) loop _without_ requesting a
 /// [`quit_event`](crate::event::EventHandler::quit_event). [`Context.continuing`](struct.Context.html#structfield.continuing)
 /// is set to `false` and the loop breaks.
@@ -360,7 +473,7 @@ pub fn quit(ctx: &mut Context) {
 #[cfg(test)]
 mod tests {
     use crate::{
-        context::{Has, HasMut},
+        context::{headless_ctx_for_tests, Has, HasMut},
         graphics::GraphicsContext,
         ContextBuilder,
     };
@@ -378,4 +491,108 @@ mod tests {
         takes_mut_gfx(&mut ctx);
         takes_mut_gfx(&mut ctx.gfx);
     }
+
+    #[test]
+    fn headless_test_render_to_frame() {
+        use crate::graphics::{Canvas, Color};
+
+        let mut ctx = headless_ctx_for_tests(67, 33);
+        assert!(ctx.gfx.is_headless());
+        assert_eq!(ctx.gfx.drawable_size(), (67., 33.));
+
+        ctx.gfx.begin_frame().unwrap();
+        let canvas = Canvas::from_frame(&ctx, Color::RED);
+        canvas.finish(&mut ctx).unwrap();
+        ctx.gfx.end_frame().unwrap();
+
+        let pixels = ctx.gfx.frame().to_pixels(&ctx).unwrap();
+        assert_eq!(pixels.len(), 67 * 33 * 4);
+        assert!(pixels.chunks_exact(4).all(|p| p == [255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn headless_test_present_image() {
+        use crate::graphics::Image;
+
+        let mut ctx = headless_ctx_for_tests(8, 4);
+        let format = ctx.gfx.surface_format();
+        let green = Image::from_pixels(&ctx, &[0, 255, 0, 255], format, 1, 1);
+
+        // like a window, the frame shows the presented image stretched over it
+        ctx.gfx.begin_frame().unwrap();
+        ctx.gfx.present(&green).unwrap();
+        ctx.gfx.end_frame().unwrap();
+
+        let pixels = ctx.gfx.frame().to_pixels(&ctx).unwrap();
+        assert!(pixels.chunks_exact(4).all(|p| p == [0, 255, 0, 255]));
+    }
+
+    fn draw_frame(ctx: &mut crate::Context, color: crate::graphics::Color) {
+        ctx.gfx.begin_frame().unwrap();
+        let canvas = crate::graphics::Canvas::from_frame(ctx, color);
+        canvas.finish(ctx).unwrap();
+        ctx.gfx.end_frame().unwrap();
+    }
+
+    #[test]
+    fn headless_test_screenshot() {
+        use crate::graphics::Color;
+
+        let mut ctx = headless_ctx_for_tests(16, 8);
+
+        draw_frame(&mut ctx, Color::RED);
+        let screenshot = ctx.gfx.screenshot().unwrap();
+        draw_frame(&mut ctx, Color::BLUE);
+
+        assert_eq!((screenshot.width(), screenshot.height()), (16, 8));
+        let pixels = screenshot.to_pixels(&ctx).unwrap();
+        assert!(pixels.chunks_exact(4).all(|p| p == [255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn headless_test_screenshot_presented() {
+        use crate::graphics::Image;
+
+        let mut ctx = headless_ctx_for_tests(16, 8);
+        let format = ctx.gfx.surface_format();
+        let green = Image::from_pixels(&ctx, &[0, 255, 0, 255].repeat(8), format, 4, 2);
+        let dir = std::path::Path::new("/headless_test_screenshot_presented");
+
+        ctx.gfx.start_recording(dir, 1).unwrap();
+        ctx.gfx.begin_frame().unwrap();
+        ctx.gfx.present(&green).unwrap();
+        ctx.gfx.end_frame().unwrap();
+        assert_eq!(ctx.gfx.stop_recording(), Some(1));
+
+        for image in [
+            ctx.gfx.screenshot().unwrap(),
+            Image::from_path(&ctx, dir.join("000000.png")).unwrap(),
+        ] {
+            assert_eq!((image.width(), image.height()), (4, 2));
+            let pixels = image.to_pixels(&ctx).unwrap();
+            assert!(pixels.chunks_exact(4).all(|p| p == [0, 255, 0, 255]));
+        }
+        ctx.fs.delete_dir(dir).unwrap();
+    }
+
+    #[test]
+    fn headless_test_recording() {
+        use crate::graphics::Color;
+
+        let mut ctx = headless_ctx_for_tests(16, 8);
+        let dir = std::path::Path::new("/headless_test_recording");
+
+        ctx.gfx.start_recording(dir, 2).unwrap();
+        assert!(ctx.gfx.is_recording());
+        for _ in 0..3 {
+            draw_frame(&mut ctx, Color::GREEN);
+        }
+        assert_eq!(ctx.gfx.stop_recording(), Some(2));
+        assert!(!ctx.gfx.is_recording());
+
+        assert!(ctx.fs.is_file(dir.join("000000.png")));
+        assert!(ctx.fs.is_file(dir.join("000001.png")));
+        assert!(!ctx.fs.exists(dir.join("000002.png")));
+        ctx.fs.delete_dir(dir).unwrap();
+    }
 }
@@ -161,6 +161,14 @@ impl From<gilrs::Error> for GameError {
     }
 }
 
+#[cfg(feature = "gamepad")]
+impl From<gilrs::ff::Error> for GameError {
+    fn from(s: gilrs::ff::Error) -> GameError {
+        let errstr = format!("Gamepad force feedback error: {s}");
+        GameError::GamepadError(errstr)
+    }
+}
+
 impl From<lyon::lyon_tessellation::TessellationError> for GameError {
     fn from(s: lyon::lyon_tessellation::TessellationError) -> GameError {
         let errstr =
@@ -41,6 +41,10 @@ use self::winit_event::{
 pub use winit::event_loop::{ControlFlow, EventLoop};
 
 use crate::context::Context;
+use crate::graphics::ProfileScope;
+use crate::input::recording::{RecordedEvent, RecordedFrame};
+use std::time::Instant;
+use winit::window::WindowId;
 
 /// Used in [`EventHandler::on_error()`](trait.EventHandler.html#method.on_error)
 /// to specify where an error originated
@@ -101,12 +105,19 @@ where
 {
     /// Called upon each logic update to the game.
     /// This should be where the game's logic takes place.
+    ///
+    /// If [`Conf::fixed_timestep`](../conf/struct.Conf.html#structfield.fixed_timestep) is enabled,
+    /// this is called zero or more times per frame, each call simulating
+    /// [`FixedTimestep::step_duration()`](../conf/struct.FixedTimestep.html#method.step_duration).
     fn update(&mut self, _ctx: &mut Context) -> Result<(), E>;
 
     /// Called to do the drawing of your game.
     /// You probably want to start this with
     /// [`Canvas::from_frame`](../graphics/struct.Canvas.html#method.from_frame) and end it
     /// with [`Canvas::finish`](../graphics/struct.Canvas.html#method.finish).
+    ///
+    /// With a fixed timestep, [`ctx.time.alpha()`](../timer/struct.TimeContext.html#method.alpha)
+    /// gives the interpolation factor between the previous and the current update.
     fn draw(&mut self, _ctx: &mut Context) -> Result<(), E>;
 
     /// A mouse button was pressed
@@ -287,294 +298,555 @@ where
         let ctx = &mut ctx;
         let state = &mut state;
 
-        if ctx.quit_requested {
-            let res = state.quit_event(ctx);
-            ctx.quit_requested = false;
-            if let Ok(false) = res {
-                ctx.continuing = false;
-            } else if catch_error(ctx, res, state, control_flow, ErrorOrigin::QuitEvent) {
-                return;
-            }
-        }
-        if !ctx.continuing {
-            *control_flow = ControlFlow::Exit;
+        if handle_quit_request(ctx, state, control_flow) {
             return;
         }
 
         *control_flow = ControlFlow::Poll;
 
+        if let Event::WindowEvent {
+            event: window_event,
+            ..
+        } = &event
+        {
+            if let Some(input) = RecordedEvent::from_window_event(window_event) {
+                if !ctx.recorder.is_replaying() {
+                    ctx.recorder.record(input);
+                } else if input == RecordedEvent::CloseRequested {
+                    ctx.recorder.stop_replay();
+                } else {
+                    // Live input is ignored while a recording is replayed, but the frame
+                    // still follows the size of the window.
+                    if let RecordedEvent::Resized { .. } = input {
+                        process_event(ctx, &mut event);
+                    }
+                    return;
+                }
+            }
+        }
+
         process_event(ctx, &mut event);
         match event {
-            Event::WindowEvent { event, .. } => match event {
-                WindowEvent::Resized(logical_size) => {
-                    // let actual_size = logical_size;
-                    let res = state.resize_event(
-                        ctx,
-                        logical_size.width as f32,
-                        logical_size.height as f32,
-                    );
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::ResizeEvent) {
-                        return;
-                    };
+            Event::WindowEvent { event, .. } => {
+                if dispatch_window_event(ctx, state, control_flow, event) {
+                    return;
                 }
-                WindowEvent::CloseRequested => {
-                    let res = state.quit_event(ctx);
-                    if let Ok(false) = res {
-                        ctx.continuing = false;
-                    } else if catch_error(ctx, res, state, control_flow, ErrorOrigin::QuitEvent) {
+            }
+            Event::DeviceEvent { .. } => (),
+            Event::Resumed => (),
+            Event::Suspended => (),
+            Event::NewEvents(_) => (),
+            Event::UserEvent(_) => (),
+            Event::MainEventsCleared => {
+                // If you are writing your own event loop, make sure
+                // you include `timer_context.tick()` and
+                // `ctx.process_event()` calls.  These update ggez's
+                // internal state however necessary.
+                let replayed = ctx.recorder.next_frame();
+                if let Some(frame) = &replayed {
+                    if replay_window_events(ctx, state, control_flow, frame) {
                         return;
                     }
+                    ctx.time.tick_by(frame.delta, frame.time);
+                } else {
+                    ctx.time.tick();
                 }
-                WindowEvent::Focused(gained) => {
-                    let res = state.focus_event(ctx, gained);
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::FocusEvent) {
-                        return;
-                    };
-                }
-                WindowEvent::ReceivedCharacter(ch) => {
-                    let res = state.text_input_event(ctx, ch);
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::TextInputEvent) {
+
+                // Handle gamepad events if necessary.
+                #[cfg(feature = "gamepad")]
+                if let Some(frame) = &replayed {
+                    if replay_gamepad_events(ctx, state, control_flow, frame) {
                         return;
-                    };
+                    }
+                } else {
+                    while let Some(gilrs::Event { id, event, .. }) = ctx.gamepad.next_event() {
+                        let Some(input) =
+                            RecordedEvent::from_gamepad_event(GamepadId::from_gilrs(id), &event)
+                        else {
+                            continue;
+                        };
+                        ctx.recorder.record(input);
+                        if dispatch_gamepad_event(ctx, state, control_flow, input) {
+                            return;
+                        }
+                    }
                 }
-                WindowEvent::ModifiersChanged(mods) => {
-                    ctx.keyboard.set_modifiers(KeyMods::from(mods))
+                ctx.recorder.end_frame(&ctx.time);
+
+                #[cfg(feature = "gamepad")]
+                if let Err(e) = ctx.gamepad.update_rumble(ctx.time.real_delta()) {
+                    error!("Error on GamepadContext::update_rumble(): {e:?}");
                 }
-                WindowEvent::KeyboardInput {
-                    input:
-                        KeyboardInput {
-                            state: ElementState::Pressed,
-                            virtual_keycode: keycode,
-                            scancode,
-                            ..
-                        },
-                    ..
-                } => {
-                    let repeat = ctx.keyboard.is_key_repeated();
-                    let res = state.key_down_event(
-                        ctx,
-                        KeyInput {
-                            scancode,
-                            keycode,
-                            mods: ctx.keyboard.active_mods(),
-                        },
-                        repeat,
-                    );
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::KeyDownEvent) {
-                        return;
-                    };
+
+                if run_frame(ctx, state, control_flow) {
+                    return;
                 }
-                WindowEvent::KeyboardInput {
-                    input:
-                        KeyboardInput {
-                            state: ElementState::Released,
-                            virtual_keycode: keycode,
-                            scancode,
-                            ..
-                        },
+            }
+            Event::RedrawRequested(_) => (),
+            Event::RedrawEventsCleared => (),
+            Event::LoopDestroyed => (),
+        }
+    })
+}
+
+/// Handles a quit requested with [`Context::request_quit`], calling [`EventHandler::quit_event`].
+///
+/// Returns `true` if the event loop should stop.
+pub(crate) fn handle_quit_request<S: 'static, E>(
+    ctx: &mut Context,
+    state: &mut S,
+    control_flow: &mut ControlFlow,
+) -> bool
+where
+    S: EventHandler<E>,
+    E: std::fmt::Debug,
+{
+    if ctx.quit_requested {
+        let res = state.quit_event(ctx);
+        ctx.quit_requested = false;
+        if let Ok(false) = res {
+            ctx.continuing = false;
+        } else if catch_error(ctx, res, state, control_flow, ErrorOrigin::QuitEvent) {
+            return true;
+        }
+    }
+    if !ctx.continuing {
+        *control_flow = ControlFlow::Exit;
+        return true;
+    }
+    false
+}
+
+/// Runs the updates and draws a frame, once the clock has been ticked and the input
+/// for the frame has been processed.
+///
+/// Returns `true` if an error stopped the event loop.
+pub(crate) fn run_frame<S: 'static, E>(
+    ctx: &mut Context,
+    state: &mut S,
+    control_flow: &mut ControlFlow,
+) -> bool
+where
+    S: EventHandler<E>,
+    E: std::fmt::Debug,
+{
+    let update_start = Instant::now();
+    let fixed_timestep = ctx.time.fixed_timestep().enabled;
+    if fixed_timestep {
+        for _ in 0..ctx.time.fixed_update_steps() {
+            let res = state.update(ctx);
+            if catch_error(ctx, res, state, control_flow, ErrorOrigin::Update) {
+                return true;
+            };
+
+            // With a fixed timestep, a frame may run no updates at all,
+            // so the input state is kept until an update has seen it.
+            ctx.mouse.reset_delta();
+            ctx.keyboard.save_keyboard_state();
+            ctx.mouse.save_mouse_state();
+            #[cfg(feature = "gamepad")]
+            ctx.gamepad.save_gamepad_state();
+        }
+    } else {
+        let res = state.update(ctx);
+        if catch_error(ctx, res, state, control_flow, ErrorOrigin::Update) {
+            return true;
+        };
+    }
+
+    ctx.gfx
+        .profiler_mut()
+        .record(ProfileScope::Update, update_start.elapsed());
+
+    if let Err(e) = ctx.gfx.begin_frame() {
+        error!("Error on GraphicsContext::begin_frame(): {e:?}");
+        eprintln!("Error on GraphicsContext::begin_frame(): {e:?}");
+        *control_flow = ControlFlow::Exit;
+    }
+
+    let draw_start = Instant::now();
+    let res = state.draw(ctx);
+    ctx.gfx
+        .profiler_mut()
+        .record(ProfileScope::Draw, draw_start.elapsed());
+    if let Err(e) = res {
+        error!("Error on EventHandler::draw(): {e:?}");
+        eprintln!("Error on EventHandler::draw(): {e:?}");
+        if state.on_error(ctx, ErrorOrigin::Draw, e) {
+            *control_flow = ControlFlow::Exit;
+            return true;
+        }
+    }
+
+    if let Err(e) = ctx.gfx.end_frame() {
+        error!("Error on GraphicsContext::end_frame(): {e:?}");
+        eprintln!("Error on GraphicsContext::end_frame(): {e:?}");
+        *control_flow = ControlFlow::Exit;
+    }
+
+    if !fixed_timestep {
+        // reset the mouse delta for the next frame
+        // necessary because it's calculated cumulatively each cycle
+        ctx.mouse.reset_delta();
+
+        // Copy the state of the keyboard into the KeyboardContext
+        // and the mouse into the MouseContext
+        ctx.keyboard.save_keyboard_state();
+        ctx.mouse.save_mouse_state();
+        #[cfg(feature = "gamepad")]
+        ctx.gamepad.save_gamepad_state();
+    }
+    false
+}
+
+/// Calls the [`EventHandler`] callback for a window event that went through [`process_event`].
+///
+/// Returns `true` if an error stopped the event loop.
+fn dispatch_window_event<S: 'static, E>(
+    ctx: &mut Context,
+    state: &mut S,
+    control_flow: &mut ControlFlow,
+    event: WindowEvent,
+) -> bool
+where
+    S: EventHandler<E>,
+    E: std::fmt::Debug,
+{
+    match event {
+        WindowEvent::Resized(logical_size) => {
+            // let actual_size = logical_size;
+            let res =
+                state.resize_event(ctx, logical_size.width as f32, logical_size.height as f32);
+            if catch_error(ctx, res, state, control_flow, ErrorOrigin::ResizeEvent) {
+                return true;
+            };
+        }
+        WindowEvent::CloseRequested => {
+            let res = state.quit_event(ctx);
+            if let Ok(false) = res {
+                ctx.continuing = false;
+            } else if catch_error(ctx, res, state, control_flow, ErrorOrigin::QuitEvent) {
+                return true;
+            }
+        }
+        WindowEvent::Focused(gained) => {
+            let res = state.focus_event(ctx, gained);
+            if catch_error(ctx, res, state, control_flow, ErrorOrigin::FocusEvent) {
+                return true;
+            };
+        }
+        WindowEvent::ReceivedCharacter(ch) => {
+            let res = state.text_input_event(ctx, ch);
+            if catch_error(ctx, res, state, control_flow, ErrorOrigin::TextInputEvent) {
+                return true;
+            };
+        }
+        WindowEvent::ModifiersChanged(mods) => ctx.keyboard.set_modifiers(KeyMods::from(mods)),
+        WindowEvent::KeyboardInput {
+            input:
+                KeyboardInput {
+                    state: ElementState::Pressed,
+                    virtual_keycode: keycode,
+                    scancode,
                     ..
-                } => {
-                    let res = state.key_up_event(
-                        ctx,
-                        KeyInput {
-                            scancode,
-                            keycode,
-                            mods: ctx.keyboard.active_mods(),
-                        },
-                    );
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::KeyUpEvent) {
-                        return;
-                    };
-                }
-                WindowEvent::MouseWheel { delta, .. } => {
-                    let (x, y) = match delta {
-                        MouseScrollDelta::LineDelta(x, y) => (x, y),
-                        MouseScrollDelta::PixelDelta(pos) => {
-                            let scale_factor = ctx.gfx.window.scale_factor();
-                            let dpi::LogicalPosition { x, y } = pos.to_logical::<f32>(scale_factor);
-                            (x, y)
-                        }
-                    };
-                    let res = state.mouse_wheel_event(ctx, x, y);
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::MouseWheelEvent) {
-                        return;
-                    };
-                }
-                WindowEvent::MouseInput {
-                    state: element_state,
-                    button,
+                },
+            ..
+        } => {
+            let repeat = ctx.keyboard.is_key_repeated();
+            let res = state.key_down_event(
+                ctx,
+                KeyInput {
+                    scancode,
+                    keycode,
+                    mods: ctx.keyboard.active_mods(),
+                },
+                repeat,
+            );
+            if catch_error(ctx, res, state, control_flow, ErrorOrigin::KeyDownEvent) {
+                return true;
+            };
+        }
+        WindowEvent::KeyboardInput {
+            input:
+                KeyboardInput {
+                    state: ElementState::Released,
+                    virtual_keycode: keycode,
+                    scancode,
                     ..
-                } => {
-                    let position = ctx.mouse.position();
-                    match element_state {
-                        ElementState::Pressed => {
-                            let res =
-                                state.mouse_button_down_event(ctx, button, position.x, position.y);
-                            if catch_error(
-                                ctx,
-                                res,
-                                state,
-                                control_flow,
-                                ErrorOrigin::MouseButtonDownEvent,
-                            ) {
-                                return;
-                            };
-                        }
-                        ElementState::Released => {
-                            let res =
-                                state.mouse_button_up_event(ctx, button, position.x, position.y);
-                            if catch_error(
-                                ctx,
-                                res,
-                                state,
-                                control_flow,
-                                ErrorOrigin::MouseButtonUpEvent,
-                            ) {
-                                return;
-                            };
-                        }
-                    }
-                }
-                WindowEvent::CursorMoved { .. } => {
-                    let position = ctx.mouse.position();
-                    let delta = ctx.mouse.last_delta();
-                    let res =
-                        state.mouse_motion_event(ctx, position.x, position.y, delta.x, delta.y);
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::MouseMotionEvent) {
-                        return;
-                    };
-                }
-                WindowEvent::Touch(touch) => {
-                    let res =
-                        state.touch_event(ctx, touch.phase, touch.location.x, touch.location.y);
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::TouchEvent) {
-                        return;
-                    };
+                },
+            ..
+        } => {
+            let res = state.key_up_event(
+                ctx,
+                KeyInput {
+                    scancode,
+                    keycode,
+                    mods: ctx.keyboard.active_mods(),
+                },
+            );
+            if catch_error(ctx, res, state, control_flow, ErrorOrigin::KeyUpEvent) {
+                return true;
+            };
+        }
+        WindowEvent::MouseWheel { delta, .. } => {
+            let (x, y) = match delta {
+                MouseScrollDelta::LineDelta(x, y) => (x, y),
+                MouseScrollDelta::PixelDelta(pos) => {
+                    let scale_factor = ctx
+                        .gfx
+                        .window
+                        .as_ref()
+                        .map_or(1.0, |window| window.scale_factor());
+                    let dpi::LogicalPosition { x, y } = pos.to_logical::<f32>(scale_factor);
+                    (x, y)
                 }
-                WindowEvent::CursorEntered { device_id: _ } => {
-                    let res = state.mouse_enter_or_leave(ctx, true);
+            };
+            let res = state.mouse_wheel_event(ctx, x, y);
+            if catch_error(ctx, res, state, control_flow, ErrorOrigin::MouseWheelEvent) {
+                return true;
+            };
+        }
+        WindowEvent::MouseInput {
+            state: element_state,
+            button,
+            ..
+        } => {
+            let position = ctx.mouse.position();
+            match element_state {
+                ElementState::Pressed => {
+                    let res = state.mouse_button_down_event(ctx, button, position.x, position.y);
                     if catch_error(
                         ctx,
                         res,
                         state,
                         control_flow,
-                        ErrorOrigin::MouseEnterOrLeave,
+                        ErrorOrigin::MouseButtonDownEvent,
                     ) {
-                        return;
-                    }
+                        return true;
+                    };
                 }
-                WindowEvent::CursorLeft { device_id: _ } => {
-                    let res = state.mouse_enter_or_leave(ctx, false);
+                ElementState::Released => {
+                    let res = state.mouse_button_up_event(ctx, button, position.x, position.y);
                     if catch_error(
                         ctx,
                         res,
                         state,
                         control_flow,
-                        ErrorOrigin::MouseEnterOrLeave,
+                        ErrorOrigin::MouseButtonUpEvent,
                     ) {
-                        return;
-                    }
-                }
-                _x => {
-                    // trace!("ignoring window event {:?}", x);
-                }
-            },
-            Event::DeviceEvent { .. } => (),
-            Event::Resumed => (),
-            Event::Suspended => (),
-            Event::NewEvents(_) => (),
-            Event::UserEvent(_) => (),
-            Event::MainEventsCleared => {
-                // If you are writing your own event loop, make sure
-                // you include `timer_context.tick()` and
-                // `ctx.process_event()` calls.  These update ggez's
-                // internal state however necessary.
-                ctx.time.tick();
-
-                // Handle gamepad events if necessary.
-                #[cfg(feature = "gamepad")]
-                while let Some(gilrs::Event { id, event, .. }) = ctx.gamepad.next_event() {
-                    match event {
-                        gilrs::EventType::ButtonPressed(button, _) => {
-                            let res = state.gamepad_button_down_event(ctx, button, GamepadId(id));
-                            if catch_error(
-                                ctx,
-                                res,
-                                state,
-                                control_flow,
-                                ErrorOrigin::GamepadButtonDownEvent,
-                            ) {
-                                return;
-                            };
-                        }
-                        gilrs::EventType::ButtonReleased(button, _) => {
-                            let res = state.gamepad_button_up_event(ctx, button, GamepadId(id));
-                            if catch_error(
-                                ctx,
-                                res,
-                                state,
-                                control_flow,
-                                ErrorOrigin::GamepadButtonUpEvent,
-                            ) {
-                                return;
-                            };
-                        }
-                        gilrs::EventType::AxisChanged(axis, value, _) => {
-                            let res = state.gamepad_axis_event(ctx, axis, value, GamepadId(id));
-                            if catch_error(
-                                ctx,
-                                res,
-                                state,
-                                control_flow,
-                                ErrorOrigin::GamepadAxisEvent,
-                            ) {
-                                return;
-                            };
-                        }
-                        _ => {}
-                    }
-                }
-
-                let res = state.update(ctx);
-                if catch_error(ctx, res, state, control_flow, ErrorOrigin::Update) {
-                    return;
-                };
-
-                if let Err(e) = ctx.gfx.begin_frame() {
-                    error!("Error on GraphicsContext::begin_frame(): {e:?}");
-                    eprintln!("Error on GraphicsContext::begin_frame(): {e:?}");
-                    *control_flow = ControlFlow::Exit;
+                        return true;
+                    };
                 }
+            }
+        }
+        WindowEvent::CursorMoved { .. } => {
+            let position = ctx.mouse.position();
+            let delta = ctx.mouse.last_delta();
+            let res = state.mouse_motion_event(ctx, position.x, position.y, delta.x, delta.y);
+            if catch_error(ctx, res, state, control_flow, ErrorOrigin::MouseMotionEvent) {
+                return true;
+            };
+        }
+        WindowEvent::Touch(touch) => {
+            let res = state.touch_event(ctx, touch.phase, touch.location.x, touch.location.y);
+            if catch_error(ctx, res, state, control_flow, ErrorOrigin::TouchEvent) {
+                return true;
+            };
+        }
+        WindowEvent::CursorEntered { device_id: _ } => {
+            let res = state.mouse_enter_or_leave(ctx, true);
+            if catch_error(
+                ctx,
+                res,
+                state,
+                control_flow,
+                ErrorOrigin::MouseEnterOrLeave,
+            ) {
+                return true;
+            }
+        }
+        WindowEvent::CursorLeft { device_id: _ } => {
+            let res = state.mouse_enter_or_leave(ctx, false);
+            if catch_error(
+                ctx,
+                res,
+                state,
+                control_flow,
+                ErrorOrigin::MouseEnterOrLeave,
+            ) {
+                return true;
+            }
+        }
+        _x => {
+            // trace!("ignoring window event {:?}", x);
+        }
+    }
+    false
+}
 
-                if let Err(e) = state.draw(ctx) {
-                    error!("Error on EventHandler::draw(): {e:?}");
-                    eprintln!("Error on EventHandler::draw(): {e:?}");
-                    if state.on_error(ctx, ErrorOrigin::Draw, e) {
-                        *control_flow = ControlFlow::Exit;
-                        return;
-                    }
-                }
+/// Calls the [`EventHandler`] callback for a gamepad event.
+///
+/// Returns `true` if an error stopped the event loop.
+#[cfg(feature = "gamepad")]
+fn dispatch_gamepad_event<S: 'static, E>(
+    ctx: &mut Context,
+    state: &mut S,
+    control_flow: &mut ControlFlow,
+    input: RecordedEvent,
+) -> bool
+where
+    S: EventHandler<E>,
+    E: std::fmt::Debug,
+{
+    match input {
+        RecordedEvent::GamepadButton {
+            id,
+            button,
+            pressed: true,
+        } => {
+            let res = state.gamepad_button_down_event(ctx, button, id);
+            catch_error(
+                ctx,
+                res,
+                state,
+                control_flow,
+                ErrorOrigin::GamepadButtonDownEvent,
+            )
+        }
+        RecordedEvent::GamepadButton {
+            id,
+            button,
+            pressed: false,
+        } => {
+            let res = state.gamepad_button_up_event(ctx, button, id);
+            catch_error(
+                ctx,
+                res,
+                state,
+                control_flow,
+                ErrorOrigin::GamepadButtonUpEvent,
+            )
+        }
+        RecordedEvent::GamepadAxis { id, axis, value } => {
+            let res = state.gamepad_axis_event(ctx, axis, value, id);
+            catch_error(ctx, res, state, control_flow, ErrorOrigin::GamepadAxisEvent)
+        }
+        _ => false,
+    }
+}
 
-                if let Err(e) = ctx.gfx.end_frame() {
-                    error!("Error on GraphicsContext::end_frame(): {e:?}");
-                    eprintln!("Error on GraphicsContext::end_frame(): {e:?}");
-                    *control_flow = ControlFlow::Exit;
-                }
+/// Feeds the window events of a replayed frame through [`process_event`] and the [`EventHandler`].
+///
+/// Returns `true` if an error stopped the event loop.
+pub(crate) fn replay_window_events<S: 'static, E>(
+    ctx: &mut Context,
+    state: &mut S,
+    control_flow: &mut ControlFlow,
+    frame: &RecordedFrame,
+) -> bool
+where
+    S: EventHandler<E>,
+    E: std::fmt::Debug,
+{
+    for input in &frame.events {
+        let Some(window_event) = input.to_window_event() else {
+            continue;
+        };
+        let stopped = if let RecordedEvent::Resized { .. } = input {
+            // The frame keeps the size of the real window.
+            dispatch_window_event(ctx, state, control_flow, window_event)
+        } else {
+            inject_window_event(ctx, state, control_flow, window_event)
+        };
+        if stopped {
+            return true;
+        }
+    }
+    false
+}
 
-                // reset the mouse delta for the next frame
-                // necessary because it's calculated cumulatively each cycle
-                ctx.mouse.reset_delta();
+/// Feeds a window event that didn't come from the event loop through [`process_event`]
+/// and the [`EventHandler`].
+///
+/// Returns `true` if an error stopped the event loop.
+pub(crate) fn inject_window_event<S: 'static, E>(
+    ctx: &mut Context,
+    state: &mut S,
+    control_flow: &mut ControlFlow,
+    window_event: WindowEvent<'static>,
+) -> bool
+where
+    S: EventHandler<E>,
+    E: std::fmt::Debug,
+{
+    let window_id = ctx
+        .gfx
+        .window
+        .as_ref()
+        .map_or_else(|| WindowId::from(0), |window| window.id());
+    let mut event = Event::WindowEvent {
+        window_id,
+        event: window_event,
+    };
+    process_event(ctx, &mut event);
+    match event {
+        Event::WindowEvent { event, .. } => dispatch_window_event(ctx, state, control_flow, event),
+        _ => false,
+    }
+}
 
-                // Copy the state of the keyboard into the KeyboardContext
-                // and the mouse into the MouseContext
-                ctx.keyboard.save_keyboard_state();
-                ctx.mouse.save_mouse_state();
-            }
-            Event::RedrawRequested(_) => (),
-            Event::RedrawEventsCleared => (),
-            Event::LoopDestroyed => (),
+/// Applies the gamepad events of a replayed frame to the [`GamepadContext`](crate::input::gamepad::GamepadContext)
+/// and feeds them to the [`EventHandler`], dropping the live gamepad events.
+///
+/// Returns `true` if an error stopped the event loop.
+#[cfg(feature = "gamepad")]
+fn replay_gamepad_events<S: 'static, E>(
+    ctx: &mut Context,
+    state: &mut S,
+    control_flow: &mut ControlFlow,
+    frame: &RecordedFrame,
+) -> bool
+where
+    S: EventHandler<E>,
+    E: std::fmt::Debug,
+{
+    if let Some(gilrs) = &mut ctx.gamepad.gilrs {
+        while gilrs.next_event().is_some() {}
+    }
+    for &input in &frame.events {
+        if inject_gamepad_event(ctx, state, control_flow, input) {
+            return true;
         }
-    })
+    }
+    false
+}
+
+/// Applies a gamepad event that didn't come from the gamepad itself to the
+/// [`GamepadContext`](crate::input::gamepad::GamepadContext) and feeds it to the [`EventHandler`].
+/// Other events are ignored.
+///
+/// Returns `true` if an error stopped the event loop.
+#[cfg(feature = "gamepad")]
+pub(crate) fn inject_gamepad_event<S: 'static, E>(
+    ctx: &mut Context,
+    state: &mut S,
+    control_flow: &mut ControlFlow,
+    input: RecordedEvent,
+) -> bool
+where
+    S: EventHandler<E>,
+    E: std::fmt::Debug,
+{
+    match input {
+        RecordedEvent::GamepadButton {
+            id,
+            button,
+            pressed,
+        } => ctx.gamepad.set_button(id, button, pressed),
+        RecordedEvent::GamepadAxis { id, axis, value } => ctx.gamepad.set_axis(id, axis, value),
+        _ => return false,
+    }
+    dispatch_gamepad_event(ctx, state, control_flow, input)
 }
 
 fn catch_error<T, E, S: 'static>(
@@ -34,16 +34,21 @@
 
 use crate::{
     conf,
+    graphics::GraphicsContext,
     vfs::{self, OverlayFS, VFS},
     Context, GameError, GameResult,
 };
 use directories::ProjectDirs;
 use std::{
-    env, io,
-    io::SeekFrom,
+    env, fmt, io,
+    io::{Read, SeekFrom},
     ops::DerefMut,
     path,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::SystemTime,
 };
 
 pub use crate::vfs::OpenOptions;
@@ -58,6 +63,7 @@ pub struct Filesystem {
     zip_dir: path::PathBuf,
     user_config_dir: path::PathBuf,
     user_data_dir: path::PathBuf,
+    assets: Arc<AssetWatcher>,
 }
 
 /// This is the same as [`std::clone::Clone`] but only accessible to ggez
@@ -77,10 +83,32 @@ impl InternalClone for Filesystem {
             zip_dir: self.zip_dir.clone(),
             user_config_dir: self.user_config_dir.clone(),
             user_data_dir: self.user_data_dir.clone(),
+            assets: self.assets.clone(),
         }
     }
 }
 
+/// An asset that can be reloaded from the file it was loaded from, see
+/// [`Filesystem::set_asset_hot_reload`].
+pub(crate) trait ReloadAsset: fmt::Debug {
+    /// Replaces the contents of the asset with the new contents of its file.
+    fn reload(&self, gfx: &GraphicsContext, bytes: Vec<u8>) -> GameResult;
+}
+
+/// Keeps track of the files assets were loaded from, to reload them when they change.
+#[derive(Debug, Default)]
+struct AssetWatcher {
+    enabled: AtomicBool,
+    assets: Mutex<Vec<WatchedAsset>>,
+}
+
+#[derive(Debug)]
+struct WatchedAsset {
+    path: path::PathBuf,
+    modified: Option<SystemTime>,
+    asset: Weak<dyn ReloadAsset>,
+}
+
 /// Represents a file, either in the filesystem, or in the resources zip file,
 /// or whatever.
 #[derive(Debug)]
@@ -215,6 +243,7 @@ impl Filesystem {
             zip_dir: resources_zip_path,
             user_config_dir: user_config_path.to_path_buf(),
             user_data_dir: user_data_path.to_path_buf(),
+            assets: Arc::default(),
         };
 
         Ok(fs)
@@ -296,6 +325,93 @@ impl Filesystem {
             .unwrap_or(false)
     }
 
+    /// Returns when the file or directory at a path was last modified, if that's known.
+    pub(crate) fn modified<P: AsRef<path::Path>>(&self, path: P) -> Option<SystemTime> {
+        self.vfs().metadata(path.as_ref()).ok()?.modified()
+    }
+
+    /// Sets whether images, fonts, shaders and sounds are reloaded when their files change.
+    ///
+    /// While this is on, the files of [`Image`](crate::graphics::Image)s,
+    /// [`FontData`](crate::graphics::FontData), [`Shader`](crate::graphics::Shader)s and
+    /// `SoundData` loaded from paths are tracked, and checked at the start of every frame.
+    /// Changed images are reloaded into their existing handles, changed fonts replace the ones
+    /// added with [`add_font`](crate::graphics::GraphicsContext::add_font), changed shaders are
+    /// recompiled and picked up the next time they're set on a canvas, and changed sounds are
+    /// used the next time a source playing them starts. If a file can't be loaded (or a shader
+    /// doesn't compile), a warning is logged and the asset is left as it was.
+    ///
+    /// Only assets loaded while this is on are tracked, so turn it on before loading them.
+    pub fn set_asset_hot_reload(&self, enabled: bool) {
+        self.assets.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if assets are reloaded when their files change, see
+    /// [`Filesystem::set_asset_hot_reload`].
+    pub fn is_asset_hot_reload_enabled(&self) -> bool {
+        self.assets.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Tracks the file an asset was loaded from, if asset hot reloading is on.
+    pub(crate) fn watch_asset<P: AsRef<path::Path>>(&self, path: P, asset: Weak<dyn ReloadAsset>) {
+        if !self.is_asset_hot_reload_enabled() {
+            return;
+        }
+        let path = path.as_ref().to_path_buf();
+        let modified = self.modified(&path);
+        if let Ok(mut assets) = self.assets.assets.lock() {
+            assets.push(WatchedAsset {
+                path,
+                modified,
+                asset,
+            });
+        }
+    }
+
+    /// Reloads the tracked assets whose files have changed, returning how many were reloaded or
+    /// the first error after trying all of them.
+    pub(crate) fn reload_assets(&self, gfx: &GraphicsContext) -> GameResult<usize> {
+        let mut assets = self
+            .assets
+            .assets
+            .lock()
+            .map_err(|_| GameError::LockError)?;
+        assets.retain(|watched| watched.asset.strong_count() > 0);
+
+        let mut reloaded = 0;
+        let mut result = Ok(());
+        for watched in assets.iter_mut() {
+            // files that are gone (for now) are left alone
+            let modified = self.modified(&watched.path);
+            if modified.is_none() || modified == watched.modified {
+                continue;
+            }
+            // even if it fails to load, so it's only tried again once it changes
+            watched.modified = modified;
+
+            let Some(asset) = watched.asset.upgrade() else {
+                continue;
+            };
+            let reload = || -> GameResult {
+                let mut bytes = Vec::new();
+                let _ = self.open(&watched.path)?.read_to_end(&mut bytes)?;
+                asset.reload(gfx, bytes)
+            };
+            match reload() {
+                Ok(()) => reloaded += 1,
+                Err(e) => {
+                    if result.is_ok() {
+                        result = Err(GameError::ResourceLoadError(format!(
+                            "failed to reload {}: {e}",
+                            watched.path.display()
+                        )));
+                    }
+                }
+            }
+        }
+        result.map(|()| reloaded)
+    }
+
     /// Returns a list of all files and directories in the resource directory,
     /// in no particular order.
     ///
@@ -569,6 +685,27 @@ pub fn write_config(ctx: &Context, conf: &conf::Conf) -> GameResult {
     ctx.fs.write_config(conf)
 }
 
+/// Writes `bytes` to a file in the user directory for tests, rewriting it until its
+/// modification time changes, since filesystem timestamps can be too coarse to tell quick
+/// writes apart.
+#[cfg(test)]
+pub(crate) fn rewrite_for_tests(fs: &Filesystem, path: &str, bytes: &[u8]) {
+    use std::io::Write;
+
+    let modified = fs.modified(path);
+    // 3 seconds, more than the 2 second timestamps of FAT
+    for _ in 0..300 {
+        fs.create(path).unwrap().write_all(bytes).unwrap();
+        let now = fs.modified(path);
+        assert!(now.is_some(), "{path} has no modification time");
+        if now != modified {
+            return;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    panic!("the modification time of {path} didn't change after rewriting it for 3 seconds");
+}
+
 #[cfg(test)]
 mod tests {
     use crate::conf;
@@ -590,6 +727,7 @@ mod tests {
             zip_dir: "".into(),
             user_config_dir: "".into(),
             user_data_dir: "".into(),
+            assets: Arc::default(),
         }
     }
 
@@ -671,4 +809,71 @@ mod tests {
         // Remove the config file!
         f.delete(CONFIG_NAME).unwrap();
     }
+
+    #[test]
+    fn headless_test_asset_hot_reload() {
+        use crate::filesystem::rewrite_for_tests as write;
+        use crate::graphics::{Color, FontData, Image, ImageEncodingFormat, Text};
+        use crate::{context::headless_ctx_for_tests, Context};
+
+        fn png(ctx: &Context, width: u32, color: Color) -> Vec<u8> {
+            let path = "/headless_test_asset_hot_reload_tmp.png";
+            Image::from_color(ctx, width, 2, Some(color))
+                .encode(ctx, ImageEncodingFormat::Png, path)
+                .unwrap();
+            let mut bytes = Vec::new();
+            let _ = ctx.fs.open(path).unwrap().read_to_end(&mut bytes).unwrap();
+            ctx.fs.delete(path).unwrap();
+            bytes
+        }
+
+        let mut ctx = headless_ctx_for_tests(8, 8);
+        ctx.fs.set_asset_hot_reload(true);
+
+        let path = "/headless_test_asset_hot_reload.png";
+        write(&ctx.fs, path, &png(&ctx, 2, Color::RED));
+        let image = Image::from_path(&ctx, path).unwrap();
+        let texture = image.texture.id();
+        assert_eq!(ctx.gfx.reload_assets().unwrap(), 0);
+
+        // an image of the same size is written into the same texture
+        write(&ctx.fs, path, &png(&ctx, 2, Color::GREEN));
+        assert_eq!(ctx.gfx.reload_assets().unwrap(), 1);
+        assert_eq!(image.current().texture.id(), texture);
+        assert_eq!(image.to_pixels(&ctx).unwrap()[..4], [0, 255, 0, 255]);
+
+        // one of another size replaces it
+        write(&ctx.fs, path, &png(&ctx, 4, Color::BLUE));
+        assert_eq!(ctx.gfx.reload_assets().unwrap(), 1);
+        assert_ne!(image.current().texture.id(), texture);
+        assert_eq!((image.width(), image.height()), (4, 2));
+        assert_eq!(image.to_pixels(&ctx).unwrap()[..4], [0, 0, 255, 255]);
+
+        // a broken file is reported, and the image is left as it was
+        write(&ctx.fs, path, b"oops");
+        assert!(ctx.gfx.reload_assets().is_err());
+        assert_eq!(ctx.gfx.reload_assets().unwrap(), 0);
+        assert_eq!(image.width(), 4);
+        ctx.fs.delete(path).unwrap();
+
+        // fonts are replaced wherever they were added
+        let font = |name: &str| {
+            let mut path = path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            path.push("resources");
+            path.push(name);
+            std::fs::read(path).unwrap()
+        };
+        let path = "/headless_test_asset_hot_reload.ttf";
+        write(&ctx.fs, path, &font("LiberationMono-Regular.ttf"));
+        ctx.gfx
+            .add_font("hot", FontData::from_path(&ctx, path).unwrap());
+        let mut text = Text::new("hot reload");
+        let _ = text.set_font("hot");
+        let measured = text.measure(&ctx).unwrap();
+
+        write(&ctx.fs, path, &font("Tangerine_Regular.ttf"));
+        assert_eq!(ctx.gfx.reload_assets().unwrap(), 1);
+        assert_ne!(text.measure(&ctx).unwrap(), measured);
+        ctx.fs.delete(path).unwrap();
+    }
 }
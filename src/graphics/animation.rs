@@ -0,0 +1,607 @@
+use super::{Canvas, DrawParam, Drawable, GraphicsContext, Rect, SpriteFrame, SpriteSheet};
+use crate::{context::Has, timer::TimeContext, GameError, GameResult};
+use std::time::Duration;
+
+/// How an [`Animation`] continues once it reaches its last frame.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AnimationMode {
+    /// Starts over from the first frame.
+    Loop,
+    /// Plays backwards to the first frame, then forwards again, and so on.
+    PingPong,
+    /// Stops on the last frame.
+    Once,
+}
+
+/// A single frame of an [`Animation`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnimationFrame {
+    /// Index of the frame in [`SpriteSheet::frames`].
+    pub index: usize,
+    /// How long the frame is shown for.
+    pub duration: Duration,
+    /// Names of the events fired when the frame is reached, see [`AnimationEvent::Event`].
+    pub events: Vec<String>,
+}
+
+/// A sequence of [`SpriteSheet`] frames, each with its own duration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Animation {
+    frames: Vec<AnimationFrame>,
+    mode: AnimationMode,
+}
+
+impl Animation {
+    /// Creates a new animation out of pairs of frame indices (into [`SpriteSheet::frames`])
+    /// and durations.
+    pub fn new(frames: impl IntoIterator<Item = (usize, Duration)>, mode: AnimationMode) -> Self {
+        Animation {
+            frames: frames
+                .into_iter()
+                .map(|(index, duration)| AnimationFrame {
+                    index,
+                    duration,
+                    events: Vec::new(),
+                })
+                .collect(),
+            mode,
+        }
+    }
+
+    /// Creates a new animation out of the frames of `sheet` with the given names.
+    ///
+    /// Each frame is shown for the duration stored in the sheet, or for `default_duration`
+    /// if the sheet doesn't specify one.
+    pub fn from_names<'a>(
+        sheet: &SpriteSheet,
+        names: impl IntoIterator<Item = &'a str>,
+        default_duration: Duration,
+        mode: AnimationMode,
+    ) -> GameResult<Self> {
+        let frames = names
+            .into_iter()
+            .map(|name| {
+                let index = sheet.frame_index(name).ok_or_else(|| {
+                    GameError::ResourceLoadError(format!(
+                        "sprite sheet has no frame named {name:?}"
+                    ))
+                })?;
+                let duration = sheet.frames()[index].duration.unwrap_or(default_duration);
+                Ok((index, duration))
+            })
+            .collect::<GameResult<Vec<_>>>()?;
+        Ok(Animation::new(frames, mode))
+    }
+
+    /// Creates a new animation out of the frames of a tag of `sheet` (see [`SpriteSheet::tag`]).
+    ///
+    /// Ping-pong tags use [`AnimationMode::PingPong`], all others [`AnimationMode::Loop`].
+    /// Each frame is shown for the duration stored in the sheet, or for `default_duration`
+    /// if the sheet doesn't specify one.
+    pub fn from_tag(
+        sheet: &SpriteSheet,
+        tag: &str,
+        default_duration: Duration,
+    ) -> GameResult<Self> {
+        let tag = sheet.tag(tag).ok_or_else(|| {
+            GameError::ResourceLoadError(format!("sprite sheet has no tag named {tag:?}"))
+        })?;
+        let frames = tag.frames.iter().map(|&index| {
+            let duration = sheet.frames()[index].duration.unwrap_or(default_duration);
+            (index, duration)
+        });
+        let mode = if tag.ping_pong {
+            AnimationMode::PingPong
+        } else {
+            AnimationMode::Loop
+        };
+        Ok(Animation::new(frames, mode))
+    }
+
+    /// Attaches an event to the frame at `frame` (an index into [`Animation::frames`]), which
+    /// is fired every time that frame is reached. Does nothing if `frame` is out of bounds.
+    #[must_use]
+    pub fn with_event(mut self, frame: usize, name: impl Into<String>) -> Self {
+        if let Some(frame) = self.frames.get_mut(frame) {
+            frame.events.push(name.into());
+        }
+        self
+    }
+
+    /// Sets how the animation continues once it reaches its last frame.
+    #[must_use]
+    pub fn with_mode(mut self, mode: AnimationMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Returns the frames of the animation.
+    #[inline]
+    pub fn frames(&self) -> &[AnimationFrame] {
+        &self.frames
+    }
+
+    /// Returns how the animation continues once it reaches its last frame.
+    #[inline]
+    pub fn mode(&self) -> AnimationMode {
+        self.mode
+    }
+
+    /// Returns the time it takes to play every frame once.
+    pub fn duration(&self) -> Duration {
+        self.frames.iter().map(|frame| frame.duration).sum()
+    }
+}
+
+/// Something that happened while an [`AnimatedSprite`] was updated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnimationEvent {
+    /// The animation moved on to the frame at this index of [`Animation::frames`].
+    FrameChanged(usize),
+    /// A frame with an event attached to it was reached, see [`Animation::with_event`].
+    Event(String),
+    /// The animation went back to its first frame, either by looping or by ping-ponging.
+    Looped,
+    /// The animation reached its last frame and stopped, see [`AnimationMode::Once`].
+    Finished,
+}
+
+/// Steps through the frames of an [`Animation`], independently of how they are drawn.
+///
+/// [`AnimatedSprite`] pairs one of these with a [`SpriteSheet`] to draw the current frame.
+#[derive(Debug, Clone)]
+pub struct AnimationPlayer {
+    animation: Animation,
+    current: usize,
+    elapsed: Duration,
+    forward: bool,
+    playing: bool,
+    finished: bool,
+    speed: f32,
+    events: Vec<AnimationEvent>,
+}
+
+impl AnimationPlayer {
+    /// Creates a new player, playing `animation` from its first frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the animation has no frames.
+    pub fn new(animation: Animation) -> Self {
+        assert!(
+            !animation.frames.is_empty(),
+            "an animation needs at least one frame"
+        );
+        AnimationPlayer {
+            animation,
+            current: 0,
+            elapsed: Duration::ZERO,
+            forward: true,
+            playing: true,
+            finished: false,
+            speed: 1.0,
+            events: Vec::new(),
+        }
+    }
+
+    /// Advances the animation by the length of the last frame, see [`TimeContext::delta`].
+    pub fn update(&mut self, time: &TimeContext) {
+        self.advance(time.delta());
+    }
+
+    /// Advances the animation by `dt`.
+    ///
+    /// The events that happened are available through [`AnimationPlayer::events`] until the
+    /// next call.
+    pub fn advance(&mut self, dt: Duration) {
+        self.events.clear();
+        if !self.playing || self.finished {
+            return;
+        }
+
+        // Scaling by 1.0 would still round through floating point.
+        let dt = if self.speed == 1.0 {
+            dt
+        } else {
+            // `Duration::mul_f32` panics on overflow
+            Duration::try_from_secs_f32(dt.as_secs_f32() * self.speed).unwrap_or(Duration::MAX)
+        };
+        self.elapsed = self.elapsed.saturating_add(dt);
+        // A whole cycle ends where it started, so there's no point in stepping through
+        // (and reporting the events of) more than one to catch up with a huge delta.
+        let cycle = self.cycle_duration();
+        if !cycle.is_zero() && self.elapsed >= cycle {
+            self.elapsed =
+                Duration::from_nanos((self.elapsed.as_nanos() % cycle.as_nanos()) as u64);
+        }
+        // With only zero-length frames, this would otherwise spin forever.
+        let max_steps = self.animation.frames.len() * 2;
+        let mut steps = 0;
+        while self.elapsed >= self.animation.frames[self.current].duration {
+            if steps == max_steps {
+                self.elapsed = Duration::ZERO;
+                break;
+            }
+            steps += 1;
+
+            self.elapsed -= self.animation.frames[self.current].duration;
+            self.step();
+            if self.finished {
+                self.elapsed = Duration::ZERO;
+                break;
+            }
+        }
+    }
+
+    /// Returns how long it takes to get back to the same frame, going the same way, or zero
+    /// for animations that don't repeat.
+    fn cycle_duration(&self) -> Duration {
+        let frames = &self.animation.frames;
+        let total = frames.iter().map(|frame| frame.duration).sum::<Duration>();
+        match self.animation.mode {
+            AnimationMode::Loop => total,
+            // the first and last frames are only shown once per round trip
+            AnimationMode::PingPong if frames.len() > 1 => {
+                total * 2 - frames[0].duration - frames[frames.len() - 1].duration
+            }
+            AnimationMode::PingPong => total,
+            AnimationMode::Once => Duration::ZERO,
+        }
+    }
+
+    fn step(&mut self) {
+        let last = self.animation.frames.len() - 1;
+        match self.animation.mode {
+            AnimationMode::Loop => {
+                if self.current < last {
+                    self.current += 1;
+                } else {
+                    self.current = 0;
+                    self.events.push(AnimationEvent::Looped);
+                }
+            }
+            AnimationMode::PingPong if last == 0 => {
+                self.events.push(AnimationEvent::Looped);
+            }
+            AnimationMode::PingPong => {
+                if self.forward {
+                    if self.current < last {
+                        self.current += 1;
+                    } else {
+                        self.forward = false;
+                        self.current -= 1;
+                    }
+                } else if self.current > 0 {
+                    self.current -= 1;
+                } else {
+                    self.forward = true;
+                    self.current += 1;
+                }
+                if self.current == 0 {
+                    self.events.push(AnimationEvent::Looped);
+                }
+            }
+            AnimationMode::Once => {
+                if self.current < last {
+                    self.current += 1;
+                } else {
+                    self.finished = true;
+                    self.events.push(AnimationEvent::Finished);
+                    return;
+                }
+            }
+        }
+
+        self.events.push(AnimationEvent::FrameChanged(self.current));
+        self.events.extend(
+            self.animation.frames[self.current]
+                .events
+                .iter()
+                .cloned()
+                .map(AnimationEvent::Event),
+        );
+    }
+
+    /// Returns the events that happened during the last [`AnimationPlayer::update`] or
+    /// [`AnimationPlayer::advance`].
+    #[inline]
+    pub fn events(&self) -> &[AnimationEvent] {
+        &self.events
+    }
+
+    /// Resumes playback.
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    /// Pauses playback; the current frame stays where it is.
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Returns whether the animation is playing, i.e. not paused.
+    /// A finished animation still counts as playing.
+    #[inline]
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Returns whether an [`AnimationMode::Once`] animation has reached its end.
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Starts the animation over from its first frame.
+    pub fn restart(&mut self) {
+        self.current = 0;
+        self.elapsed = Duration::ZERO;
+        self.forward = true;
+        self.finished = false;
+    }
+
+    /// Switches to another animation and starts it from its first frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the animation has no frames.
+    pub fn set_animation(&mut self, animation: Animation) {
+        assert!(
+            !animation.frames.is_empty(),
+            "an animation needs at least one frame"
+        );
+        self.animation = animation;
+        self.restart();
+    }
+
+    /// Returns the animation being played.
+    #[inline]
+    pub fn animation(&self) -> &Animation {
+        &self.animation
+    }
+
+    /// Returns the index of the current frame in [`Animation::frames`].
+    #[inline]
+    pub fn current_index(&self) -> usize {
+        self.current
+    }
+
+    /// Jumps to the frame at `index` in [`Animation::frames`], clamped to the last frame.
+    pub fn set_current_index(&mut self, index: usize) {
+        self.current = index.min(self.animation.frames.len() - 1);
+        self.elapsed = Duration::ZERO;
+    }
+
+    /// Returns the current frame.
+    #[inline]
+    pub fn current_frame(&self) -> &AnimationFrame {
+        &self.animation.frames[self.current]
+    }
+
+    /// Returns the playback speed multiplier.
+    #[inline]
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Sets the playback speed multiplier, e.g. `2.0` plays twice as fast.
+    /// Negative and NaN values are treated as `0.0`, and infinite ones are ignored.
+    pub fn set_speed(&mut self, speed: f32) {
+        if speed == f32::INFINITY {
+            warn!("Ignoring an infinite animation speed");
+            return;
+        }
+        self.speed = speed.max(0.0);
+    }
+}
+
+/// Plays an [`Animation`] out of a [`SpriteSheet`].
+///
+/// Call [`AnimatedSprite::update`] once per frame to advance it, and draw it like any other
+/// [`Drawable`]; see [`SpriteSheet::frame_param`] for how the `DrawParam` is applied.
+#[derive(Debug, Clone)]
+pub struct AnimatedSprite {
+    sheet: SpriteSheet,
+    player: AnimationPlayer,
+}
+
+impl AnimatedSprite {
+    /// Creates a new sprite playing `animation` from its first frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the animation has no frames, or refers to frames not in `sheet`.
+    pub fn new(sheet: SpriteSheet, animation: Animation) -> Self {
+        Self::check_frames(&sheet, &animation);
+        AnimatedSprite {
+            sheet,
+            player: AnimationPlayer::new(animation),
+        }
+    }
+
+    fn check_frames(sheet: &SpriteSheet, animation: &Animation) {
+        assert!(
+            animation
+                .frames
+                .iter()
+                .all(|frame| frame.index < sheet.frames().len()),
+            "animation refers to frames not in the sprite sheet"
+        );
+    }
+
+    /// Advances the animation by the length of the last frame, see [`TimeContext::delta`].
+    pub fn update(&mut self, time: &TimeContext) {
+        self.player.update(time);
+    }
+
+    /// Advances the animation by `dt`.
+    pub fn advance(&mut self, dt: Duration) {
+        self.player.advance(dt);
+    }
+
+    /// Returns the events that happened during the last update, see [`AnimationPlayer::events`].
+    #[inline]
+    pub fn events(&self) -> &[AnimationEvent] {
+        self.player.events()
+    }
+
+    /// Switches to another animation and starts it from its first frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the animation has no frames, or refers to frames not in the sprite sheet.
+    pub fn set_animation(&mut self, animation: Animation) {
+        Self::check_frames(&self.sheet, &animation);
+        self.player.set_animation(animation);
+    }
+
+    /// Returns the player stepping through the animation.
+    #[inline]
+    pub fn player(&self) -> &AnimationPlayer {
+        &self.player
+    }
+
+    /// Returns the player stepping through the animation, e.g. to pause it.
+    #[inline]
+    pub fn player_mut(&mut self) -> &mut AnimationPlayer {
+        &mut self.player
+    }
+
+    /// Returns the sprite sheet the frames are drawn from.
+    #[inline]
+    pub fn sheet(&self) -> &SpriteSheet {
+        &self.sheet
+    }
+
+    /// Returns the sprite sheet frame currently shown.
+    pub fn current_frame(&self) -> &SpriteFrame {
+        &self.sheet.frames()[self.player.current_frame().index]
+    }
+}
+
+impl Drawable for AnimatedSprite {
+    fn draw(&self, canvas: &mut Canvas, param: impl Into<DrawParam>) {
+        let index = self.player.current_frame().index;
+        canvas.draw(&self.sheet.image(), self.sheet.frame_param(index, param));
+    }
+
+    fn dimensions(&self, _gfx: &impl Has<GraphicsContext>) -> Option<Rect> {
+        let frame = self.current_frame();
+        Some(Rect::new(
+            -frame.pivot.x * frame.source_size.x,
+            -frame.pivot.y * frame.source_size.y,
+            frame.source_size.x,
+            frame.source_size.y,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    fn player(mode: AnimationMode) -> AnimationPlayer {
+        AnimationPlayer::new(Animation::new(
+            [(0, ms(100)), (1, ms(50)), (2, ms(100))],
+            mode,
+        ))
+    }
+
+    #[test]
+    fn headless_test_animation_loop() {
+        let mut player = player(AnimationMode::Loop);
+        player.advance(ms(99));
+        assert_eq!(player.current_index(), 0);
+        assert!(player.events().is_empty());
+        player.advance(ms(1));
+        assert_eq!(player.events(), [AnimationEvent::FrameChanged(1)]);
+        // per-frame durations: 50ms on frame 1, then 100ms on frame 2
+        player.advance(ms(150));
+        assert_eq!(player.current_index(), 0);
+        assert_eq!(
+            player.events(),
+            [
+                AnimationEvent::FrameChanged(2),
+                AnimationEvent::Looped,
+                AnimationEvent::FrameChanged(0)
+            ]
+        );
+    }
+
+    #[test]
+    fn headless_test_animation_ping_pong() {
+        let mut player = player(AnimationMode::PingPong);
+        let mut visited = vec![player.current_index()];
+        for _ in 0..6 {
+            player.advance(player.current_frame().duration);
+            visited.push(player.current_index());
+        }
+        assert_eq!(visited, [0, 1, 2, 1, 0, 1, 2]);
+    }
+
+    #[test]
+    fn headless_test_animation_large_delta() {
+        // a loop takes 250ms, so 10 of them and 120ms more end 20ms into frame 1
+        let mut looping = player(AnimationMode::Loop);
+        looping.advance(ms(2620));
+        assert_eq!(looping.current_index(), 1);
+        looping.advance(ms(29));
+        assert_eq!(looping.current_index(), 1);
+        looping.advance(ms(1));
+        assert_eq!(looping.current_index(), 2);
+
+        // a round trip takes 300ms: 0, 1, 2, 1
+        let mut ping_pong = player(AnimationMode::PingPong);
+        ping_pong.advance(ms(3000 + 260));
+        assert_eq!(ping_pong.current_index(), 1);
+        ping_pong.advance(ms(40));
+        assert_eq!(ping_pong.current_index(), 0);
+
+        // speeds too fast for a `Duration` saturate instead of panicking
+        looping.set_speed(f32::INFINITY);
+        assert_eq!(looping.speed(), 1.0);
+        looping.set_speed(f32::MAX);
+        looping.advance(ms(16));
+        assert!(!looping.events().is_empty());
+    }
+
+    #[test]
+    fn headless_test_animation_once_and_events() {
+        let animation = Animation::new([(0, ms(10)), (1, ms(10))], AnimationMode::Once)
+            .with_event(1, "footstep");
+        let mut player = AnimationPlayer::new(animation);
+        player.advance(ms(1000));
+        assert!(player.is_finished());
+        assert_eq!(player.current_index(), 1);
+        assert_eq!(
+            player.events(),
+            [
+                AnimationEvent::FrameChanged(1),
+                AnimationEvent::Event("footstep".to_string()),
+                AnimationEvent::Finished
+            ]
+        );
+        player.advance(ms(1000));
+        assert!(player.events().is_empty());
+
+        player.restart();
+        player.pause();
+        player.advance(ms(1000));
+        assert_eq!(player.current_index(), 0);
+    }
+
+    #[test]
+    fn headless_test_animation_zero_durations() {
+        let mut player = AnimationPlayer::new(Animation::new(
+            [(0, Duration::ZERO), (1, Duration::ZERO)],
+            AnimationMode::Loop,
+        ));
+        // must not spin forever
+        player.advance(ms(16));
+        assert_eq!(player.events().len(), 6);
+    }
+}
@@ -0,0 +1,375 @@
+use super::{internal_canvas::screen_to_mat, Canvas, Rect};
+use crate::{timer::TimeContext, GameResult};
+use std::time::Duration;
+
+/// A 2D camera looking at a scrolling, zooming and rotating world.
+///
+/// The camera maps world coordinates onto its viewport, a rectangle in the pixel coordinates of
+/// the canvas it's applied to (by default the whole canvas). Apply it with [`Camera2D::apply`]
+/// before drawing the world, and use [`Camera2D::screen_to_world`] to find out what the mouse
+/// is pointing at (see [`MouseContext::position`](crate::input::mouse::MouseContext::position)).
+///
+/// Following a target, clamping to the world bounds and screen shake are updated by
+/// [`Camera2D::update`], which should be called once per frame.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Camera2D {
+    position: glam::Vec2,
+    zoom: f32,
+    rotation: f32,
+    viewport: Rect,
+    bounds: Option<Rect>,
+
+    target: Option<glam::Vec2>,
+    follow_speed: f32,
+
+    shake_intensity: f32,
+    shake_duration: Duration,
+    shake_elapsed: Duration,
+    shake_offset: glam::Vec2,
+}
+
+impl Camera2D {
+    /// The smallest zoom factor, which [`Camera2D::set_zoom`] clamps to.
+    pub const MIN_ZOOM: f32 = 0.001;
+
+    /// Creates a camera looking at the origin with a zoom of `1.0` through the given viewport,
+    /// which is usually the whole screen, e.g. `Rect::new(0., 0., width, height)`.
+    pub fn new(viewport: Rect) -> Self {
+        Camera2D {
+            position: glam::Vec2::ZERO,
+            zoom: 1.0,
+            rotation: 0.0,
+            viewport,
+            bounds: None,
+            target: None,
+            follow_speed: 0.0,
+            shake_intensity: 0.0,
+            shake_duration: Duration::ZERO,
+            shake_elapsed: Duration::ZERO,
+            shake_offset: glam::Vec2::ZERO,
+        }
+    }
+
+    /// Returns the point of the world shown at the center of the viewport.
+    #[inline]
+    pub fn position(&self) -> mint::Point2<f32> {
+        self.position.into()
+    }
+
+    /// Moves the camera so the given point of the world is shown at the center of the viewport.
+    pub fn set_position(&mut self, position: impl Into<mint::Point2<f32>>) {
+        self.position = glam::Vec2::from(position.into());
+        self.clamp_to_bounds();
+    }
+
+    /// Returns the zoom factor; `2.0` makes everything twice as big.
+    #[inline]
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Sets the zoom factor; `2.0` makes everything twice as big.
+    ///
+    /// Zooms smaller than [`Camera2D::MIN_ZOOM`] are clamped to it, and NaN is ignored.
+    pub fn set_zoom(&mut self, zoom: f32) {
+        if zoom.is_nan() {
+            warn!("Ignoring a NaN camera zoom");
+            return;
+        }
+        self.zoom = zoom.max(Self::MIN_ZOOM);
+        self.clamp_to_bounds();
+    }
+
+    /// Returns the rotation of the camera, in radians.
+    #[inline]
+    pub fn rotation(&self) -> f32 {
+        self.rotation
+    }
+
+    /// Sets the rotation of the camera, in radians. Rotating the camera clockwise makes the
+    /// world appear rotated counter-clockwise.
+    ///
+    /// NaN and infinite rotations are ignored.
+    pub fn set_rotation(&mut self, rotation: f32) {
+        if !rotation.is_finite() {
+            warn!("Ignoring a non-finite camera rotation");
+            return;
+        }
+        self.rotation = rotation;
+        self.clamp_to_bounds();
+    }
+
+    /// Returns the viewport, in canvas pixel coordinates.
+    #[inline]
+    pub fn viewport(&self) -> Rect {
+        self.viewport
+    }
+
+    /// Sets the viewport, in canvas pixel coordinates. Should be updated when the window is resized.
+    pub fn set_viewport(&mut self, viewport: Rect) {
+        self.viewport = viewport;
+        self.clamp_to_bounds();
+    }
+
+    /// Returns the area of the world the camera is kept inside of, if any.
+    #[inline]
+    pub fn bounds(&self) -> Option<Rect> {
+        self.bounds
+    }
+
+    /// Keeps the camera from showing anything outside of the given area of the world.
+    /// If the area is smaller than what the camera shows, it's kept centered instead.
+    ///
+    /// Bounds with NaN coordinates are ignored.
+    pub fn set_bounds(&mut self, bounds: impl Into<Option<Rect>>) {
+        let bounds = bounds.into();
+        if bounds.is_some_and(|b| [b.x, b.y, b.w, b.h].iter().any(|v| v.is_nan())) {
+            warn!("Ignoring NaN camera bounds");
+            return;
+        }
+        self.bounds = bounds;
+        self.clamp_to_bounds();
+    }
+
+    /// Makes the camera follow a point of the world during [`Camera2D::update`], or stop
+    /// following if `None`.
+    pub fn set_target(&mut self, target: impl Into<Option<mint::Point2<f32>>>) {
+        self.target = target.into().map(glam::Vec2::from);
+    }
+
+    /// Returns the point of the world the camera follows, if any.
+    #[inline]
+    pub fn target(&self) -> Option<mint::Point2<f32>> {
+        self.target.map(Into::into)
+    }
+
+    /// Sets how quickly the camera catches up with its target. Each second, it covers all but
+    /// `e^-speed` of the remaining distance. `0.0` (the default) snaps right onto the target.
+    pub fn set_follow_speed(&mut self, speed: f32) {
+        self.follow_speed = speed.max(0.0);
+    }
+
+    /// Returns how quickly the camera catches up with its target, see [`Camera2D::set_follow_speed`].
+    #[inline]
+    pub fn follow_speed(&self) -> f32 {
+        self.follow_speed
+    }
+
+    /// Shakes the camera by up to `intensity` pixels, fading out over `duration`.
+    ///
+    /// Replaces any shake still in progress, unless that one is stronger.
+    pub fn shake(&mut self, intensity: f32, duration: Duration) {
+        if intensity >= self.remaining_shake() {
+            self.shake_intensity = intensity;
+            self.shake_duration = duration;
+            self.shake_elapsed = Duration::ZERO;
+        }
+    }
+
+    fn remaining_shake(&self) -> f32 {
+        if self.shake_elapsed >= self.shake_duration {
+            return 0.0;
+        }
+        let left = 1.0 - self.shake_elapsed.as_secs_f32() / self.shake_duration.as_secs_f32();
+        self.shake_intensity * left * left
+    }
+
+    /// Follows the target and shakes the camera, by the length of the last frame
+    /// (see [`TimeContext::delta`]).
+    pub fn update(&mut self, time: &TimeContext) {
+        self.advance(time.delta());
+    }
+
+    /// Follows the target and shakes the camera, by `dt`.
+    pub fn advance(&mut self, dt: Duration) {
+        if let Some(target) = self.target {
+            self.position = if self.follow_speed == 0.0 {
+                target
+            } else {
+                let t = 1.0 - (-self.follow_speed * dt.as_secs_f32()).exp();
+                self.position.lerp(target, t)
+            };
+        }
+        self.clamp_to_bounds();
+
+        self.shake_elapsed = (self.shake_elapsed + dt).min(self.shake_duration);
+        let intensity = self.remaining_shake();
+        // Cheap, smooth and deterministic noise is plenty for a shake.
+        let t = self.shake_elapsed.as_secs_f32();
+        self.shake_offset = intensity
+            * glam::Vec2::new(
+                (t * 47.0).sin() * (t * 23.0).cos(),
+                (t * 53.0).cos() * (t * 31.0).sin(),
+            );
+    }
+
+    fn clamp_to_bounds(&mut self) {
+        let Some(bounds) = self.bounds else {
+            return;
+        };
+        let half = glam::Vec2::from(self.half_extents());
+        let clamp = |pos: f32, min: f32, max: f32, half: f32| {
+            let (low, high) = (min + half, max - half);
+            if !(low.is_finite() && high.is_finite()) {
+                // Unbounded, or a NaN viewport; `f32::clamp` panics on NaN.
+                pos
+            } else if low >= high {
+                (min + max) / 2.0
+            } else {
+                pos.clamp(low, high)
+            }
+        };
+        self.position = glam::Vec2::new(
+            clamp(self.position.x, bounds.left(), bounds.right(), half.x),
+            clamp(self.position.y, bounds.top(), bounds.bottom(), half.y),
+        );
+    }
+
+    /// Half the size of the (axis-aligned) area of the world that's visible, ignoring shake.
+    fn half_extents(&self) -> mint::Vector2<f32> {
+        let mut rect = Rect::new(
+            -self.viewport.w / 2.0,
+            -self.viewport.h / 2.0,
+            self.viewport.w,
+            self.viewport.h,
+        );
+        rect.rotate(self.rotation);
+        mint::Vector2 {
+            x: rect.w / 2.0 / self.zoom,
+            y: rect.h / 2.0 / self.zoom,
+        }
+    }
+
+    /// Returns the matrix transforming world coordinates into canvas pixel coordinates.
+    pub fn view_matrix(&self) -> mint::ColumnMatrix4<f32> {
+        self.view().into()
+    }
+
+    fn view(&self) -> glam::Mat4 {
+        let center = glam::Vec2::from(self.viewport.center()) + self.shake_offset;
+        glam::Mat4::from_translation(center.extend(0.))
+            * glam::Mat4::from_rotation_z(-self.rotation)
+            * glam::Mat4::from_scale(glam::Vec3::new(self.zoom, self.zoom, 1.))
+            * glam::Mat4::from_translation((-self.position).extend(0.))
+    }
+
+    /// Converts a point in canvas pixel coordinates (e.g. the mouse position) into world coordinates.
+    pub fn screen_to_world(&self, point: impl Into<mint::Point2<f32>>) -> mint::Point2<f32> {
+        let point = glam::Vec2::from(point.into());
+        self.view()
+            .inverse()
+            .transform_point3(point.extend(0.))
+            .truncate()
+            .into()
+    }
+
+    /// Converts a point in world coordinates into canvas pixel coordinates.
+    pub fn world_to_screen(&self, point: impl Into<mint::Point2<f32>>) -> mint::Point2<f32> {
+        let point = glam::Vec2::from(point.into());
+        self.view()
+            .transform_point3(point.extend(0.))
+            .truncate()
+            .into()
+    }
+
+    /// Returns the (axis-aligned) area of the world that's currently visible, e.g. for culling.
+    pub fn visible_rect(&self) -> Rect {
+        let inverse = self.view().inverse();
+        let Rect { x, y, w, h } = self.viewport;
+        let corners = [(x, y), (x + w, y), (x, y + h), (x + w, y + h)].map(|(x, y)| {
+            inverse
+                .transform_point3(glam::Vec3::new(x, y, 0.))
+                .truncate()
+        });
+        let min = corners.iter().fold(corners[0], |min, c| min.min(*c));
+        let max = corners.iter().fold(corners[0], |max, c| max.max(*c));
+        Rect::new(min.x, min.y, max.x - min.x, max.y - min.y)
+    }
+
+    /// Sets the projection of `canvas` so that everything drawn afterwards is seen through this
+    /// camera, and restricts drawing to the viewport with a scissor rectangle if it doesn't
+    /// cover the whole canvas.
+    ///
+    /// This overrides any projection previously set with [`Canvas::set_projection`] or
+    /// [`Canvas::set_screen_coordinates`]. To draw a HUD on top afterwards, restore the
+    /// default with `canvas.set_screen_coordinates(Rect::new(0., 0., width, height))`.
+    pub fn apply(&self, canvas: &mut Canvas) -> GameResult {
+        let (width, height) = canvas.target_size();
+        let screen = Rect::new(0., 0., width as f32, height as f32);
+        canvas.set_projection(screen_to_mat(screen) * self.view());
+        if self.viewport == screen {
+            canvas.set_default_scissor_rect();
+            Ok(())
+        } else {
+            canvas.set_scissor_rect(self.viewport)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: mint::Point2<f32>, b: [f32; 2]) {
+        assert!(
+            (a.x - b[0]).abs() < 1e-3 && (a.y - b[1]).abs() < 1e-3,
+            "{a:?} != {b:?}"
+        );
+    }
+
+    #[test]
+    fn headless_test_camera_conversions() {
+        let mut camera = Camera2D::new(Rect::new(0., 0., 800., 600.));
+        assert_near(camera.world_to_screen([0., 0.]), [400., 300.]);
+
+        camera.set_position([100., 50.]);
+        camera.set_zoom(2.0);
+        assert_near(camera.world_to_screen([110., 50.]), [420., 300.]);
+        assert_near(camera.screen_to_world([0., 0.]), [-100., -100.]);
+        assert_eq!(camera.visible_rect(), Rect::new(-100., -100., 400., 300.));
+
+        // e.g. a scroll wheel zooming out too far
+        camera.set_zoom(-1.0);
+        assert_eq!(camera.zoom(), Camera2D::MIN_ZOOM);
+        camera.set_zoom(f32::NAN);
+        assert_eq!(camera.zoom(), Camera2D::MIN_ZOOM);
+        camera.set_zoom(2.0);
+
+        camera.set_rotation(f32::NAN);
+        assert_eq!(camera.rotation(), 0.0);
+        camera.set_rotation(std::f32::consts::FRAC_PI_2);
+        let p = camera.world_to_screen([110., 50.]);
+        assert_near(camera.screen_to_world(p), [110., 50.]);
+        assert_near(p, [400., 280.]);
+    }
+
+    #[test]
+    fn headless_test_camera_follow_bounds_shake() {
+        let mut camera = Camera2D::new(Rect::new(0., 0., 100., 100.));
+        camera.set_bounds(Rect::new(0., 0., 1000., 200.));
+        // clamped so the view stays inside the bounds
+        assert_near(camera.position(), [50., 50.]);
+        camera.set_bounds(Rect::new(f32::NAN, 0., 1000., 200.));
+        assert_eq!(camera.bounds(), Some(Rect::new(0., 0., 1000., 200.)));
+        camera.set_bounds(Rect::new(0., 0., f32::INFINITY, 200.));
+        camera.set_position([500., 0.]);
+        assert_near(camera.position(), [500., 50.]);
+        camera.set_bounds(Rect::new(0., 0., 1000., 200.));
+        camera.set_position([50., 50.]);
+
+        camera.set_target(mint::Point2 { x: 500., y: 100. });
+        camera.set_follow_speed(1.0);
+        camera.advance(Duration::from_secs(1));
+        let t = 1. - (-1f32).exp();
+        assert_near(camera.position(), [50. + 450. * t, 50. + 50. * t]);
+        camera.set_follow_speed(0.0);
+        camera.advance(Duration::ZERO);
+        assert_near(camera.position(), [500., 100.]);
+
+        camera.shake(10., Duration::from_millis(500));
+        camera.advance(Duration::from_millis(100));
+        assert_ne!(camera.world_to_screen([500., 100.]), [50., 50.].into());
+        camera.advance(Duration::from_millis(400));
+        assert_near(camera.world_to_screen([500., 100.]), [50., 50.]);
+    }
+}
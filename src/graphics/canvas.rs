@@ -6,10 +6,11 @@ use crate::{
 };
 
 use super::{
-    gpu::arc::{ArcBindGroup, ArcBindGroupLayout},
-    internal_canvas::{screen_to_mat, InstanceArrayView, InternalCanvas},
-    BlendMode, Color, DrawParam, Drawable, GraphicsContext, Image, InstanceArray, Mesh, Rect,
-    Sampler, ScreenImage, Shader, ShaderParams, Text, WgpuContext, ZIndex,
+    gpu::arc::{ArcBindGroup, ArcBindGroupLayout, ArcTextureView},
+    internal_canvas::{screen_to_mat, InstanceArrayView, InternalCanvas, MeshView, BATCH_CAPACITY},
+    BlendMode, Color, DebugDraw, DrawParam, Drawable, GraphicsContext, Image, InstanceArray, Mesh,
+    MeshVertex, Rect, Sampler, ScreenImage, Shader, ShaderParams, Text, Vertex, WgpuContext,
+    ZIndex,
 };
 use std::{collections::BTreeMap, sync::Arc};
 
@@ -19,7 +20,9 @@ use std::{collections::BTreeMap, sync::Arc};
 /// or they can draw directly to the screen.
 ///
 /// Canvases are also where you can bind your own custom shaders and samplers to use while drawing.
-/// Canvases *do not* automatically batch draws. To used batched (instanced) drawing, refer to [`InstanceArray`].
+/// Consecutive draws of the same image or mesh, with the same shader, sampler, blend mode and scissor
+/// rectangle, are automatically batched into a single instanced draw call (unless a custom vertex
+/// shader is set). To control batching yourself, or to reuse the instances between frames, refer to [`InstanceArray`].
 // note:
 //   Canvas does not draw anything itself. It is merely a state-tracking and draw-reordering wrapper around InternalCanvas, which does the actual
 // drawing.
@@ -36,6 +39,8 @@ pub struct Canvas {
     resolve: Option<Image>,
     clear: Option<Color>,
 
+    debug: DebugDraw,
+
     // This will be removed after queue_text and draw_queued_text have been removed.
     pub(crate) queued_texts: Vec<(Text, mint::Point2<f32>, Option<Color>)>,
 }
@@ -131,6 +136,7 @@ impl Canvas {
             premul_text: true,
             projection: glam::Mat4::IDENTITY.into(),
             scissor_rect: (0, 0, target.width(), target.height()),
+            images: Vec::new(),
         };
 
         let screen = Rect {
@@ -152,6 +158,8 @@ impl Canvas {
             resolve,
             clear,
 
+            debug: DebugDraw::default(),
+
             queued_texts: Vec::new(),
         };
 
@@ -161,9 +169,13 @@ impl Canvas {
     }
 
     /// Sets the shader to use when drawing meshes.
+    ///
+    /// A shader rebuilt by
+    /// [asset hot reloading](crate::filesystem::Filesystem::set_asset_hot_reload) is picked up
+    /// the next time it's set.
     #[inline]
     pub fn set_shader(&mut self, shader: &Shader) {
-        self.state.shader = shader.clone();
+        self.state.shader = shader.current();
     }
 
     /// Returns the current shader being used when drawing meshes.
@@ -184,10 +196,23 @@ impl Canvas {
         ));
     }
 
+    #[inline]
+    pub(crate) fn raw_shader_params(&self) -> Option<(ArcBindGroup, ArcBindGroupLayout, u32)> {
+        self.state.params.clone()
+    }
+
+    #[inline]
+    pub(crate) fn set_raw_shader_params(
+        &mut self,
+        params: Option<(ArcBindGroup, ArcBindGroupLayout, u32)>,
+    ) {
+        self.state.params = params;
+    }
+
     /// Sets the shader to use when drawing text.
     #[inline]
     pub fn set_text_shader(&mut self, shader: Shader) {
-        self.state.text_shader = shader;
+        self.state.text_shader = shader.current();
     }
 
     /// Returns the current text shader being used when drawing text.
@@ -341,6 +366,11 @@ impl Canvas {
         Ok(())
     }
 
+    #[inline]
+    pub(crate) fn target_size(&self) -> (u32, u32) {
+        (self.target.width(), self.target.height())
+    }
+
     /// Returns the scissor rectangle as set by [`Canvas::set_scissor_rect`].
     #[inline]
     pub fn scissor_rect(&self) -> Rect {
@@ -365,13 +395,47 @@ impl Canvas {
         drawable.draw(self, param)
     }
 
+    /// Draws the given `Drawable` with extra `images` for a custom shader, such as normal maps,
+    /// masks or palettes.
+    ///
+    /// The images are bound after the texture and sampler of every mesh, image or instance
+    /// array drawn, so they're visible to the fragment shader from `@group(1) @binding(2)` on,
+    /// and can be sampled with the sampler at `@group(1) @binding(1)`:
+    ///
+    /// ```ignore
+    /// @group(1) @binding(0)
+    /// var t: texture_2d<f32>;
+    /// @group(1) @binding(1)
+    /// var s: sampler;
+    /// @group(1) @binding(2)
+    /// var normal_map: texture_2d<f32>;
+    /// ```
+    ///
+    /// Text is drawn without them.
+    pub fn draw_with_images(
+        &mut self,
+        drawable: &impl Drawable,
+        images: &[&Image],
+        param: impl Into<DrawParam>,
+    ) {
+        let images = images.iter().map(|image| image.current().view).collect();
+        let images = std::mem::replace(&mut self.state.images, images);
+        drawable.draw(self, param);
+        self.state.images = images;
+    }
+
     /// Draws a `Mesh` textured with an `Image`.
     ///
     /// This differs from `canvas.draw(mesh, param)` as in that case, the mesh is untextured.
-    pub fn draw_textured_mesh(&mut self, mesh: Mesh, image: Image, param: impl Into<DrawParam>) {
+    pub fn draw_textured_mesh<V: MeshVertex>(
+        &mut self,
+        mesh: Mesh<V>,
+        image: Image,
+        param: impl Into<DrawParam>,
+    ) {
         self.push_draw(
             Draw::Mesh {
-                mesh,
+                mesh: MeshView::from_mesh(&mesh),
                 image,
                 scale: false,
             },
@@ -379,20 +443,36 @@ impl Canvas {
         );
     }
 
+    /// Draws a `Mesh` textured with an `Image`, along with extra `images` for a custom shader.
+    ///
+    /// See [`Canvas::draw_with_images`] for how the images are bound.
+    pub fn draw_textured_mesh_with_images<V: MeshVertex>(
+        &mut self,
+        mesh: Mesh<V>,
+        image: Image,
+        images: &[&Image],
+        param: impl Into<DrawParam>,
+    ) {
+        let images = images.iter().map(|image| image.current().view).collect();
+        let images = std::mem::replace(&mut self.state.images, images);
+        self.draw_textured_mesh(mesh, image, param);
+        self.state.images = images;
+    }
+
     /// Draws an `InstanceArray` textured with a `Mesh`.
     ///
     /// This differs from `canvas.draw(instances, param)` as in that case, the instances are
     /// drawn as quads.
-    pub fn draw_instanced_mesh(
+    pub fn draw_instanced_mesh<V: MeshVertex, T: bytemuck::Pod>(
         &mut self,
-        mesh: Mesh,
-        instances: &InstanceArray,
+        mesh: Mesh<V>,
+        instances: &InstanceArray<T>,
         param: impl Into<DrawParam>,
     ) {
         instances.flush_wgpu(&self.wgpu).unwrap(); // Will only fail if you can't lock the buffers shouldn't happen
         self.push_draw(
             Draw::MeshInstances {
-                mesh,
+                mesh: MeshView::from_mesh(&mesh),
                 instances: InstanceArrayView::from_instances(instances).unwrap(),
                 scale: false,
             },
@@ -400,13 +480,46 @@ impl Canvas {
         );
     }
 
+    /// Returns the [`DebugDraw`] layer, for drawing lines, shapes and labels on top of
+    /// this canvas without building meshes.
+    #[inline]
+    pub fn debug_draw(&mut self) -> &mut DebugDraw {
+        &mut self.debug
+    }
+
     /// Finish drawing with this canvas and submit all the draw calls.
     #[inline]
-    pub fn finish(mut self, gfx: &mut impl HasMut<GraphicsContext>) -> GameResult {
+    pub fn finish(self, gfx: &mut impl HasMut<GraphicsContext>) -> GameResult {
+        self.finish_with_stats(gfx).map(|_| ())
+    }
+
+    /// Like [`Canvas::finish`], but also returns [`CanvasStats`] about how the draws were
+    /// turned into draw calls, for finding out why a scene is slow to render.
+    pub fn finish_with_stats(
+        mut self,
+        gfx: &mut impl HasMut<GraphicsContext>,
+    ) -> GameResult<CanvasStats> {
         let gfx = gfx.retrieve_mut();
+        #[cfg(feature = "debug-draw")]
+        self.flush_debug_draw(gfx);
         self.finalize(gfx)
     }
 
+    #[cfg(feature = "debug-draw")]
+    fn flush_debug_draw(&mut self, gfx: &mut GraphicsContext) {
+        if self.debug.is_empty() {
+            return;
+        }
+        self.set_default_shader();
+        self.set_default_text_shader();
+        self.set_default_sampler();
+        self.set_blend_mode(BlendMode::ALPHA);
+        self.set_premultiplied_text(true);
+        self.set_default_scissor_rect();
+        let mut debug = std::mem::take(&mut self.debug);
+        debug.flush(self, gfx);
+    }
+
     #[inline]
     pub(crate) fn default_resources(&self) -> &DefaultResources {
         &self.defaults
@@ -414,6 +527,34 @@ impl Canvas {
 
     #[inline]
     pub(crate) fn push_draw(&mut self, draw: Draw, param: DrawParam) {
+        if let Draw::Mesh { mesh, .. } | Draw::MeshInstances { mesh, .. } = &draw {
+            if mesh.layout != Vertex::layout() && self.state.shader.vs_module.is_none() {
+                warn!("Skipping a mesh with a custom vertex layout drawn without a custom vertex shader");
+                return;
+            }
+        }
+
+        // Images may have been hot reloaded into new textures since they were loaded.
+        let draw = match draw {
+            Draw::Mesh { mesh, image, scale } => Draw::Mesh {
+                mesh,
+                image: image.current(),
+                scale,
+            },
+            Draw::MeshInstances {
+                mesh,
+                mut instances,
+                scale,
+            } => {
+                instances.image = instances.image.current();
+                Draw::MeshInstances {
+                    mesh,
+                    instances,
+                    scale,
+                }
+            }
+            draw => draw,
+        };
         self.draws.entry(param.z).or_default().push(DrawCommand {
             state: self.state.clone(),
             draw,
@@ -421,14 +562,37 @@ impl Canvas {
         });
     }
 
-    fn finalize(&mut self, gfx: &mut GraphicsContext) -> GameResult {
+    fn finalize(&mut self, gfx: &mut GraphicsContext) -> GameResult<CanvasStats> {
+        let query = match &mut gfx.fcx {
+            Some(fcx) => gfx.profiler.begin_gpu_scope(&mut fcx.cmd),
+            None => None,
+        };
+        let result = self.encode(gfx);
+        if let Some(fcx) = &mut gfx.fcx {
+            gfx.profiler.end_gpu_scope(&mut fcx.cmd, query);
+        }
+        if let Ok(stats) = &result {
+            gfx.profiler.add_canvas(stats);
+        }
+        result
+    }
+
+    fn encode(&mut self, gfx: &mut GraphicsContext) -> GameResult<CanvasStats> {
         let mut canvas = if let Some(resolve) = &self.resolve {
             InternalCanvas::from_msaa(gfx, self.clear, &self.target, resolve)?
         } else {
             InternalCanvas::from_image(gfx, self.clear, &self.target)?
         };
 
-        let mut state = self.state.clone();
+        // Start out in the state of the first draw, so that only changes between draws
+        // are applied and counted.
+        let mut state = self
+            .draws
+            .values()
+            .flatten()
+            .next()
+            .map_or_else(|| self.state.clone(), |draw| draw.state.clone());
+        let mut changes = CanvasStats::default();
 
         // apply initial state
         canvas.set_shader(state.shader.clone());
@@ -442,6 +606,7 @@ impl Canvas {
         }
 
         canvas.set_sampler(state.sampler);
+        canvas.set_images(state.images.clone());
         canvas.set_blend_mode(state.blend_mode);
         canvas.set_projection(state.projection);
 
@@ -449,73 +614,142 @@ impl Canvas {
             canvas.set_scissor_rect(state.scissor_rect);
         }
 
-        for draws in self.draws.values() {
-            for draw in draws {
-                // track state and apply to InternalCanvas if changed
+        let draws = self.draws.values().flatten().collect::<Vec<_>>();
+        let mut remaining = draws.as_slice();
+        while let Some((&draw, rest)) = remaining.split_first() {
+            // track state and apply to InternalCanvas if changed
 
-                if draw.state.shader != state.shader {
-                    canvas.set_shader(draw.state.shader.clone());
-                }
+            if draw.state.shader != state.shader || draw.state.params != state.params {
+                changes.shader_changes += 1;
+            }
+            if draw.state.text_shader != state.text_shader
+                || draw.state.text_params != state.text_params
+            {
+                changes.shader_changes += 1;
+            }
 
-                if draw.state.params != state.params {
-                    if let Some((bind_group, layout, offset)) = &draw.state.params {
-                        canvas.set_shader_params(bind_group.clone(), layout.clone(), *offset);
-                    }
-                }
+            if draw.state.shader != state.shader {
+                canvas.set_shader(draw.state.shader.clone());
+            }
 
-                if draw.state.text_shader != state.text_shader {
-                    canvas.set_text_shader(draw.state.text_shader.clone());
+            if draw.state.params != state.params {
+                if let Some((bind_group, layout, offset)) = &draw.state.params {
+                    canvas.set_shader_params(bind_group.clone(), layout.clone(), *offset);
                 }
+            }
 
-                if draw.state.text_params != state.text_params {
-                    if let Some((bind_group, layout, offset)) = &draw.state.text_params {
-                        canvas.set_text_shader_params(bind_group.clone(), layout.clone(), *offset);
-                    }
-                }
+            if draw.state.text_shader != state.text_shader {
+                canvas.set_text_shader(draw.state.text_shader.clone());
+            }
 
-                if draw.state.sampler != state.sampler {
-                    canvas.set_sampler(draw.state.sampler);
+            if draw.state.text_params != state.text_params {
+                if let Some((bind_group, layout, offset)) = &draw.state.text_params {
+                    canvas.set_text_shader_params(bind_group.clone(), layout.clone(), *offset);
                 }
+            }
 
-                if draw.state.blend_mode != state.blend_mode {
-                    canvas.set_blend_mode(draw.state.blend_mode);
-                }
+            if draw.state.sampler != state.sampler {
+                canvas.set_sampler(draw.state.sampler);
+                changes.sampler_changes += 1;
+            }
 
-                if draw.state.premul_text != state.premul_text {
-                    canvas.set_premultiplied_text(draw.state.premul_text);
-                }
+            if draw.state.images != state.images {
+                canvas.set_images(draw.state.images.clone());
+            }
 
-                if draw.state.projection != state.projection {
-                    canvas.set_projection(draw.state.projection);
-                }
+            if draw.state.blend_mode != state.blend_mode {
+                canvas.set_blend_mode(draw.state.blend_mode);
+                changes.blend_mode_changes += 1;
+            }
 
-                if draw.state.scissor_rect != state.scissor_rect {
-                    canvas.set_scissor_rect(draw.state.scissor_rect);
-                }
+            if draw.state.premul_text != state.premul_text {
+                canvas.set_premultiplied_text(draw.state.premul_text);
+            }
+
+            if draw.state.projection != state.projection {
+                canvas.set_projection(draw.state.projection);
+            }
 
-                state = draw.state.clone();
-
-                match &draw.draw {
-                    Draw::Mesh { mesh, image, scale } => {
-                        canvas.draw_mesh(mesh, image, draw.param, *scale)
-                    }
-                    Draw::MeshInstances {
-                        mesh,
-                        instances,
-                        scale,
-                    } => canvas.draw_mesh_instances(mesh, instances, draw.param, *scale)?,
-                    Draw::BoundedText { text } => canvas.draw_bounded_text(text, draw.param)?,
+            if draw.state.scissor_rect != state.scissor_rect {
+                canvas.set_scissor_rect(draw.state.scissor_rect);
+                changes.scissor_changes += 1;
+            }
+
+            state = draw.state.clone();
+
+            // Consecutive draws of the same mesh and image in the same state are drawn
+            // with a single instanced draw call.
+            let batch_len = 1 + rest
+                .iter()
+                .take(BATCH_CAPACITY - 1)
+                .take_while(|next| draw.batches_with(next))
+                .count();
+            let (batch, rest) = remaining.split_at(batch_len);
+            remaining = rest;
+
+            match &draw.draw {
+                Draw::Mesh { mesh, image, .. } if batch.len() > 1 => canvas.draw_mesh_batch(
+                    mesh,
+                    image,
+                    batch.iter().filter_map(|draw| match draw.draw {
+                        Draw::Mesh { scale, .. } => Some((draw.param, scale)),
+                        _ => None,
+                    }),
+                ),
+                Draw::Mesh { mesh, image, scale } => {
+                    canvas.draw_mesh(mesh, image, draw.param, *scale)
                 }
+                Draw::MeshInstances {
+                    mesh,
+                    instances,
+                    scale,
+                } => canvas.draw_mesh_instances(mesh, instances, draw.param, *scale)?,
+                Draw::BoundedText { text } => canvas.draw_bounded_text(text, draw.param)?,
             }
         }
 
-        canvas.finish();
-
-        Ok(())
+        Ok(CanvasStats {
+            shader_changes: changes.shader_changes,
+            sampler_changes: changes.sampler_changes,
+            blend_mode_changes: changes.blend_mode_changes,
+            scissor_changes: changes.scissor_changes,
+            ..canvas.finish()
+        })
     }
 }
 
-#[derive(Debug, Clone)]
+/// What a [`Canvas`] did to render its draws, returned by [`Canvas::finish_with_stats`].
+///
+/// Consecutive draws of the same mesh or image in the same state are batched into a single
+/// instanced draw call, up to 1024 at a time. An [`InstanceArray`] is drawn with a single one
+/// too, and so are consecutive [`Text`]s. State changes between draws, such as switching
+/// shaders, can't be batched, and they usually mean a new render pipeline has to be bound as
+/// well.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct CanvasStats {
+    /// Draw calls issued to the GPU.
+    pub draw_calls: u32,
+    /// Instances drawn by those calls, counting each mesh or image, each instance of an
+    /// [`InstanceArray`] and each glyph of text.
+    pub instances: u32,
+    /// Times a different render pipeline had to be bound.
+    pub pipeline_switches: u32,
+    /// Times the shader or the shader parameters changed between draws, for meshes or for text.
+    pub shader_changes: u32,
+    /// Times the [`Sampler`] changed between draws.
+    pub sampler_changes: u32,
+    /// Times the [`BlendMode`] changed between draws.
+    pub blend_mode_changes: u32,
+    /// Times the scissor rectangle changed between draws.
+    pub scissor_changes: u32,
+    /// [`Text`]s drawn.
+    pub text_sections: u32,
+    /// Glyph images uploaded to the text cache texture, which happens when text is drawn
+    /// with glyphs that haven't been drawn recently.
+    pub glyph_uploads: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 struct DrawState {
     shader: Shader,
     params: Option<(ArcBindGroup, ArcBindGroupLayout, u32)>,
@@ -526,17 +760,18 @@ struct DrawState {
     premul_text: bool,
     projection: mint::ColumnMatrix4<f32>,
     scissor_rect: (u32, u32, u32, u32),
+    images: Vec<ArcTextureView>,
 }
 
 #[derive(Debug)]
 pub(crate) enum Draw {
     Mesh {
-        mesh: Mesh,
+        mesh: MeshView,
         image: Image,
         scale: bool,
     },
     MeshInstances {
-        mesh: Mesh,
+        mesh: MeshView,
         instances: InstanceArrayView,
         scale: bool,
     },
@@ -553,6 +788,32 @@ struct DrawCommand {
     draw: Draw,
 }
 
+impl DrawCommand {
+    /// Returns `true` if `next` can be drawn in the same instanced draw call as this draw.
+    fn batches_with(&self, next: &DrawCommand) -> bool {
+        match (&self.draw, &next.draw) {
+            (
+                Draw::Mesh { mesh, image, .. },
+                Draw::Mesh {
+                    mesh: next_mesh,
+                    image: next_image,
+                    ..
+                },
+            ) => {
+                // Batches are drawn by the instance vertex shader, so custom vertex shaders
+                // (which are written for single draws) can't be batched.
+                self.state.shader.vs_module.is_none()
+                    && self.state == next.state
+                    && mesh.verts == next_mesh.verts
+                    && mesh.inds == next_mesh.inds
+                    && mesh.index_count == next_mesh.index_count
+                    && image.view == next_image.view
+            }
+            _ => false,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct DefaultResources {
     pub mesh: Mesh,
@@ -573,6 +834,7 @@ pub fn default_shader() -> Shader {
     Shader {
         fs_module: None,
         vs_module: None,
+        watched: None,
     }
 }
 
@@ -581,5 +843,184 @@ pub fn default_text_shader() -> Shader {
     Shader {
         fs_module: None,
         vs_module: None,
+        watched: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        context::headless_ctx_for_tests,
+        graphics::{ImageFormat, Quad, ShaderBuilder},
+    };
+
+    #[test]
+    fn headless_test_canvas_stats() {
+        let mut ctx = headless_ctx_for_tests(64, 64);
+        ctx.gfx.profiler_mut().set_enabled(true);
+        ctx.gfx.begin_frame().unwrap();
+
+        let mut instances = InstanceArray::new(&ctx, None);
+        for i in 0..5 {
+            instances.push(DrawParam::new().dest([i as f32 * 4.0, 0.0]));
+        }
+        let text = Text::new("hi");
+
+        let mut canvas = Canvas::from_frame(&ctx, Color::BLACK);
+        // These two are batched.
+        canvas.draw(&Quad, DrawParam::new());
+        canvas.draw(&Quad, DrawParam::new());
+        canvas.set_sampler(Sampler::nearest_clamp());
+        canvas.draw(&Quad, DrawParam::new());
+        canvas.set_blend_mode(BlendMode::ADD);
+        canvas.draw(&Quad, DrawParam::new());
+        canvas
+            .set_scissor_rect(Rect::new(0.0, 0.0, 32.0, 32.0))
+            .unwrap();
+        canvas.draw(&Quad, DrawParam::new());
+        canvas.draw(&instances, DrawParam::new());
+        // Consecutive texts are drawn together.
+        canvas.draw(&text, DrawParam::new());
+        canvas.draw(&text, DrawParam::new().dest([0.0, 16.0]));
+        let stats = canvas.finish_with_stats(&mut ctx).unwrap();
+
+        assert_eq!(stats.draw_calls, 6);
+        // 5 quads, 5 instances and 4 glyphs.
+        assert_eq!(stats.instances, 14);
+        assert!(stats.pipeline_switches >= 3);
+        assert_eq!(stats.shader_changes, 0);
+        assert_eq!(stats.sampler_changes, 1);
+        assert_eq!(stats.blend_mode_changes, 1);
+        assert_eq!(stats.scissor_changes, 1);
+        assert_eq!(stats.text_sections, 2);
+        assert!(stats.glyph_uploads > 0);
+
+        ctx.gfx.end_frame().unwrap();
+        let counters = ctx.gfx.profiler().last_frame().unwrap().counters;
+        assert_eq!(counters.canvases, 1);
+        assert_eq!(counters.draw_calls, stats.draw_calls);
+        assert_eq!(counters.pipeline_switches, stats.pipeline_switches);
+        assert_eq!(counters.glyph_uploads, stats.glyph_uploads);
+
+        let image = Image::from_pixels(&ctx, &[255; 4], ImageFormat::Rgba8UnormSrgb, 1, 1);
+        ctx.gfx.begin_frame().unwrap();
+        let mut canvas = Canvas::from_frame(&ctx, Color::BLACK);
+        for i in 0..10 {
+            canvas.draw(&image, DrawParam::new().dest([i as f32, 0.0]));
+        }
+        let stats = canvas.finish_with_stats(&mut ctx).unwrap();
+        ctx.gfx.end_frame().unwrap();
+        assert_eq!(stats.draw_calls, 1);
+        assert_eq!(stats.instances, 10);
+    }
+
+    #[test]
+    fn headless_test_batching() {
+        let mut ctx = headless_ctx_for_tests(32, 32);
+        #[rustfmt::skip]
+        let pixels = [
+            255, 0, 0, 255,     0, 255, 0, 255,
+            0, 0, 255, 255,     255, 255, 255, 128,
+        ];
+        let image = Image::from_pixels(&ctx, &pixels, ImageFormat::Rgba8UnormSrgb, 2, 2);
+        let params = [
+            DrawParam::new().dest([1.0, 1.0]),
+            DrawParam::new().dest([8.0, 2.0]).scale([3.0, 2.0]),
+            DrawParam::new()
+                .dest([20.0, 20.0])
+                .offset([0.5, 0.5])
+                .scale([4.0, 4.0])
+                .color(Color::new(1.0, 1.0, 1.0, 0.5)),
+            DrawParam::new()
+                .dest([2.0, 16.0])
+                .src(Rect::new(0.5, 0.0, 0.5, 1.0))
+                .scale([6.0, 6.0]),
+            DrawParam::new()
+                .transform(glam::Mat4::from_translation(glam::vec3(24.0, 2.0, 0.0)))
+                .color(Color::new(0.2, 0.4, 0.6, 1.0)),
+        ];
+
+        // Drawn in one canvas, the images are batched...
+        ctx.gfx.begin_frame().unwrap();
+        let mut canvas = Canvas::from_frame(&ctx, Color::BLACK);
+        for param in params {
+            canvas.draw(&image, param);
+        }
+        canvas.draw(&Quad, DrawParam::new().dest([30.0, 30.0]));
+        let stats = canvas.finish_with_stats(&mut ctx).unwrap();
+        assert_eq!(stats.draw_calls, 2);
+        assert_eq!(stats.instances, 6);
+        ctx.gfx.end_frame().unwrap();
+        let batched = ctx.gfx.frame().to_pixels(&ctx).unwrap();
+
+        // ...and they look the same as when drawn one at a time.
+        ctx.gfx.begin_frame().unwrap();
+        Canvas::from_frame(&ctx, Color::BLACK)
+            .finish(&mut ctx)
+            .unwrap();
+        for param in params {
+            let mut canvas = Canvas::from_frame(&ctx, None);
+            canvas.draw(&image, param);
+            canvas.finish(&mut ctx).unwrap();
+        }
+        let mut canvas = Canvas::from_frame(&ctx, None);
+        canvas.draw(&Quad, DrawParam::new().dest([30.0, 30.0]));
+        canvas.finish(&mut ctx).unwrap();
+        ctx.gfx.end_frame().unwrap();
+        assert_eq!(ctx.gfx.frame().to_pixels(&ctx).unwrap(), batched);
+    }
+
+    #[test]
+    fn headless_test_draw_with_images() {
+        let mut ctx = headless_ctx_for_tests(32, 16);
+        let format = ctx.gfx.surface_format();
+        let red = Image::from_pixels(&ctx, &[255, 0, 0, 255], format, 1, 1);
+        let green = Image::from_pixels(&ctx, &[0, 255, 0, 255], format, 1, 1);
+        let mask = ShaderBuilder::new()
+            .fragment_code(
+                r"
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+@group(1) @binding(0)
+var t: texture_2d<f32>;
+@group(1) @binding(1)
+var s: sampler;
+@group(1) @binding(2)
+var mask: texture_2d<f32>;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color * textureSample(t, s, in.uv) * textureSample(mask, s, in.uv);
+}
+",
+            )
+            .build(&ctx.gfx)
+            .unwrap();
+        let quad = |x: f32| DrawParam::new().dest([x, 0.0]).scale([8.0, 16.0]);
+
+        ctx.gfx.begin_frame().unwrap();
+        let mut canvas = Canvas::from_frame(&ctx, Color::BLACK);
+        canvas.set_shader(&mask);
+        // Draws with the same images are still batched.
+        canvas.draw_with_images(&Quad, &[&red], quad(0.0));
+        canvas.draw_with_images(&Quad, &[&red], quad(8.0));
+        canvas.draw_with_images(&Quad, &[&green], quad(16.0));
+        canvas.set_default_shader();
+        canvas.draw(&Quad, quad(24.0).color(Color::BLUE));
+        let stats = canvas.finish_with_stats(&mut ctx).unwrap();
+        assert_eq!(stats.draw_calls, 3);
+        ctx.gfx.end_frame().unwrap();
+
+        let frame = ctx.gfx.frame().to_pixels(&ctx).unwrap();
+        let pixel = |x: usize| &frame[(8 * 32 + x) * 4..][..4];
+        assert_eq!(pixel(4), [255, 0, 0, 255]);
+        assert_eq!(pixel(12), [255, 0, 0, 255]);
+        assert_eq!(pixel(20), [0, 255, 0, 255]);
+        assert_eq!(pixel(28), [0, 0, 255, 255]);
     }
 }
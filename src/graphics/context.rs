@@ -1,3 +1,5 @@
+#[cfg(feature = "debug-draw")]
+use super::debug_draw::DebugMeshes;
 use super::{
     draw::DrawUniforms,
     gpu::{
@@ -10,10 +12,12 @@ use super::{
         pipeline::PipelineCache,
         text::TextRenderer,
     },
-    image::{Image, ImageFormat},
-    mesh::{Mesh, Vertex},
+    image::{Image, ImageEncodingFormat, ImageFormat},
+    internal_canvas::BATCH_CAPACITY,
+    mesh::{Mesh, MeshVertex, Vertex},
+    profiler::{ProfileScope, Profiler},
     sampler::{Sampler, SamplerCache},
-    text::FontData,
+    text::{FontData, WatchedFont},
     MeshData, ScreenImage,
 };
 use crate::{
@@ -27,7 +31,12 @@ use crate::{
 use ::image as imgcrate;
 use crevice::std140::AsStd140;
 use glyph_brush::FontId;
-use std::{collections::HashMap, path::Path, sync::Arc};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Instant,
+};
 use typed_arena::Arena as TypedArena;
 use winit::{
     self,
@@ -38,8 +47,8 @@ pub(crate) struct FrameContext {
     pub cmd: wgpu::CommandEncoder,
     pub present: Image,
     pub arenas: FrameArenas,
-    pub frame: wgpu::SurfaceTexture,
-    pub frame_view: wgpu::TextureView,
+    /// The swapchain image and its view, or `None` if the context is headless.
+    pub frame: Option<(wgpu::SurfaceTexture, wgpu::TextureView)>,
 }
 
 #[derive(Default)]
@@ -54,7 +63,8 @@ pub(crate) struct FrameArenas {
 #[allow(missing_docs)]
 pub struct WgpuContext {
     pub instance: wgpu::Instance,
-    pub surface: wgpu::Surface,
+    /// `None` if the context is headless.
+    pub surface: Option<wgpu::Surface>,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
 }
@@ -64,7 +74,9 @@ pub struct WgpuContext {
 pub struct GraphicsContext {
     pub(crate) wgpu: Arc<WgpuContext>,
 
-    pub(crate) window: winit::window::Window,
+    /// `None` if the context is headless, in which case `surface_config` only tracks
+    /// the size and format of the offscreen frame.
+    pub(crate) window: Option<winit::window::Window>,
     pub(crate) surface_config: wgpu::SurfaceConfiguration,
 
     pub(crate) bind_group_cache: BindGroupCache,
@@ -79,9 +91,14 @@ pub struct GraphicsContext {
 
     pub(crate) fcx: Option<FrameContext>,
     pub(crate) text: TextRenderer,
+    pub(crate) profiler: Profiler,
+    #[cfg(feature = "debug-draw")]
+    pub(crate) debug_meshes: DebugMeshes,
     pub(crate) fonts: HashMap<String, FontId>,
+    pub(crate) watched_fonts: Vec<Arc<WatchedFont>>,
     pub(crate) staging_belt: wgpu::util::StagingBelt,
     pub(crate) uniform_arena: GrowingBufferArena,
+    pub(crate) instance_arena: GrowingBufferArena,
 
     pub(crate) draw_shader: ArcShaderModule,
     pub(crate) instance_shader: ArcShaderModule,
@@ -95,6 +112,18 @@ pub struct GraphicsContext {
     pub(crate) fs: Filesystem,
 
     bind_group: Option<(Vec<BindGroupEntryKey>, ArcBindGroup)>,
+    // The image presented at the end of the last frame, see `GraphicsContext::present`.
+    presented: Option<Image>,
+    recording: Option<Recording>,
+}
+
+/// State of an in-progress frame recording, see [`GraphicsContext::start_recording`].
+#[derive(Debug)]
+struct Recording {
+    dir: PathBuf,
+    every_nth_frame: u32,
+    frames_seen: u64,
+    frames_saved: u64,
 }
 
 impl GraphicsContext {
@@ -105,6 +134,26 @@ impl GraphicsContext {
         event_loop: &winit::event_loop::EventLoop<()>,
         conf: &Conf,
         filesystem: &Filesystem,
+    ) -> GameResult<Self> {
+        Self::new_with_backends(conf, |instance| {
+            Self::new_from_instance(game_id, instance, Some(event_loop), conf, filesystem)
+        })
+    }
+
+    /// Create a new headless graphics context.
+    ///
+    /// No window or surface is created; everything is rendered to an offscreen frame image
+    /// of `conf.window_mode`'s size, which can be read back with [`Image::to_pixels`] or
+    /// [`Image::encode`]. If no hardware adapter is available, a software adapter is used.
+    pub fn new_headless(conf: &Conf, filesystem: &Filesystem) -> GameResult<Self> {
+        Self::new_with_backends(conf, |instance| {
+            Self::new_from_instance("", instance, None, conf, filesystem)
+        })
+    }
+
+    fn new_with_backends(
+        conf: &Conf,
+        new_from_instance: impl Fn(wgpu::Instance) -> GameResult<Self>,
     ) -> GameResult<Self> {
         let new_instance = |backends| {
             wgpu::Instance::new(wgpu::InstanceDescriptor {
@@ -114,13 +163,7 @@ impl GraphicsContext {
         };
 
         if conf.backend == Backend::All {
-            match Self::new_from_instance(
-                game_id,
-                new_instance(wgpu::Backends::PRIMARY),
-                event_loop,
-                conf,
-                filesystem,
-            ) {
+            match new_from_instance(new_instance(wgpu::Backends::PRIMARY)) {
                 Ok(o) => Ok(o),
                 Err(GameError::GraphicsInitializationError) => {
                     println!(
@@ -130,13 +173,7 @@ impl GraphicsContext {
                         "Failed to initialize graphics, trying secondary backends.. Please mention this if you encounter any bugs!"
                     );
 
-                    Self::new_from_instance(
-                        game_id,
-                        new_instance(wgpu::Backends::SECONDARY),
-                        event_loop,
-                        conf,
-                        filesystem,
-                    )
+                    new_from_instance(new_instance(wgpu::Backends::SECONDARY))
                 }
                 Err(e) => Err(e),
             }
@@ -152,7 +189,7 @@ impl GraphicsContext {
                 Backend::BrowserWebGpu => wgpu::Backends::BROWSER_WEBGPU,
             });
 
-            Self::new_from_instance(game_id, instance, event_loop, conf, filesystem)
+            new_from_instance(instance)
         }
     }
 
@@ -231,57 +268,33 @@ impl GraphicsContext {
     pub(crate) fn new_from_instance(
         #[allow(unused_variables)] game_id: &str,
         instance: wgpu::Instance,
-        event_loop: &winit::event_loop::EventLoop<()>,
+        event_loop: Option<&winit::event_loop::EventLoop<()>>,
         conf: &Conf,
         filesystem: &Filesystem,
     ) -> GameResult<Self> {
-        let mut window_builder = winit::window::WindowBuilder::new()
-            .with_title(conf.window_setup.title.clone())
-            .with_inner_size(conf.window_mode.actual_size().unwrap()) // Unwrap since actual_size only fails if one of the window dimensions is less than 1
-            .with_resizable(conf.window_mode.resizable)
-            .with_visible(conf.window_mode.visible)
-            .with_transparent(conf.window_mode.transparent);
-
-        #[cfg(any(
-            target_os = "linux",
-            target_os = "dragonfly",
-            target_os = "freebsd",
-            target_os = "netbsd",
-            target_os = "openbsd"
-        ))]
-        {
-            {
-                use winit::platform::x11::WindowBuilderExtX11;
-                window_builder = window_builder.with_name(game_id, game_id);
-            }
-            {
-                use winit::platform::wayland::WindowBuilderExtWayland;
-                window_builder = window_builder.with_name(game_id, game_id);
+        let (window, surface) = match event_loop {
+            Some(event_loop) => {
+                let window = Self::build_window(game_id, event_loop, conf, filesystem)?;
+                let surface = unsafe { instance.create_surface(&window) }
+                    .map_err(|_| GameError::GraphicsInitializationError)?;
+                (Some(window), Some(surface))
             }
-        }
-
-        #[cfg(target_os = "windows")]
-        {
-            use winit::platform::windows::WindowBuilderExtWindows;
-            window_builder = window_builder.with_drag_and_drop(false);
-        }
-
-        window_builder = if !conf.window_setup.icon.is_empty() {
-            let icon = load_icon(conf.window_setup.icon.as_ref(), filesystem)?;
-            window_builder.with_window_icon(Some(icon))
-        } else {
-            window_builder
+            None => (None, None),
         };
 
-        let window = window_builder.build(event_loop)?;
-        let surface = unsafe { instance.create_surface(&window) }
-            .map_err(|_| GameError::GraphicsInitializationError)?;
-
-        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
-            power_preference: wgpu::PowerPreference::HighPerformance,
-            force_fallback_adapter: false,
-            compatible_surface: Some(&surface),
-        }))
+        let request_adapter = |force_fallback_adapter| {
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                force_fallback_adapter,
+                compatible_surface: surface.as_ref(),
+            }))
+        };
+        let adapter = match request_adapter(false) {
+            Some(adapter) => Some(adapter),
+            // Machines without a display (e.g. CI) often only have a software adapter.
+            None if surface.is_none() => request_adapter(true),
+            None => None,
+        }
         .ok_or(GameError::GraphicsInitializationError)?;
 
         // One instance is 96 bytes, and we allow 1 million of them, for a total of 96MB (default being 128MB).
@@ -291,15 +304,16 @@ impl GraphicsContext {
         let (device, queue) = pollster::block_on(adapter.request_device(
             &wgpu::DeviceDescriptor {
                 label: None,
-                features: wgpu::Features::default(),
+                // Timestamp queries are used by the profiler, if they're available.
+                features: adapter.features() & wgpu::Features::TIMESTAMP_QUERY,
                 limits: wgpu::Limits {
                     // 1st: DrawParams
                     // 2nd: Texture + Sampler
                     // 3rd: InstanceArray
                     // 4th: ShaderParams
                     max_bind_groups: 4,
-                    // InstanceArray uses 2 storage buffers.
-                    max_storage_buffers_per_shader_stage: 2,
+                    // InstanceArray uses 3 storage buffers: instances, indices and instance data.
+                    max_storage_buffers_per_shader_stage: 3,
                     max_storage_buffer_binding_size: INSTANCE_BUFFER_SIZE,
                     max_texture_dimension_1d: 8192,
                     max_texture_dimension_2d: 8192,
@@ -316,12 +330,19 @@ impl GraphicsContext {
             queue,
         });
 
-        let capabilities = wgpu.surface.get_capabilities(&adapter);
-
-        let size = window.inner_size();
+        let (format, size) = match (&window, &wgpu.surface) {
+            (Some(window), Some(surface)) => (
+                surface.get_capabilities(&adapter).formats[0],
+                window.inner_size(),
+            ),
+            _ => (
+                wgpu::TextureFormat::Rgba8UnormSrgb,
+                conf.window_mode.actual_size()?.to_physical(1.0),
+            ),
+        };
         let surface_config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: capabilities.formats[0],
+            format,
             width: size.width,
             height: size.height,
             present_mode: if conf.window_setup.vsync {
@@ -333,7 +354,9 @@ impl GraphicsContext {
             view_formats: vec![],
         };
 
-        wgpu.surface.configure(&wgpu.device, &surface_config);
+        if let Some(surface) = &wgpu.surface {
+            surface.configure(&wgpu.device, &surface_config);
+        }
 
         let mut bind_group_cache = BindGroupCache::new();
         let pipeline_cache = PipelineCache::new();
@@ -344,6 +367,7 @@ impl GraphicsContext {
             .create(&wgpu.device, &mut bind_group_cache);
 
         let text = TextRenderer::new(&wgpu.device, image_bind_layout);
+        let profiler = Profiler::new(&wgpu.device, &wgpu.queue);
 
         let staging_belt = wgpu::util::StagingBelt::new(1024);
         let uniform_arena = GrowingBufferArena::new(
@@ -357,6 +381,18 @@ impl GraphicsContext {
             },
         );
 
+        // Instance data for draws that the canvases batch together.
+        let instance_arena = GrowingBufferArena::new(
+            &wgpu.device,
+            u64::from(wgpu.device.limits().min_storage_buffer_offset_alignment),
+            wgpu::BufferDescriptor {
+                label: None,
+                size: BATCH_CAPACITY as u64 * DrawUniforms::std140_size_static() as u64,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            },
+        );
+
         let draw_shader = ArcShaderModule::new(wgpu.device.create_shader_module(
             wgpu::ShaderModuleDescriptor {
                 label: None,
@@ -434,6 +470,11 @@ impl GraphicsContext {
                 wgpu::BufferBindingType::Storage { read_only: true },
                 false,
             )
+            .buffer(
+                wgpu::ShaderStages::VERTEX,
+                wgpu::BufferBindingType::Storage { read_only: true },
+                false,
+            )
             .create(&wgpu.device, &mut bind_group_cache);
 
         let white_image =
@@ -457,9 +498,14 @@ impl GraphicsContext {
 
             fcx: None,
             text,
+            profiler,
+            #[cfg(feature = "debug-draw")]
+            debug_meshes: DebugMeshes::default(),
             fonts: HashMap::new(),
+            watched_fonts: Vec::new(),
             staging_belt,
             uniform_arena,
+            instance_arena,
             draw_shader,
             instance_shader,
             instance_unordered_shader,
@@ -472,6 +518,8 @@ impl GraphicsContext {
             fs: InternalClone::clone(filesystem),
 
             bind_group: None,
+            presented: None,
+            recording: None,
         };
 
         this.set_window_mode(&conf.window_mode)?;
@@ -494,6 +542,53 @@ impl GraphicsContext {
         Ok(this)
     }
 
+    fn build_window(
+        #[allow(unused_variables)] game_id: &str,
+        event_loop: &winit::event_loop::EventLoop<()>,
+        conf: &Conf,
+        filesystem: &Filesystem,
+    ) -> GameResult<winit::window::Window> {
+        let mut window_builder = winit::window::WindowBuilder::new()
+            .with_title(conf.window_setup.title.clone())
+            .with_inner_size(conf.window_mode.actual_size().unwrap()) // Unwrap since actual_size only fails if one of the window dimensions is less than 1
+            .with_resizable(conf.window_mode.resizable)
+            .with_visible(conf.window_mode.visible)
+            .with_transparent(conf.window_mode.transparent);
+
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        ))]
+        {
+            {
+                use winit::platform::x11::WindowBuilderExtX11;
+                window_builder = window_builder.with_name(game_id, game_id);
+            }
+            {
+                use winit::platform::wayland::WindowBuilderExtWayland;
+                window_builder = window_builder.with_name(game_id, game_id);
+            }
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            use winit::platform::windows::WindowBuilderExtWindows;
+            window_builder = window_builder.with_drag_and_drop(false);
+        }
+
+        window_builder = if !conf.window_setup.icon.is_empty() {
+            let icon = load_icon(conf.window_setup.icon.as_ref(), filesystem)?;
+            window_builder.with_window_icon(Some(icon))
+        } else {
+            window_builder
+        };
+
+        Ok(window_builder.build(event_loop)?)
+    }
+
     /// Returns a reference to the underlying WGPU context.
     #[inline]
     pub fn wgpu(&self) -> &WgpuContext {
@@ -516,38 +611,61 @@ impl GraphicsContext {
     #[allow(unused_results)]
     pub fn add_font(&mut self, name: &str, font: FontData) {
         let id = self.text.glyph_brush.borrow_mut().add_font(font.font);
+        if let Some(watched) = font.watched {
+            watched.add(id);
+            self.watched_fonts.push(watched);
+        }
         self.fonts.insert(name.to_string(), id);
     }
 
     /// Returns the size of the window’s underlying drawable in physical pixels as (width, height).
+    ///
+    /// For a headless context, this is the size of the offscreen frame.
     pub fn drawable_size(&self) -> (f32, f32) {
-        let size = self.window.inner_size();
+        let size = self.inner_size();
         (size.width as f32, size.height as f32)
     }
 
+    /// Returns `true` if this context was created without a window, see [`GraphicsContext::new_headless`].
+    #[inline]
+    pub fn is_headless(&self) -> bool {
+        self.window.is_none()
+    }
+
+    pub(crate) fn inner_size(&self) -> dpi::PhysicalSize<u32> {
+        match &self.window {
+            Some(window) => window.inner_size(),
+            None => dpi::PhysicalSize::new(self.surface_config.width, self.surface_config.height),
+        }
+    }
+
     /// Sets the window size (in physical pixels) / resolution to the specified width and height.
     ///
     /// Note:   These dimensions are only interpreted as resolutions in true fullscreen mode.
     ///         If the selected resolution is not supported this function will return an Error.
+    ///
+    /// For a headless context, this resizes the offscreen frame.
     pub fn set_drawable_size(&mut self, width: f32, height: f32) -> GameResult {
         self.set_mode(self.window_mode.dimensions(width, height))
     }
 
     /// Sets the window title.
     pub fn set_window_title(&self, title: &str) {
-        self.window.set_title(title);
+        if let Some(window) = &self.window {
+            window.set_title(title);
+        }
     }
 
     /// Returns the position of the system window, including the outer frame.
     pub fn window_position(&self) -> GameResult<PhysicalPosition<i32>> {
-        self.window
+        self.window_or_err()?
             .outer_position()
             .map_err(|e| GameError::WindowError(e.to_string()))
     }
 
     /// Sets the window position.
     pub fn set_window_position(&self, position: impl Into<winit::dpi::Position>) -> GameResult {
-        self.window.set_outer_position(position);
+        self.window_or_err()?.set_outer_position(position);
         Ok(())
     }
 
@@ -555,23 +673,49 @@ impl GraphicsContext {
     /// including borders, titlebar, etc.
     /// Returns zeros if the window doesn't exist.
     pub fn size(&self) -> (f32, f32) {
-        let size = self.window.outer_size();
+        let size = self
+            .window
+            .as_ref()
+            .map(|window| window.outer_size())
+            .unwrap_or_default();
         (size.width as f32, size.height as f32)
     }
 
     /// Returns an iterator providing all resolutions supported by the current monitor.
+    ///
+    /// The iterator is empty for a headless context.
     pub fn supported_resolutions(&self) -> impl Iterator<Item = winit::dpi::PhysicalSize<u32>> {
         self.window
-            .current_monitor()
-            .unwrap() // Unwrap is fine current monitor should always exist
-            .video_modes()
+            .as_ref()
+            .map(|window| window.current_monitor().unwrap()) // Unwrap is fine current monitor should always exist
+            .into_iter()
+            .flat_map(|monitor| monitor.video_modes())
             .map(|vm| vm.size())
     }
 
     /// Returns a reference to the Winit window.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the context is headless, see [`GraphicsContext::is_headless`] and
+    /// [`GraphicsContext::try_window`].
     #[inline]
     pub fn window(&self) -> &winit::window::Window {
-        &self.window
+        self.window
+            .as_ref()
+            .expect("a headless graphics context has no window")
+    }
+
+    /// Returns a reference to the Winit window, or `None` if the context is headless.
+    #[inline]
+    pub fn try_window(&self) -> Option<&winit::window::Window> {
+        self.window.as_ref()
+    }
+
+    fn window_or_err(&self) -> GameResult<&winit::window::Window> {
+        self.window.as_ref().ok_or_else(|| {
+            GameError::WindowError(String::from("a headless graphics context has no window"))
+        })
     }
 
     /// Sets the window icon. `None` for path removes the icon.
@@ -585,7 +729,9 @@ impl GraphicsContext {
             Some(p) => Some(load_icon(p.as_ref(), filesystem)?),
             None => None,
         };
-        self.window.set_window_icon(icon);
+        if let Some(window) = &self.window {
+            window.set_window_icon(icon);
+        }
         Ok(())
     }
 
@@ -617,6 +763,10 @@ impl GraphicsContext {
             mode.fullscreen_type = old_fullscreen;
         }
         self.window_mode = mode;
+        // Without a window there won't be a resize event to pick up the new size.
+        if self.window.is_none() {
+            self.update_frame_image();
+        }
         result
     }
 
@@ -628,7 +778,126 @@ impl GraphicsContext {
         self.frame_image.as_ref().unwrap(/* invariant */)
     }
 
+    /// Returns the frame [`Profiler`].
+    #[inline]
+    pub fn profiler(&self) -> &Profiler {
+        &self.profiler
+    }
+
+    /// Returns the frame [`Profiler`], for enabling it or showing its overlay.
+    #[inline]
+    pub fn profiler_mut(&mut self) -> &mut Profiler {
+        &mut self.profiler
+    }
+
+    /// Returns a copy of the last presented frame.
+    ///
+    /// This is the frame image, or the image passed to [`GraphicsContext::present`] if there
+    /// was one. Unlike [`GraphicsContext::frame`], the returned image is not drawn over by later
+    /// frames, so it can be kept around, drawn, or saved with [`Image::encode`].
+    /// If called while a frame is in progress, the copy holds the previous frame.
+    pub fn screenshot(&self) -> GameResult<Image> {
+        let frame = self.last_presented();
+        let image = Image::new(
+            &self.wgpu,
+            frame.format(),
+            frame.width(),
+            frame.height(),
+            1,
+            wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+        );
+
+        let mut cmd = self
+            .wgpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        cmd.copy_texture_to_texture(
+            frame.texture.as_image_copy(),
+            image.texture.as_image_copy(),
+            wgpu::Extent3d {
+                width: frame.width(),
+                height: frame.height(),
+                depth_or_array_layers: 1,
+            },
+        );
+        let _ = self.wgpu.queue.submit([cmd.finish()]);
+
+        Ok(image)
+    }
+
+    fn last_presented(&self) -> &Image {
+        self.presented.as_ref().unwrap_or_else(|| self.frame())
+    }
+
+    /// Starts saving every `every_nth_frame`th presented frame as a PNG into `dir`.
+    ///
+    /// `dir` is a path in the [`Filesystem`], so the frames end up in the user data
+    /// directory, named `000000.png`, `000001.png` and so on.
+    /// Any recording already in progress is replaced.
+    ///
+    /// **Every saved frame is read back from the GPU, which stalls the frame - use sparingly.**
+    pub fn start_recording(&mut self, dir: impl AsRef<Path>, every_nth_frame: u32) -> GameResult {
+        if every_nth_frame == 0 {
+            return Err(GameError::RenderError(String::from(
+                "cannot record every 0th frame; use 1 to record every frame",
+            )));
+        }
+        let dir = dir.as_ref().to_path_buf();
+        self.fs.create_dir(&dir)?;
+        self.recording = Some(Recording {
+            dir,
+            every_nth_frame,
+            frames_seen: 0,
+            frames_saved: 0,
+        });
+        Ok(())
+    }
+
+    /// Stops the current recording, returning how many frames were saved.
+    ///
+    /// Returns `None` if no recording was in progress.
+    pub fn stop_recording(&mut self) -> Option<u64> {
+        self.recording.take().map(|r| r.frames_saved)
+    }
+
+    /// Returns `true` if frames are currently being recorded, see [`GraphicsContext::start_recording`].
+    #[inline]
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    fn record_frame(&mut self) -> GameResult {
+        let Some(recording) = &mut self.recording else {
+            return Ok(());
+        };
+        recording.frames_seen += 1;
+        if (recording.frames_seen - 1) % u64::from(recording.every_nth_frame) != 0 {
+            return Ok(());
+        }
+        let path = recording
+            .dir
+            .join(format!("{:06}.png", recording.frames_saved));
+        recording.frames_saved += 1;
+        self.last_presented()
+            .encode(self, ImageEncodingFormat::Png, path)
+    }
+
+    /// Reloads the images, fonts, shaders and sounds whose files have changed, returning how
+    /// many were.
+    ///
+    /// Only assets loaded while
+    /// [asset hot reloading](crate::filesystem::Filesystem::set_asset_hot_reload) was on are
+    /// tracked. If some fail to load, the others are still reloaded and the first error is
+    /// returned.
+    pub fn reload_assets(&self) -> GameResult<usize> {
+        self.fs.reload_assets(self)
+    }
+
     /// Returns the image format of the window surface.
+    ///
+    /// For a headless context, this is the format of the offscreen frame.
     #[inline]
     pub fn surface_format(&self) -> ImageFormat {
         self.surface_config.format
@@ -649,24 +918,36 @@ impl GraphicsContext {
             )));
         }
 
-        let size = self.window.inner_size();
-        let frame = match self.wgpu.surface.get_current_texture() {
-            Ok(frame) => Ok(frame),
-            Err(_) => {
-                self.surface_config.width = size.width.max(1);
-                self.surface_config.height = size.height.max(1);
-                self.wgpu
-                    .surface
-                    .configure(&self.wgpu.device, &self.surface_config);
-                self.wgpu.surface.get_current_texture().map_err(|_| {
-                    GameError::RenderError(String::from("failed to get next swapchain image"))
-                })
+        if self.fs.is_asset_hot_reload_enabled() {
+            if let Err(e) = self.reload_assets() {
+                warn!("Failed to reload assets: {e}");
             }
-        }?;
+        }
+
+        let frame = match &self.wgpu.surface {
+            Some(surface) => {
+                let frame = match surface.get_current_texture() {
+                    Ok(frame) => Ok(frame),
+                    Err(_) => {
+                        let size = self.inner_size();
+                        self.surface_config.width = size.width.max(1);
+                        self.surface_config.height = size.height.max(1);
+                        surface.configure(&self.wgpu.device, &self.surface_config);
+                        surface.get_current_texture().map_err(|_| {
+                            GameError::RenderError(String::from(
+                                "failed to get next swapchain image",
+                            ))
+                        })
+                    }
+                }?;
 
-        let frame_view = frame
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+                let frame_view = frame
+                    .texture
+                    .create_view(&wgpu::TextureViewDescriptor::default());
+                Some((frame, frame_view))
+            }
+            None => None,
+        };
 
         self.fcx = Some(FrameContext {
             cmd: self
@@ -676,12 +957,16 @@ impl GraphicsContext {
             present: self.frame().clone(),
             arenas: FrameArenas::default(),
             frame,
-            frame_view,
         });
 
         self.uniform_arena.free();
+        self.instance_arena.free();
 
         self.text.verts.free();
+        #[cfg(feature = "debug-draw")]
+        self.debug_meshes.begin_frame();
+
+        self.profiler.begin_frame(&self.wgpu.device);
 
         Ok(())
     }
@@ -690,76 +975,112 @@ impl GraphicsContext {
     ///
     /// The only situation you need to call this in is when you are rolling your own event loop.
     pub fn end_frame(&mut self) -> GameResult {
-        if let Some(mut fcx) = self.fcx.take() {
-            let mut present_pass = fcx.cmd.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: None,
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &fcx.frame_view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                        store: true,
-                    },
-                })],
-                depth_stencil_attachment: None,
-            });
+        if self.fcx.is_none() {
+            return Err(GameError::RenderError(String::from(
+                "cannot end a frame as there was never one in progress; call begin_frame first",
+            )));
+        }
+        if self.profiler.is_enabled() && self.profiler.is_overlay_visible() {
+            Profiler::draw_overlay(self)?;
+        }
 
-            let sampler = &mut self
-                .sampler_cache
-                .get(&self.wgpu.device, Sampler::default());
-
-            let (bind, layout) = self.bind_group(fcx.present.view, sampler.clone());
-
-            let layout = self.pipeline_cache.layout(&self.wgpu.device, &[layout]);
-            let copy = self.pipeline_cache.render_pipeline(
-                &self.wgpu.device,
-                &layout,
-                RenderPipelineInfo {
-                    vs: self.copy_shader.clone(),
-                    fs: self.copy_shader.clone(),
-                    vs_entry: "vs_main".into(),
-                    fs_entry: "fs_main".into(),
-                    samples: 1,
-                    format: self.surface_config.format,
-                    blend: None,
-                    depth: false,
-                    vertices: false,
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    vertex_layout: Vertex::layout(),
-                },
-            );
+        let start = Instant::now();
+        self.submit_frame();
+        self.profiler.record(ProfileScope::Present, start.elapsed());
+        let (hits, misses) = self.bind_group_cache.take_stats();
+        let counters = self.profiler.counters();
+        counters.bind_group_cache_hits += hits;
+        counters.bind_group_cache_misses += misses;
+        self.profiler.end_frame();
 
-            let copy = fcx.arenas.render_pipelines.alloc(copy);
-            let bind = fcx.arenas.bind_groups.alloc(bind);
+        self.record_frame()
+    }
 
-            present_pass.set_pipeline(copy);
-            present_pass.set_bind_group(0, bind, &[]);
-            present_pass.draw(0..3, 0..1);
+    /// Submits the commands of the current frame and presents it.
+    fn submit_frame(&mut self) {
+        if let Some(mut fcx) = self.fcx.take() {
+            self.profiler.resolve_gpu_scopes(&mut fcx.cmd);
+            let Some((frame, frame_view)) = fcx.frame.take() else {
+                // Headless; the frame image stands in for the window, so it gets the presented
+                // image unless that's what everything was rendered to.
+                if fcx.present.texture != self.frame().texture {
+                    let frame_view = self.frame().view.clone();
+                    self.draw_present(&mut fcx, &frame_view);
+                }
+                self.staging_belt.finish();
+                let _ = self.wgpu.queue.submit([fcx.cmd.finish()]);
+                self.staging_belt.recall();
+                self.presented = Some(fcx.present);
+                return;
+            };
 
-            std::mem::drop(present_pass);
+            self.draw_present(&mut fcx, &frame_view);
 
             self.staging_belt.finish();
             let _ = self.wgpu.queue.submit([fcx.cmd.finish()]);
-            fcx.frame.present();
+            frame.present();
 
             self.staging_belt.recall();
-
-            Ok(())
-        } else {
-            Err(GameError::RenderError(String::from(
-                "cannot end a frame as there was never one in progress; call begin_frame first",
-            )))
+            self.presented = Some(fcx.present);
         }
     }
 
+    /// Draws the presented image of the frame stretched over `target`.
+    fn draw_present(&mut self, fcx: &mut FrameContext, target: &wgpu::TextureView) {
+        let mut present_pass = fcx.cmd.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+
+        let sampler = &mut self
+            .sampler_cache
+            .get(&self.wgpu.device, Sampler::default());
+
+        let (bind, layout) = self.bind_group(fcx.present.view.clone(), sampler.clone());
+
+        let layout = self.pipeline_cache.layout(&self.wgpu.device, &[layout]);
+        let copy = self.pipeline_cache.render_pipeline(
+            &self.wgpu.device,
+            &layout,
+            RenderPipelineInfo {
+                vs: self.copy_shader.clone(),
+                fs: self.copy_shader.clone(),
+                vs_entry: "vs_main".into(),
+                fs_entry: "fs_main".into(),
+                samples: 1,
+                format: self.surface_config.format,
+                blend: None,
+                depth: false,
+                vertices: false,
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                vertex_layout: Vertex::layout(),
+            },
+        );
+
+        let copy = fcx.arenas.render_pipelines.alloc(copy);
+        let bind = fcx.arenas.bind_groups.alloc(bind);
+
+        present_pass.set_pipeline(copy);
+        present_pass.set_bind_group(0, bind, &[]);
+        present_pass.draw(0..3, 0..1);
+    }
+
     pub(crate) fn resize(&mut self, _new_size: dpi::PhysicalSize<u32>) {
-        let size = self.window.inner_size();
+        let size = self.inner_size();
         let _ = self.wgpu.device.poll(wgpu::Maintain::Wait);
         self.surface_config.width = size.width.max(1);
         self.surface_config.height = size.height.max(1);
-        self.wgpu
-            .surface
-            .configure(&self.wgpu.device, &self.surface_config);
+        if let Some(surface) = &self.wgpu.surface {
+            surface.configure(&self.wgpu.device, &self.surface_config);
+        }
         self.update_frame_image();
     }
 
@@ -778,7 +1099,12 @@ impl GraphicsContext {
     }
 
     pub(crate) fn set_window_mode(&mut self, mode: &WindowMode) -> GameResult {
-        let window = &mut self.window;
+        let Some(window) = &mut self.window else {
+            let size: dpi::PhysicalSize<u32> = mode.actual_size()?.to_physical(1.0);
+            self.surface_config.width = size.width.max(1);
+            self.surface_config.height = size.height.max(1);
+            return Ok(());
+        };
 
         // TODO LATER: find out if single-dimension constraints are possible?
         let min_dimensions = if mode.min_width >= 1.0 && mode.min_height >= 1.0 {
@@ -850,9 +1176,9 @@ impl GraphicsContext {
         self.surface_config.width = size.width.max(1);
         self.surface_config.height = size.height.max(1);
 
-        self.wgpu
-            .surface
-            .configure(&self.wgpu.device, &self.surface_config);
+        if let Some(surface) = &self.wgpu.surface {
+            surface.configure(&self.wgpu.device, &self.surface_config);
+        }
 
         Ok(())
     }
@@ -0,0 +1,272 @@
+#[cfg(feature = "debug-draw")]
+use super::{
+    Canvas, DrawParam, DynamicMesh, GraphicsContext, MeshBuilder, Text, WgpuContext, ZIndex,
+};
+use super::{Color, DrawMode, Rect, TextFragment};
+#[cfg(feature = "debug-draw")]
+use crate::GameResult;
+
+/// Immediate-mode drawing of lines, shapes and labels for debugging, found in
+/// [`Canvas::debug_draw`](super::Canvas::debug_draw).
+///
+/// Every primitive is added to one mesh, which the canvas draws on top of everything else
+/// when it's finished, so any number of them takes a single draw call (plus one for the
+/// labels). They're drawn in the canvas's coordinates (i.e. its projection when it's
+/// finished), with the default shader, sampler and blend mode and without a scissor
+/// rectangle, and are gone in the next frame.
+///
+/// ```rust,no_run
+/// # use ggez::graphics::{Canvas, Color, DrawMode, Rect};
+/// # fn t(canvas: &mut Canvas) {
+/// canvas
+///     .debug_draw()
+///     .rect(Rect::new(10.0, 10.0, 32.0, 32.0), DrawMode::stroke(1.0), Color::GREEN)
+///     .arrow([26.0, 26.0], [80.0, 40.0], 2.0, Color::RED)
+///     .label([10.0, 46.0], "player", Color::WHITE);
+/// # }
+/// ```
+///
+/// Without the `debug-draw` cargo feature, which is on by default, all of this compiles
+/// to nothing, so the calls can be left in release builds.
+#[derive(Debug, Default)]
+#[cfg_attr(not(feature = "debug-draw"), allow(missing_copy_implementations))]
+pub struct DebugDraw {
+    #[cfg(feature = "debug-draw")]
+    mesh: MeshBuilder,
+    #[cfg(feature = "debug-draw")]
+    primitives: usize,
+    #[cfg(feature = "debug-draw")]
+    labels: Vec<(Text, mint::Point2<f32>, Color)>,
+}
+
+#[cfg_attr(not(feature = "debug-draw"), allow(unused_variables))]
+impl DebugDraw {
+    /// Tolerance used for flattening circles, see [`MeshBuilder::circle`](super::MeshBuilder::circle).
+    #[cfg(feature = "debug-draw")]
+    const TOLERANCE: f32 = 0.1;
+
+    #[cfg(feature = "debug-draw")]
+    fn add(&mut self, add: impl FnOnce(&mut MeshBuilder) -> GameResult<&mut MeshBuilder>) {
+        if let Err(e) = add(&mut self.mesh) {
+            warn!("Skipping a debug draw primitive: {e:?}");
+        }
+        self.primitives += 1;
+    }
+
+    /// Draws a line from `from` to `to`.
+    pub fn line(
+        &mut self,
+        from: impl Into<mint::Point2<f32>>,
+        to: impl Into<mint::Point2<f32>>,
+        width: f32,
+        color: Color,
+    ) -> &mut Self {
+        #[cfg(feature = "debug-draw")]
+        self.add(|mesh| mesh.line(&[from.into(), to.into()], width, color));
+        self
+    }
+
+    /// Draws an arrow from `from`, pointing at `to`.
+    pub fn arrow(
+        &mut self,
+        from: impl Into<mint::Point2<f32>>,
+        to: impl Into<mint::Point2<f32>>,
+        width: f32,
+        color: Color,
+    ) -> &mut Self {
+        #[cfg(feature = "debug-draw")]
+        {
+            let (from, to) = (glam::Vec2::from(from.into()), glam::Vec2::from(to.into()));
+            let length = from.distance(to);
+            if length > 0.0 {
+                let direction = (to - from) / length;
+                let head = (width * 4.0).max(8.0).min(length);
+                let base = to - direction * head;
+                let side = direction.perp() * head * 0.5;
+                let head_points = [to, base + side, base - side].map(mint::Point2::from);
+                self.add(|mesh| mesh.line(&[from, base].map(mint::Point2::from), width, color));
+                self.add(|mesh| mesh.triangles(&head_points, color));
+            }
+        }
+        self
+    }
+
+    /// Draws a rectangle.
+    pub fn rect(&mut self, rect: Rect, mode: DrawMode, color: Color) -> &mut Self {
+        #[cfg(feature = "debug-draw")]
+        self.add(|mesh| mesh.rectangle(mode, rect, color));
+        self
+    }
+
+    /// Draws a circle.
+    pub fn circle(
+        &mut self,
+        center: impl Into<mint::Point2<f32>>,
+        radius: f32,
+        mode: DrawMode,
+        color: Color,
+    ) -> &mut Self {
+        #[cfg(feature = "debug-draw")]
+        self.add(|mesh| mesh.circle(mode, center, radius, Self::TOLERANCE, color));
+        self
+    }
+
+    /// Draws a closed polygon.
+    pub fn polygon<P>(&mut self, points: &[P], mode: DrawMode, color: Color) -> &mut Self
+    where
+        P: Into<mint::Point2<f32>> + Clone,
+    {
+        #[cfg(feature = "debug-draw")]
+        self.add(|mesh| mesh.polygon(mode, points, color));
+        self
+    }
+
+    /// Draws a point as a square `size` pixels wide.
+    pub fn point(
+        &mut self,
+        position: impl Into<mint::Point2<f32>>,
+        size: f32,
+        color: Color,
+    ) -> &mut Self {
+        #[cfg(feature = "debug-draw")]
+        {
+            let position = position.into();
+            let rect = Rect::new(position.x - size / 2.0, position.y - size / 2.0, size, size);
+            self.add(|mesh| mesh.rectangle(DrawMode::fill(), rect, color));
+        }
+        self
+    }
+
+    /// Draws text with its top left corner at `position`.
+    pub fn label(
+        &mut self,
+        position: impl Into<mint::Point2<f32>>,
+        text: impl Into<TextFragment>,
+        color: Color,
+    ) -> &mut Self {
+        #[cfg(feature = "debug-draw")]
+        self.labels.push((Text::new(text), position.into(), color));
+        self
+    }
+
+    /// Returns `true` if nothing has been drawn.
+    #[cfg(feature = "debug-draw")]
+    pub fn is_empty(&self) -> bool {
+        self.primitives == 0 && self.labels.is_empty()
+    }
+
+    /// Returns `true` if nothing has been drawn.
+    #[cfg(not(feature = "debug-draw"))]
+    pub fn is_empty(&self) -> bool {
+        true
+    }
+
+    /// Forgets everything drawn so far.
+    pub fn clear(&mut self) {
+        #[cfg(feature = "debug-draw")]
+        {
+            self.mesh = MeshBuilder::new();
+            self.primitives = 0;
+            self.labels.clear();
+        }
+    }
+
+    /// Draws everything onto `canvas`, on top of its other draws, and clears it.
+    #[cfg(feature = "debug-draw")]
+    pub(crate) fn flush(&mut self, canvas: &mut Canvas, gfx: &mut GraphicsContext) {
+        let data = self.mesh.build();
+        if !data.indices.is_empty() {
+            let mesh = gfx.debug_meshes.next(&gfx.wgpu);
+            // a growable mesh always has room
+            mesh.update_wgpu(&gfx.wgpu, data).unwrap();
+            canvas.draw(&*mesh, DrawParam::new().z(ZIndex::MAX));
+        }
+        for (text, position, color) in self.labels.drain(..) {
+            canvas.draw(
+                &text,
+                DrawParam::new().dest(position).color(color).z(ZIndex::MAX),
+            );
+        }
+        self.clear();
+    }
+}
+
+/// The meshes that the debug layers of the canvases finished in the current frame were
+/// uploaded to, kept from frame to frame so their buffers are reused.
+#[cfg(feature = "debug-draw")]
+#[derive(Debug, Default)]
+pub(crate) struct DebugMeshes {
+    meshes: Vec<DynamicMesh>,
+    used: usize,
+}
+
+#[cfg(feature = "debug-draw")]
+impl DebugMeshes {
+    /// Returns a mesh that no other canvas used in the current frame, since a mesh's
+    /// buffers are only read when the frame is submitted.
+    fn next(&mut self, wgpu: &WgpuContext) -> &mut DynamicMesh {
+        if self.used == self.meshes.len() {
+            self.meshes.push(DynamicMesh::new_wgpu(wgpu, 0, 0, true));
+        }
+        self.used += 1;
+        &mut self.meshes[self.used - 1]
+    }
+
+    pub(crate) fn begin_frame(&mut self) {
+        self.used = 0;
+    }
+}
+
+#[cfg(all(test, feature = "debug-draw"))]
+mod tests {
+    use super::*;
+    use crate::{
+        context::headless_ctx_for_tests,
+        graphics::{BlendMode, Canvas},
+    };
+
+    #[test]
+    fn headless_test_debug_draw() {
+        let mut ctx = headless_ctx_for_tests(32, 32);
+        ctx.gfx.begin_frame().unwrap();
+        let mut canvas = Canvas::from_frame(&ctx, Color::BLACK);
+        // The debug layer isn't affected by the canvas's state.
+        canvas.set_blend_mode(BlendMode::MULTIPLY);
+        assert!(canvas.debug_draw().is_empty());
+        let _ = canvas
+            .debug_draw()
+            .rect(Rect::new(2.0, 2.0, 8.0, 8.0), DrawMode::fill(), Color::RED)
+            .line([0.0, 20.0], [32.0, 20.0], 2.0, Color::GREEN)
+            .arrow([0.0, 28.0], [20.0, 28.0], 1.0, Color::BLUE)
+            .circle([24.0, 8.0], 4.0, DrawMode::stroke(1.0), Color::WHITE)
+            .polygon(
+                &[[12.0, 12.0], [16.0, 12.0], [14.0, 16.0]],
+                DrawMode::fill(),
+                Color::WHITE,
+            )
+            .point([30.0, 30.0], 2.0, Color::WHITE)
+            .label([0.0, 22.0], "debug", Color::WHITE);
+        assert!(!canvas.debug_draw().is_empty());
+        let stats = canvas.finish_with_stats(&mut ctx).unwrap();
+        // One draw for all the shapes and one for the labels.
+        assert_eq!(stats.draw_calls, 2);
+        assert_eq!(stats.text_sections, 1);
+        ctx.gfx.end_frame().unwrap();
+
+        let frame = ctx.gfx.frame().to_pixels(&ctx).unwrap();
+        let pixel = |x: usize, y: usize| &frame[(y * 32 + x) * 4..][..4];
+        assert_eq!(pixel(6, 8), [255, 0, 0, 255]);
+        assert_eq!(pixel(16, 20), [0, 255, 0, 255]);
+        assert_eq!(pixel(20, 12), [0, 0, 0, 255]);
+
+        // The next frame reuses the mesh, while a second canvas gets its own.
+        ctx.gfx.begin_frame().unwrap();
+        for _ in 0..2 {
+            let mut canvas = Canvas::from_frame(&ctx, None);
+            let _ = canvas.debug_draw().point([4.0, 4.0], 2.0, Color::WHITE);
+            canvas.finish(&mut ctx).unwrap();
+        }
+        ctx.gfx.end_frame().unwrap();
+        assert_eq!(ctx.gfx.debug_meshes.meshes.len(), 2);
+    }
+}
@@ -0,0 +1,305 @@
+use super::{
+    context::GraphicsContext, gpu::arc::ArcBuffer, Canvas, DrawParam, Drawable, Mesh, MeshData,
+    Rect, Vertex, WgpuContext,
+};
+use crate::{context::Has, GameError, GameResult};
+use std::marker::PhantomData;
+
+/// A [`Mesh`] whose vertices and indices can be replaced without creating new GPU buffers,
+/// for geometry that changes every frame, like trails or deformable terrain.
+///
+/// [`DynamicMesh::update`] writes the new data into the existing buffers through the
+/// queue. When it doesn't fit, a growable mesh (see [`DynamicMesh::new`]) reallocates
+/// with at least double the capacity, while a fixed one (see
+/// [`DynamicMesh::with_fixed_capacity`]) returns an error.
+///
+/// Like [`InstanceArray`](super::InstanceArray), the buffers are only read when the frame is
+/// submitted, so every draw of a `DynamicMesh` within one frame uses the data of its last
+/// update in that frame.
+///
+/// ```rust,no_run
+/// # use ggez::graphics::*;
+/// # fn t(ctx: &mut ggez::Context, trail: &mut DynamicMesh, points: &[[f32; 2]]) -> ggez::GameResult {
+/// trail.update(ctx, MeshBuilder::new().line(points, 4.0, Color::WHITE)?.build())?;
+/// let mut canvas = Canvas::from_frame(ctx, Color::BLACK);
+/// canvas.draw(trail, DrawParam::new());
+/// canvas.finish(ctx)
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct DynamicMesh {
+    mesh: Mesh,
+    vertex_capacity: usize,
+    index_capacity: usize,
+    growable: bool,
+}
+
+impl DynamicMesh {
+    /// Creates a new, empty [`DynamicMesh`] with room for `vertex_capacity` vertices and
+    /// `index_capacity` indices, growing when an update needs more.
+    pub fn new(
+        gfx: &impl Has<GraphicsContext>,
+        vertex_capacity: usize,
+        index_capacity: usize,
+    ) -> Self {
+        Self::new_wgpu(&gfx.retrieve().wgpu, vertex_capacity, index_capacity, true)
+    }
+
+    /// Creates a new, empty [`DynamicMesh`] with room for `vertex_capacity` vertices and
+    /// `index_capacity` indices, which [`DynamicMesh::update`] won't exceed.
+    pub fn with_fixed_capacity(
+        gfx: &impl Has<GraphicsContext>,
+        vertex_capacity: usize,
+        index_capacity: usize,
+    ) -> Self {
+        Self::new_wgpu(&gfx.retrieve().wgpu, vertex_capacity, index_capacity, false)
+    }
+
+    /// Creates a new growable [`DynamicMesh`] holding `data`, with exactly enough capacity for it.
+    pub fn from_data(gfx: &impl Has<GraphicsContext>, data: MeshData) -> Self {
+        let mut mesh = Self::new(gfx, data.vertices.len(), data.indices.len());
+        // there's always room for the data the mesh was sized for
+        mesh.update(gfx, data).unwrap();
+        mesh
+    }
+
+    pub(crate) fn new_wgpu(
+        wgpu: &WgpuContext,
+        vertex_capacity: usize,
+        index_capacity: usize,
+        growable: bool,
+    ) -> Self {
+        // empty buffers can't be bound
+        let vertex_capacity = vertex_capacity.max(1);
+        let index_capacity = index_capacity.max(1);
+
+        DynamicMesh {
+            mesh: Mesh {
+                verts: Self::create_buffer::<Vertex>(
+                    wgpu,
+                    vertex_capacity,
+                    wgpu::BufferUsages::VERTEX,
+                ),
+                inds: Self::create_buffer::<u32>(wgpu, index_capacity, wgpu::BufferUsages::INDEX),
+                vertex_count: 0,
+                index_count: 0,
+                bounds: Rect::default(),
+                vertex: PhantomData,
+            },
+            vertex_capacity,
+            index_capacity,
+            growable,
+        }
+    }
+
+    /// Replaces the vertices and indices of this mesh.
+    ///
+    /// The data is written into the existing buffers if it fits; otherwise a growable mesh
+    /// reallocates them, and a fixed one returns an error, leaving the mesh unchanged.
+    pub fn update(&mut self, gfx: &impl Has<GraphicsContext>, data: MeshData) -> GameResult {
+        self.update_wgpu(&gfx.retrieve().wgpu, data)
+    }
+
+    pub(crate) fn update_wgpu(&mut self, wgpu: &WgpuContext, data: MeshData) -> GameResult {
+        let (vertices, indices) = (data.vertices.len(), data.indices.len());
+
+        if vertices > self.vertex_capacity || indices > self.index_capacity {
+            if !self.growable {
+                return Err(GameError::RenderError(format!(
+                    "mesh data with {vertices} vertices and {indices} indices doesn't fit in a DynamicMesh with a fixed capacity of {} vertices and {} indices",
+                    self.vertex_capacity, self.index_capacity
+                )));
+            }
+
+            if vertices > self.vertex_capacity {
+                self.vertex_capacity = vertices.max(self.vertex_capacity * 2);
+                self.mesh.verts = Self::create_buffer::<Vertex>(
+                    wgpu,
+                    self.vertex_capacity,
+                    wgpu::BufferUsages::VERTEX,
+                );
+            }
+            if indices > self.index_capacity {
+                self.index_capacity = indices.max(self.index_capacity * 2);
+                self.mesh.inds = Self::create_buffer::<u32>(
+                    wgpu,
+                    self.index_capacity,
+                    wgpu::BufferUsages::INDEX,
+                );
+            }
+        }
+
+        if vertices > 0 {
+            wgpu.queue
+                .write_buffer(&self.mesh.verts, 0, bytemuck::cast_slice(data.vertices));
+        }
+        if indices > 0 {
+            wgpu.queue
+                .write_buffer(&self.mesh.inds, 0, bytemuck::cast_slice(data.indices));
+        }
+
+        self.mesh.vertex_count = vertices;
+        self.mesh.index_count = indices;
+        self.mesh.bounds = if vertices > 0 {
+            Mesh::bounds(data.vertices)
+        } else {
+            Rect::default()
+        };
+
+        Ok(())
+    }
+
+    /// Empties the mesh, keeping its buffers.
+    pub fn clear(&mut self) {
+        self.mesh.vertex_count = 0;
+        self.mesh.index_count = 0;
+        self.mesh.bounds = Rect::default();
+    }
+
+    /// Returns the current contents as a [`Mesh`], e.g. for
+    /// [`Canvas::draw_textured_mesh`](super::Canvas::draw_textured_mesh).
+    ///
+    /// A clone of it shares the buffers of this one, so it sees later updates that don't
+    /// reallocate, while keeping the vertex and index count of the time it was cloned.
+    #[inline]
+    pub fn mesh(&self) -> &Mesh {
+        &self.mesh
+    }
+
+    /// Returns the number of vertices in this mesh.
+    #[inline]
+    pub fn vertex_count(&self) -> usize {
+        self.mesh.vertex_count
+    }
+
+    /// Returns the number of indices in this mesh.
+    #[inline]
+    pub fn index_count(&self) -> usize {
+        self.mesh.index_count
+    }
+
+    /// Returns the number of vertices this mesh can hold without reallocating.
+    #[inline]
+    pub fn vertex_capacity(&self) -> usize {
+        self.vertex_capacity
+    }
+
+    /// Returns the number of indices this mesh can hold without reallocating.
+    #[inline]
+    pub fn index_capacity(&self) -> usize {
+        self.index_capacity
+    }
+
+    /// Returns whether this mesh reallocates when an update doesn't fit.
+    #[inline]
+    pub fn is_growable(&self) -> bool {
+        self.growable
+    }
+
+    fn create_buffer<T>(
+        wgpu: &WgpuContext,
+        capacity: usize,
+        usage: wgpu::BufferUsages,
+    ) -> ArcBuffer {
+        ArcBuffer::new(wgpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: (std::mem::size_of::<T>() * capacity) as u64,
+            usage: usage | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        }))
+    }
+}
+
+impl Drawable for DynamicMesh {
+    fn draw(&self, canvas: &mut Canvas, param: impl Into<DrawParam>) {
+        if self.mesh.index_count > 0 {
+            self.mesh.draw(canvas, param);
+        }
+    }
+
+    fn dimensions(&self, gfx: &impl Has<GraphicsContext>) -> Option<Rect> {
+        if self.mesh.vertex_count == 0 {
+            return None;
+        }
+        self.mesh.dimensions(gfx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        context::headless_ctx_for_tests,
+        graphics::{Color, DrawMode, MeshBuilder},
+    };
+
+    #[test]
+    fn headless_test_dynamic_mesh() {
+        let mut ctx = headless_ctx_for_tests(16, 16);
+
+        let mut mesh = DynamicMesh::with_fixed_capacity(&ctx, 4, 6);
+        assert!(mesh.dimensions(&ctx).is_none());
+
+        let mut builder = MeshBuilder::new();
+        let _ = builder
+            .rectangle(DrawMode::fill(), Rect::new(0.0, 0.0, 8.0, 16.0), Color::RED)
+            .unwrap();
+        mesh.update(&ctx, builder.build()).unwrap();
+        let verts = mesh.mesh().verts.id();
+        let inds = mesh.mesh().inds.id();
+
+        // the same buffers are reused when the data fits
+        let mut builder = MeshBuilder::new();
+        let _ = builder
+            .rectangle(
+                DrawMode::fill(),
+                Rect::new(8.0, 0.0, 8.0, 16.0),
+                Color::GREEN,
+            )
+            .unwrap();
+        mesh.update(&ctx, builder.build()).unwrap();
+        assert_eq!(mesh.mesh().verts.id(), verts);
+        assert_eq!(mesh.mesh().inds.id(), inds);
+        assert_eq!(mesh.dimensions(&ctx), Some(Rect::new(8.0, 0.0, 8.0, 16.0)));
+
+        ctx.gfx.begin_frame().unwrap();
+        let mut canvas = Canvas::from_frame(&ctx, Color::BLACK);
+        canvas.draw(&mesh, DrawParam::new());
+        canvas.finish(&mut ctx).unwrap();
+        ctx.gfx.end_frame().unwrap();
+
+        let frame = ctx.gfx.frame().to_pixels(&ctx).unwrap();
+        let pixel = |x: usize, y: usize| &frame[(y * 16 + x) * 4..][..4];
+        assert_eq!(pixel(4, 8), [0, 0, 0, 255]);
+        assert_eq!(pixel(12, 8), [0, 255, 0, 255]);
+
+        // a fixed mesh can't grow
+        let _ = builder
+            .rectangle(DrawMode::fill(), Rect::new(0.0, 0.0, 8.0, 16.0), Color::RED)
+            .unwrap();
+        assert!(mesh.update(&ctx, builder.build()).is_err());
+        assert_eq!(mesh.vertex_count(), 4);
+
+        // a growable one reallocates
+        let mut mesh = DynamicMesh::new(&ctx, 4, 6);
+        mesh.update(&ctx, builder.build()).unwrap();
+        assert_eq!((mesh.vertex_count(), mesh.index_count()), (8, 12));
+        assert_eq!((mesh.vertex_capacity(), mesh.index_capacity()), (8, 12));
+
+        mesh.clear();
+        assert_eq!(mesh.index_count(), 0);
+        assert_eq!(mesh.index_capacity(), 12);
+
+        // updating with no data is the same as clearing
+        mesh.update(
+            &ctx,
+            MeshData {
+                vertices: &[],
+                indices: &[],
+            },
+        )
+        .unwrap();
+        assert_eq!(mesh.mesh().bounds, Rect::default());
+        assert!(mesh.dimensions(&ctx).is_none());
+    }
+}
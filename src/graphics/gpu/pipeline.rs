@@ -20,7 +20,8 @@ pub struct RenderPipelineInfo {
 /// Caches both the pipeline *and* the pipeline layout.
 #[derive(Debug)]
 pub struct PipelineCache {
-    pipelines: HashMap<RenderPipelineInfo, ArcRenderPipeline>,
+    // keyed by the layout's ID as well, since the same shaders can be used with different bind groups
+    pipelines: HashMap<(u64, RenderPipelineInfo), ArcRenderPipeline>,
     layouts: HashMap<u64, ArcPipelineLayout>,
 }
 
@@ -35,18 +36,18 @@ impl PipelineCache {
     pub fn render_pipeline(
         &mut self,
         device: &wgpu::Device,
-        layout: &wgpu::PipelineLayout,
+        layout: &ArcPipelineLayout,
         info: RenderPipelineInfo,
     ) -> ArcRenderPipeline {
         let vertex_buffers = [info.vertex_layout.clone()];
 
         self.pipelines
-            .entry(info.clone())
+            .entry((layout.id(), info.clone()))
             .or_insert_with(|| {
                 ArcRenderPipeline::new(device.create_render_pipeline(
                     &wgpu::RenderPipelineDescriptor {
                         label: None,
-                        layout: Some(layout),
+                        layout: Some(layout.as_ref()),
                         vertex: wgpu::VertexState {
                             module: &info.vs,
                             entry_point: &info.vs_entry,
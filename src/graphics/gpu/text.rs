@@ -3,7 +3,7 @@ use super::{
     bind_group::BindGroupBuilder,
     growing::GrowingBufferArena,
 };
-use crate::graphics::{context::FrameArenas, LinearColor};
+use crate::graphics::{context::FrameArenas, CanvasStats, LinearColor};
 use crevice::std140::AsStd140;
 use glyph_brush::{GlyphBrush, GlyphBrushBuilder};
 use ordered_float::OrderedFloat;
@@ -101,9 +101,11 @@ impl TextRenderer {
         queue: &wgpu::Queue,
         arenas: &'a FrameArenas,
         pass: &mut wgpu::RenderPass<'a>,
+        stats: &mut CanvasStats,
     ) {
         let res = self.glyph_brush.borrow_mut().process_queued(
             |rect, pixels| {
+                stats.glyph_uploads += 1;
                 queue.write_texture(
                     wgpu::ImageCopyTexture {
                         texture: &self.cache,
@@ -168,6 +170,8 @@ impl TextRenderer {
                 // Also note that vertex data is stepped PER INSTANCE.
                 // Therefore we only store ONE VERTEX for ONE GLYPH (and in the vertex shader we generate the quad vertices on the fly).
                 pass.draw(0..4, 0..verts.len() as u32);
+                stats.draw_calls += 1;
+                stats.instances += verts.len() as u32;
             }
             Err(glyph_brush::BrushError::TextureTooSmall { suggested }) => {
                 // increase texture size as recommended by glyph_brush
@@ -206,7 +210,7 @@ impl TextRenderer {
                         entries: cache_bind.entries(),
                     }));
 
-                self.draw_queued(device, queue, arenas, pass)
+                self.draw_queued(device, queue, arenas, pass, stats)
             }
             _ => unreachable!(),
         }
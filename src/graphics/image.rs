@@ -4,9 +4,10 @@ use super::{
         arc::{ArcBindGroup, ArcSampler, ArcTexture, ArcTextureView},
         bind_group::BindGroupBuilder,
     },
+    internal_canvas::MeshView,
     Canvas, Color, Draw, DrawParam, Drawable, Rect, WgpuContext,
 };
-use crate::{context::Has, Context, GameError, GameResult};
+use crate::{context::Has, filesystem::ReloadAsset, GameError, GameResult};
 use image::ImageEncoder;
 use std::{
     collections::BTreeMap,
@@ -33,6 +34,8 @@ pub struct Image {
     pub(crate) height: u32,
     pub(crate) samples: u32,
     pub(crate) cache: Arc<RwLock<BTreeMap<u64, ArcBindGroup>>>,
+    // What the image was reloaded as, for images loaded while asset hot reloading is on.
+    pub(crate) watched: Option<Arc<WatchedImage>>,
 }
 
 impl Image {
@@ -111,42 +114,54 @@ impl Image {
                 | wgpu::TextureUsages::TEXTURE_BINDING
                 | wgpu::TextureUsages::COPY_SRC,
         );
+        image.write_pixels(wgpu, pixels);
+        image
+    }
 
+    fn write_pixels(&self, wgpu: &WgpuContext, pixels: &[u8]) {
         wgpu.queue.write_texture(
-            image.texture.as_image_copy(),
+            self.texture.as_image_copy(),
             pixels,
             wgpu::ImageDataLayout {
                 offset: 0,
-                bytes_per_row: Some(format.block_size(None).unwrap() * width), // Unwrap since it only fails with depth formats.
+                bytes_per_row: Some(self.format.block_size(None).unwrap() * self.width), // Unwrap since it only fails with depth formats.
                 rows_per_image: None,
             },
             wgpu::Extent3d {
-                width,
-                height,
+                width: self.width,
+                height: self.height,
                 depth_or_array_layers: 1,
             },
         );
-
-        image
     }
 
     /// Creates a new image initialized with pixel data loaded from a given path as an
     /// encoded image `Read` (e.g. PNG or JPEG).
+    ///
+    /// With [asset hot reloading](crate::filesystem::Filesystem::set_asset_hot_reload) on, the
+    /// image is reloaded when the file changes.
     #[allow(unused_results)]
     pub fn from_path(gfx: &impl Has<GraphicsContext>, path: impl AsRef<Path>) -> GameResult<Self> {
         let gfx = gfx.retrieve();
 
         let mut encoded = Vec::new();
-        gfx.fs.open(path)?.read_to_end(&mut encoded)?;
-
-        Self::from_bytes(gfx, encoded.as_slice())
+        gfx.fs.open(&path)?.read_to_end(&mut encoded)?;
+
+        let mut image = Self::from_bytes(gfx, encoded.as_slice())?;
+        if gfx.fs.is_asset_hot_reload_enabled() {
+            let watched = Arc::new(WatchedImage {
+                image: image.clone(),
+                reloaded: RwLock::new(None),
+            });
+            gfx.fs.watch_asset(path, Arc::downgrade(&watched) as _);
+            image.watched = Some(watched);
+        }
+        Ok(image)
     }
 
     /// Creates a new image initialized with pixel data from a given encoded image (e.g. PNG or JPEG)
     pub fn from_bytes(gfx: &impl Has<GraphicsContext>, encoded: &[u8]) -> Result<Image, GameError> {
-        let decoded = image::load_from_memory(encoded)
-            .map_err(|_| GameError::ResourceLoadError(String::from("failed to load image")))?;
-        let rgba8 = decoded.to_rgba8();
+        let rgba8 = Self::decode(encoded)?;
         let (width, height) = (rgba8.width(), rgba8.height());
 
         Ok(Self::from_pixels(
@@ -158,7 +173,13 @@ impl Image {
         ))
     }
 
-    fn new(
+    fn decode(encoded: &[u8]) -> GameResult<image::RgbaImage> {
+        let decoded = image::load_from_memory(encoded)
+            .map_err(|_| GameError::ResourceLoadError(String::from("failed to load image")))?;
+        Ok(decoded.to_rgba8())
+    }
+
+    pub(crate) fn new(
         wgpu: &WgpuContext,
         format: ImageFormat,
         width: u32,
@@ -205,10 +226,24 @@ impl Image {
             height,
             samples,
             cache: Arc::new(RwLock::new(BTreeMap::default())),
+            watched: None,
         }
     }
 
+    /// Returns the image this one was reloaded as, if its file changed size since it was loaded.
+    fn reloaded(&self) -> Option<Image> {
+        self.watched.as_ref()?.reloaded.read().ok()?.clone()
+    }
+
+    /// Returns this image with the texture it was last (re)loaded with.
+    pub(crate) fn current(&self) -> Image {
+        self.reloaded().unwrap_or_else(|| self.clone())
+    }
+
     /// Returns the underlying [`wgpu::Texture`] and [`wgpu::TextureView`] for this [`Image`].
+    ///
+    /// If the image was [hot reloaded](crate::filesystem::Filesystem::set_asset_hot_reload)
+    /// from a file with a different size, these are of the image as it was loaded.
     #[inline]
     pub fn wgpu(&self) -> (&wgpu::Texture, &wgpu::TextureView) {
         (&self.texture, &self.view)
@@ -220,6 +255,9 @@ impl Image {
     /// **This is a very expensive operation - call sparingly.**
     pub fn to_pixels(&self, gfx: &impl Has<GraphicsContext>) -> GameResult<Vec<u8>> {
         let gfx = gfx.retrieve();
+        if let Some(image) = self.reloaded() {
+            return image.to_pixels(gfx);
+        }
         if self.samples > 1 {
             return Err(GameError::RenderError(String::from(
                 "cannot read the pixels of a multisampled image; resolve this image with a canvas",
@@ -228,9 +266,16 @@ impl Image {
 
         let block_size = u64::from(self.format.block_size(None).unwrap()); // Unwrap since it only fails with depth formats.
 
+        // Rows copied out of a texture have to be aligned, so pad them and strip the padding afterwards.
+        let row_size = block_size * u64::from(self.width);
+        let align = u64::from(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        // `u64::div_ceil` needs a newer rustc than ggez supports.
+        #[allow(clippy::manual_div_ceil)]
+        let padded_row_size = (row_size + align - 1) / align * align;
+
         let buffer = gfx.wgpu.device.create_buffer(&wgpu::BufferDescriptor {
             label: None,
-            size: block_size * u64::from(self.width) * u64::from(self.height),
+            size: padded_row_size * u64::from(self.height),
             usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
             mapped_at_creation: false,
         });
@@ -246,7 +291,7 @@ impl Image {
                     buffer: &buffer,
                     layout: wgpu::ImageDataLayout {
                         offset: 0,
-                        bytes_per_row: Some(block_size as u32 * self.width),
+                        bytes_per_row: Some(padded_row_size as u32),
                         rows_per_image: None,
                     },
                 },
@@ -272,23 +317,39 @@ impl Image {
             .expect("All senders dropped, this should not be possible.");
         map_result?;
 
-        let out = buffer.slice(..).get_mapped_range().to_vec();
+        let mapped = buffer.slice(..).get_mapped_range();
+        let out = mapped
+            .chunks_exact(padded_row_size as usize)
+            .flat_map(|row| &row[..row_size as usize])
+            .copied()
+            .collect();
         Ok(out)
     }
 
     /// Encodes the `ImageView` to the given file format and return the encoded bytes.
     ///
+    /// BGRA images (such as the frame of most windows) are converted to RGBA before encoding.
+    ///
     /// **This is a very expensive operation - call sparingly.**
     pub fn encode(
         &self,
-        ctx: &Context,
+        gfx: &impl Has<GraphicsContext>,
         format: ImageEncodingFormat,
         path: impl AsRef<std::path::Path>,
     ) -> GameResult {
-        let color = match self.format {
-            ImageFormat::Rgba8Unorm | ImageFormat::Rgba8UnormSrgb => ::image::ColorType::Rgba8,
-            ImageFormat::R8Unorm => ::image::ColorType::L8,
-            ImageFormat::R16Unorm => ::image::ColorType::L16,
+        let gfx = gfx.retrieve();
+        if let Some(image) = self.reloaded() {
+            return image.encode(gfx, format, path);
+        }
+        let (color, swizzle) = match self.format {
+            ImageFormat::Rgba8Unorm | ImageFormat::Rgba8UnormSrgb => {
+                (::image::ColorType::Rgba8, false)
+            }
+            ImageFormat::Bgra8Unorm | ImageFormat::Bgra8UnormSrgb => {
+                (::image::ColorType::Rgba8, true)
+            }
+            ImageFormat::R8Unorm => (::image::ColorType::L8, false),
+            ImageFormat::R16Unorm => (::image::ColorType::L16, false),
             format => {
                 return Err(GameError::RenderError(format!(
                     "cannot ImageView::encode for the {format:#?} GPU image format"
@@ -296,8 +357,11 @@ impl Image {
             }
         };
 
-        let pixels = self.to_pixels(ctx)?;
-        let f = ctx.fs.create(path)?;
+        let mut pixels = self.to_pixels(gfx)?;
+        if swizzle {
+            pixels.chunks_exact_mut(4).for_each(|p| p.swap(0, 2));
+        }
+        let f = gfx.fs.create(path)?;
         let writer = &mut std::io::BufWriter::new(f);
 
         match format {
@@ -328,22 +392,23 @@ impl Image {
     /// Returns the width (in pixels) of the image.
     #[inline]
     pub fn width(&self) -> u32 {
-        self.width
+        self.reloaded().map_or(self.width, |image| image.width)
     }
 
     /// Returns the height (in pixels) of the image.
     #[inline]
     pub fn height(&self) -> u32 {
-        self.height
+        self.reloaded().map_or(self.height, |image| image.height)
     }
 
     /// Helper function that calculates a sub-rectangle of this image in UV coordinates, given pixel coordinates.
     pub fn uv_rect(&self, x: u32, y: u32, w: u32, h: u32) -> Rect {
+        let (width, height) = (self.width(), self.height());
         Rect {
-            x: x as f32 / self.width as f32,
-            y: y as f32 / self.height as f32,
-            w: w as f32 / self.width as f32,
-            h: h as f32 / self.height as f32,
+            x: x as f32 / width as f32,
+            y: y as f32 / height as f32,
+            w: w as f32 / width as f32,
+            h: h as f32 / height as f32,
         }
     }
 
@@ -371,11 +436,41 @@ impl Image {
     }
 }
 
+/// The file an [`Image`] was loaded from, see [`Image::from_path`].
+#[derive(Debug)]
+pub(crate) struct WatchedImage {
+    image: Image,
+    reloaded: RwLock<Option<Image>>,
+}
+
+impl ReloadAsset for WatchedImage {
+    fn reload(&self, gfx: &GraphicsContext, bytes: Vec<u8>) -> GameResult {
+        let rgba8 = Image::decode(&bytes)?;
+        let (width, height) = (rgba8.width(), rgba8.height());
+
+        // Textures can't be resized, so only new sizes need a new one.
+        let reloaded = if (width, height) == (self.image.width, self.image.height) {
+            self.image.write_pixels(&gfx.wgpu, rgba8.as_ref());
+            None
+        } else {
+            Some(Image::from_pixels_wgpu(
+                &gfx.wgpu,
+                rgba8.as_ref(),
+                ImageFormat::Rgba8UnormSrgb,
+                width,
+                height,
+            ))
+        };
+        *self.reloaded.write().map_err(|_| GameError::LockError)? = reloaded;
+        Ok(())
+    }
+}
+
 impl Drawable for Image {
     fn draw(&self, canvas: &mut Canvas, param: impl Into<DrawParam>) {
         canvas.push_draw(
             Draw::Mesh {
-                mesh: canvas.default_resources().mesh.clone(),
+                mesh: MeshView::from_mesh(&canvas.default_resources().mesh),
                 image: self.clone(),
                 scale: true,
             },
@@ -442,7 +537,7 @@ impl ScreenImage {
 
     fn size(gfx: &impl Has<GraphicsContext>, (width, height): (f32, f32)) -> (u32, u32) {
         let gfx = gfx.retrieve();
-        let size = gfx.window.inner_size();
+        let size = gfx.inner_size();
         let width = (size.width as f32 * width) as u32;
         let height = (size.height as f32 * height) as u32;
         (width.max(1), height.max(1))
@@ -4,11 +4,12 @@ use super::{
     context::GraphicsContext,
     draw::{DrawParam, DrawUniforms, Std140DrawUniforms},
     gpu::arc::{ArcBindGroup, ArcBindGroupLayout, ArcBuffer},
-    internal_canvas::InstanceArrayView,
+    internal_canvas::{InstanceArrayView, MeshView},
     transform_rect, Canvas, Draw, Drawable, Image, Mesh, Rect, WgpuContext,
 };
 use crevice::std140::AsStd140;
 use std::{
+    borrow::Cow,
     collections::BTreeMap,
     sync::{
         atomic::{AtomicBool, AtomicUsize, Ordering::SeqCst},
@@ -18,13 +19,40 @@ use std::{
 
 const DEFAULT_CAPACITY: usize = 16;
 
+/// Rounds `size` up to a multiple of [`wgpu::COPY_BUFFER_ALIGNMENT`], which buffer writes
+/// have to be, e.g. for an odd number of `u16`s.
+fn padded_size(size: u64) -> u64 {
+    let align = wgpu::COPY_BUFFER_ALIGNMENT;
+    (size + align - 1) & !(align - 1)
+}
+
 /// Array of instances for fast rendering of many meshes.
 ///
 /// Traditionally known as a "batch".
+///
+/// Besides a [`DrawParam`], each instance can carry data of type `T` for custom vertex shaders,
+/// for effects like a per-sprite dissolve amount or palette index (see
+/// [`InstanceArray::new_with_data`]). The data is bound as a storage buffer next to the
+/// instances, and the shader indexes it like them:
+///
+/// ```wgsl
+/// struct Dissolve {
+///     amount: f32,
+/// }
+///
+/// @group(2) @binding(2)
+/// var<storage, read> data: array<Dissolve>;
+///
+/// // in vs_main, with `index` as in the built-in `instance.wgsl`:
+/// var dissolve = data[index];
+/// ```
+///
+/// `T` must have the same layout as the WGSL struct, which may need explicit padding.
 #[derive(Debug)]
-pub struct InstanceArray {
+pub struct InstanceArray<T: bytemuck::Pod = ()> {
     pub(crate) buffer: Mutex<ArcBuffer>,
     pub(crate) indices: Mutex<ArcBuffer>,
+    pub(crate) data_buffer: Mutex<ArcBuffer>,
     pub(crate) bind_group: Mutex<ArcBindGroup>,
     pub(crate) bind_layout: ArcBindGroupLayout,
     pub(crate) image: Image,
@@ -33,6 +61,7 @@ pub struct InstanceArray {
     capacity: AtomicUsize,
     uniforms: Vec<Std140DrawUniforms>,
     params: Vec<DrawParam>,
+    data: Vec<T>,
 }
 
 impl InstanceArray {
@@ -44,7 +73,7 @@ impl InstanceArray {
     /// This constructor is `unordered` meaning instances will be drawn by their push/index order. Use [`InstanceArray::new_ordered`] to order by z-value.
     pub fn new(gfx: &impl Has<GraphicsContext>, image: impl Into<Option<Image>>) -> Self {
         let gfx = gfx.retrieve();
-        InstanceArray::new_wgpu(
+        Self::new_wgpu(
             &gfx.wgpu,
             gfx.instance_bind_layout.clone(),
             image.into().unwrap_or_else(|| gfx.white_image.clone()),
@@ -58,7 +87,38 @@ impl InstanceArray {
     /// This constructor is `ordered` meaning instances will be drawn by their z-value at a slight performance cost. Use [`InstanceArray::new`] to order by index.
     pub fn new_ordered(gfx: &impl Has<GraphicsContext>, image: impl Into<Option<Image>>) -> Self {
         let gfx = gfx.retrieve();
-        InstanceArray::new_wgpu(
+        Self::new_wgpu(
+            &gfx.wgpu,
+            gfx.instance_bind_layout.clone(),
+            image.into().unwrap_or_else(|| gfx.white_image.clone()),
+            DEFAULT_CAPACITY,
+            true,
+        )
+    }
+}
+
+impl<T: bytemuck::Pod> InstanceArray<T> {
+    /// Creates a new [`InstanceArray`] like [`InstanceArray::new`], whose instances also carry
+    /// data of type `T` for custom vertex shaders.
+    pub fn new_with_data(gfx: &impl Has<GraphicsContext>, image: impl Into<Option<Image>>) -> Self {
+        let gfx = gfx.retrieve();
+        Self::new_wgpu(
+            &gfx.wgpu,
+            gfx.instance_bind_layout.clone(),
+            image.into().unwrap_or_else(|| gfx.white_image.clone()),
+            DEFAULT_CAPACITY,
+            false,
+        )
+    }
+
+    /// Creates a new [`InstanceArray`] like [`InstanceArray::new_ordered`], whose instances also
+    /// carry data of type `T` for custom vertex shaders.
+    pub fn new_ordered_with_data(
+        gfx: &impl Has<GraphicsContext>,
+        image: impl Into<Option<Image>>,
+    ) -> Self {
+        let gfx = gfx.retrieve();
+        Self::new_wgpu(
             &gfx.wgpu,
             gfx.instance_bind_layout.clone(),
             image.into().unwrap_or_else(|| gfx.white_image.clone()),
@@ -98,6 +158,16 @@ impl InstanceArray {
             mapped_at_creation: false,
         }));
 
+        let data_buffer = ArcBuffer::new(wgpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            // min for layout, if there's no data
+            size: padded_size(std::mem::size_of::<T>() as u64 * capacity as u64).max(4),
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        }));
+
         let bind_group = BindGroupBuilder::new()
             .buffer(
                 &buffer,
@@ -114,6 +184,14 @@ impl InstanceArray {
                 wgpu::BufferBindingType::Storage { read_only: true },
                 false,
                 None,
+            )
+            .buffer(
+                &data_buffer,
+                0,
+                wgpu::ShaderStages::VERTEX,
+                wgpu::BufferBindingType::Storage { read_only: true },
+                false,
+                None,
             );
         let bind_group =
             ArcBindGroup::new(wgpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -124,10 +202,12 @@ impl InstanceArray {
 
         let uniforms = Vec::with_capacity(capacity);
         let params = Vec::with_capacity(capacity);
+        let data = Vec::with_capacity(capacity);
 
         InstanceArray {
             buffer: Mutex::new(buffer),
             indices: Mutex::new(indices),
+            data_buffer: Mutex::new(data_buffer),
             bind_group: Mutex::new(bind_group),
             bind_layout,
             image,
@@ -136,14 +216,26 @@ impl InstanceArray {
             capacity: AtomicUsize::new(capacity),
             uniforms,
             params,
+            data,
         }
     }
 
     /// Resets all the instance data to a set of `DrawParam`.
+    ///
+    /// The data of each instance is zeroed, see [`InstanceArray::set_with_data`].
     pub fn set(&mut self, instances: impl IntoIterator<Item = DrawParam>) {
+        self.set_with_data(instances.into_iter().map(|x| (x, T::zeroed())));
+    }
+
+    /// Resets all the instance data to a set of `DrawParam`s along with the data of each instance.
+    pub fn set_with_data(&mut self, instances: impl IntoIterator<Item = (DrawParam, T)>) {
         self.dirty.store(true, SeqCst);
         self.params.clear();
-        self.params.extend(instances);
+        self.data.clear();
+        for (param, data) in instances {
+            self.params.push(param);
+            self.data.push(data);
+        }
         self.uniforms.clear();
         self.uniforms.extend(
             self.params
@@ -153,14 +245,24 @@ impl InstanceArray {
     }
 
     /// Pushes a new instance onto the end.
+    ///
+    /// Its data is zeroed, see [`InstanceArray::push_with_data`].
     pub fn push(&mut self, instance: DrawParam) {
+        self.push_with_data(instance, T::zeroed());
+    }
+
+    /// Pushes a new instance with its data onto the end.
+    pub fn push_with_data(&mut self, instance: DrawParam, data: T) {
         self.dirty.store(true, SeqCst);
         self.uniforms
             .push(DrawUniforms::from_param(&instance, None).as_std140());
         self.params.push(instance);
+        self.data.push(data);
     }
 
     /// Updates an existing instance at a given index, if it is valid.
+    ///
+    /// Its data is left as it is, see [`InstanceArray::update_with_data`].
     pub fn update(&mut self, index: u32, instance: DrawParam) {
         if let Some((uniform, param)) = self
             .uniforms
@@ -173,11 +275,20 @@ impl InstanceArray {
         }
     }
 
+    /// Updates an existing instance and its data at a given index, if it is valid.
+    pub fn update_with_data(&mut self, index: u32, instance: DrawParam, data: T) {
+        if let Some(x) = self.data.get_mut(index as usize) {
+            *x = data;
+            self.update(index, instance);
+        }
+    }
+
     /// Clears all instance data.
     pub fn clear(&mut self) {
         // don't need to set dirty here
         self.uniforms.clear();
         self.params.clear();
+        self.data.clear();
     }
 
     /// Returns whether the instance data has been changed without being flushed (i.e., uploaded to the GPU).
@@ -192,6 +303,13 @@ impl InstanceArray {
         &self.params
     }
 
+    /// Returns an immutable slice of the data of all the instances in this [`InstanceArray`],
+    /// in the same order as [`InstanceArray::instances`].
+    #[inline]
+    pub fn data(&self) -> &[T] {
+        &self.data
+    }
+
     pub(crate) fn flush_wgpu(&self, wgpu: &WgpuContext) -> GameResult {
         if !self.dirty.load(SeqCst) {
             return Ok(());
@@ -201,7 +319,7 @@ impl InstanceArray {
 
         let len = self.uniforms.len();
         //if len > self.capacity.load(SeqCst) {
-        let mut resized = InstanceArray::new_wgpu(
+        let mut resized = Self::new_wgpu(
             wgpu,
             self.bind_layout.clone(),
             self.image.clone(),
@@ -212,6 +330,8 @@ impl InstanceArray {
             resized.buffer.get_mut().unwrap().clone();
         *self.indices.lock().map_err(|_| GameError::LockError)? =
             resized.indices.get_mut().unwrap().clone();
+        *self.data_buffer.lock().map_err(|_| GameError::LockError)? =
+            resized.data_buffer.get_mut().unwrap().clone();
         *self.bind_group.lock().map_err(|_| GameError::LockError)? =
             resized.bind_group.get_mut().unwrap().clone();
         self.capacity.store(len, SeqCst);
@@ -223,6 +343,16 @@ impl InstanceArray {
             bytemuck::cast_slice(self.uniforms.as_slice()),
         );
 
+        if std::mem::size_of::<T>() > 0 {
+            let mut data = Cow::Borrowed(bytemuck::cast_slice::<T, u8>(self.data.as_slice()));
+            let size = padded_size(data.len() as u64) as usize;
+            if data.len() != size {
+                data.to_mut().resize(size, 0);
+            }
+            wgpu.queue
+                .write_buffer(&self.data_buffer.lock().unwrap(), 0, &data);
+        }
+
         if self.ordered {
             let mut layers = BTreeMap::<_, Vec<_>>::new();
             for (i, param) in self.params.iter().enumerate() {
@@ -249,7 +379,7 @@ impl InstanceArray {
         assert!(new_capacity > 0);
 
         let gfx: &GraphicsContext = gfx.retrieve();
-        let resized = InstanceArray::new_wgpu(
+        let resized = Self::new_wgpu(
             &gfx.wgpu,
             self.bind_layout.clone(),
             self.image.clone(),
@@ -258,14 +388,17 @@ impl InstanceArray {
         );
         self.buffer = resized.buffer;
         self.indices = resized.indices;
+        self.data_buffer = resized.data_buffer;
         self.bind_group = resized.bind_group;
 
         self.capacity.store(new_capacity, SeqCst);
         self.dirty.store(true, SeqCst);
         self.uniforms.truncate(new_capacity);
         self.params.truncate(new_capacity);
+        self.data.truncate(new_capacity);
         self.uniforms.reserve(new_capacity - self.uniforms.len());
         self.params.reserve(new_capacity - self.params.len());
+        self.data.reserve(new_capacity - self.data.len());
     }
 
     /// Returns this `InstanceArray`'s associated `image`.
@@ -304,7 +437,7 @@ impl InstanceArray {
     }
 }
 
-impl Drawable for InstanceArray {
+impl<T: bytemuck::Pod> Drawable for InstanceArray<T> {
     fn draw(&self, canvas: &mut Canvas, param: impl Into<DrawParam>) {
         // Only flush (and then push a draw) if there are any instances to draw.
         // This guards against attempts to create empty buffers in `new_wgpu`, see #1168.
@@ -314,7 +447,7 @@ impl Drawable for InstanceArray {
         self.flush_wgpu(&canvas.wgpu).unwrap();
         canvas.push_draw(
             Draw::MeshInstances {
-                mesh: canvas.default_resources().mesh.clone(),
+                mesh: MeshView::from_mesh(&canvas.default_resources().mesh),
                 instances: InstanceArrayView::from_instances(self).unwrap(),
                 scale: true,
             },
@@ -340,3 +473,137 @@ impl Drawable for InstanceArray {
             })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        context::headless_ctx_for_tests,
+        graphics::{Color, ShaderBuilder},
+    };
+
+    const TINT_SHADER: &str = r"
+struct Uniforms {
+    transform: mat4x4<f32>,
+    color: vec4<f32>,
+    scale: vec2<f32>,
+}
+
+struct DrawParam {
+    color: vec4<f32>,
+    src_rect: vec4<f32>,
+    transform: mat4x4<f32>,
+}
+
+struct Tint {
+    color: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+@group(2) @binding(0)
+var<storage, read> instances: array<DrawParam>;
+
+@group(2) @binding(2)
+var<storage, read> data: array<Tint>;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(
+    @builtin(instance_index) index: u32,
+    @location(0) position: vec2<f32>,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = uniforms.transform * instances[index].transform * vec4<f32>(position, 0.0, 1.0);
+    out.color = data[index].color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
+";
+
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, PartialEq, bytemuck::Zeroable, bytemuck::Pod)]
+    struct Tint {
+        color: [f32; 4],
+    }
+
+    #[test]
+    fn headless_test_instance_data() {
+        let mut ctx = headless_ctx_for_tests(24, 16);
+
+        let tint = |color: Color| Tint {
+            color: [color.r, color.g, color.b, color.a],
+        };
+        let quad = |x: f32| DrawParam::new().dest([x, 0.0]).scale([8.0, 16.0]);
+
+        let mut instances = InstanceArray::<Tint>::new_with_data(&ctx, None);
+        instances.push_with_data(quad(0.0), tint(Color::RED));
+        instances.push_with_data(quad(8.0), tint(Color::RED));
+        instances.push(quad(16.0));
+        instances.update_with_data(1, quad(8.0), tint(Color::GREEN));
+        assert_eq!(
+            instances.data(),
+            [
+                tint(Color::RED),
+                tint(Color::GREEN),
+                bytemuck::Zeroable::zeroed()
+            ]
+        );
+
+        let shader = ShaderBuilder::from_code(TINT_SHADER)
+            .build(&ctx.gfx)
+            .unwrap();
+
+        ctx.gfx.begin_frame().unwrap();
+        let mut canvas = Canvas::from_frame(&ctx, Color::BLUE);
+        canvas.set_shader(&shader);
+        canvas.draw(&instances, DrawParam::new());
+        canvas.finish(&mut ctx).unwrap();
+        ctx.gfx.end_frame().unwrap();
+
+        let frame = ctx.gfx.frame().to_pixels(&ctx).unwrap();
+        let pixel = |x: usize| &frame[(8 * 24 + x) * 4..][..4];
+        assert_eq!(pixel(4), [255, 0, 0, 255]);
+        assert_eq!(pixel(12), [0, 255, 0, 255]);
+        // zeroed data is transparent
+        assert_eq!(pixel(20), [0, 0, 255, 255]);
+
+        // instances without data draw as before
+        let mut instances = InstanceArray::new(&ctx, None);
+        instances.push(quad(0.0).color(Color::GREEN));
+        assert_eq!(instances.data().len(), 1);
+
+        ctx.gfx.begin_frame().unwrap();
+        let mut canvas = Canvas::from_frame(&ctx, Color::BLACK);
+        canvas.draw(&instances, DrawParam::new());
+        canvas.finish(&mut ctx).unwrap();
+        ctx.gfx.end_frame().unwrap();
+
+        let frame = ctx.gfx.frame().to_pixels(&ctx).unwrap();
+        assert_eq!(&frame[(8 * 24 + 4) * 4..][..4], [0, 255, 0, 255]);
+
+        // data whose size isn't a multiple of 4 bytes is padded to upload it
+        let mut instances = InstanceArray::<u16>::new_with_data(&ctx, None);
+        for x in [0.0, 8.0, 16.0] {
+            instances.push_with_data(quad(x).color(Color::RED), 1);
+        }
+
+        ctx.gfx.begin_frame().unwrap();
+        let mut canvas = Canvas::from_frame(&ctx, Color::BLACK);
+        canvas.draw(&instances, DrawParam::new());
+        canvas.finish(&mut ctx).unwrap();
+        ctx.gfx.end_frame().unwrap();
+
+        let frame = ctx.gfx.frame().to_pixels(&ctx).unwrap();
+        assert_eq!(&frame[(8 * 24 + 20) * 4..][..4], [255, 0, 0, 255]);
+    }
+}
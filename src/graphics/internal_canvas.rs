@@ -9,15 +9,18 @@ use super::{
         text::{TextRenderer, TextVertex},
     },
     image::Image,
-    mesh::{Mesh, Vertex},
+    mesh::{Mesh, MeshVertex, Vertex},
     sampler::{Sampler, SamplerCache},
     shader::Shader,
-    BlendMode, Color, InstanceArray, LinearColor, Rect, Text, Transform, WgpuContext,
+    BlendMode, CanvasStats, Color, InstanceArray, LinearColor, Rect, Text, Transform, WgpuContext,
 };
 use crate::{GameError, GameResult};
 use crevice::std140::AsStd140;
 use std::{collections::HashMap, hash::Hash};
 
+/// The most draws [`InternalCanvas::draw_mesh_batch`] can draw at once.
+pub(crate) const BATCH_CAPACITY: usize = 1024;
+
 /// A canvas represents a render pass and is how you render primitives such as meshes and text onto images.
 #[allow(missing_debug_implementations)]
 pub struct InternalCanvas<'a> {
@@ -29,6 +32,8 @@ pub struct InternalCanvas<'a> {
     text_renderer: &'a mut TextRenderer,
     fonts: &'a HashMap<String, glyph_brush::FontId>,
     uniform_arena: &'a mut GrowingBufferArena,
+    instance_arena: &'a mut GrowingBufferArena,
+    stats: CanvasStats,
 
     shader: Shader,
     shader_bind_group: Option<(&'a wgpu::BindGroup, ArcBindGroupLayout, u32)>,
@@ -50,7 +55,10 @@ pub struct InternalCanvas<'a> {
     text_sm: ArcShaderModule,
 
     transform: glam::Mat4,
+    vertex_layout: wgpu::VertexBufferLayout<'static>,
     curr_image: Option<ArcTextureView>,
+    curr_images: Vec<ArcTextureView>,
+    next_images: Vec<ArcTextureView>,
     curr_sampler: Sampler,
     next_sampler: Sampler,
     premul_text: bool,
@@ -149,6 +157,7 @@ impl<'a> InternalCanvas<'a> {
         let text_renderer = &mut gfx.text;
         let fonts = &gfx.fonts;
         let uniform_arena = &mut gfx.uniform_arena;
+        let instance_arena = &mut gfx.instance_arena;
 
         let (arenas, mut pass) = {
             let fcx = gfx.fcx.as_mut().unwrap(/* see above */);
@@ -172,11 +181,13 @@ impl<'a> InternalCanvas<'a> {
         let shader = Shader {
             vs_module: None,
             fs_module: None,
+            watched: None,
         };
 
         let text_shader = Shader {
             vs_module: None,
             fs_module: None,
+            watched: None,
         };
 
         let text_uniforms =
@@ -201,6 +212,8 @@ impl<'a> InternalCanvas<'a> {
             text_renderer,
             fonts,
             uniform_arena,
+            instance_arena,
+            stats: CanvasStats::default(),
 
             shader,
             shader_bind_group: None,
@@ -222,7 +235,10 @@ impl<'a> InternalCanvas<'a> {
             text_sm: gfx.text_shader.clone(),
 
             transform,
+            vertex_layout: Vertex::layout(),
             curr_image: None,
+            curr_images: Vec::new(),
+            next_images: Vec::new(),
             curr_sampler: Sampler::default(),
             next_sampler: Sampler::default(),
             premul_text: true,
@@ -269,6 +285,16 @@ impl<'a> InternalCanvas<'a> {
         self.next_sampler = sampler;
     }
 
+    /// Sets the images bound after the texture and sampler of the following mesh draws.
+    pub fn set_images(&mut self, images: Vec<ArcTextureView>) {
+        self.flush_text();
+        if images.len() != self.next_images.len() {
+            // the texture bind group layout has changed
+            self.dirty_pipeline = true;
+        }
+        self.next_images = images;
+    }
+
     pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
         self.flush_text();
         self.dirty_pipeline = true;
@@ -303,8 +329,9 @@ impl<'a> InternalCanvas<'a> {
     }
 
     #[allow(unsafe_code)]
-    pub fn draw_mesh(&mut self, mesh: &'a Mesh, image: &Image, param: DrawParam, scale: bool) {
+    pub fn draw_mesh(&mut self, mesh: &'a MeshView, image: &Image, param: DrawParam, scale: bool) {
         self.flush_text();
+        self.set_vertex_layout(&mesh.layout);
         self.update_pipeline(ShaderType::Draw);
 
         let alloc_size = DrawUniforms::std140_size_static() as u64;
@@ -354,16 +381,19 @@ impl<'a> InternalCanvas<'a> {
             .set_index_buffer(mesh.inds.slice(..), wgpu::IndexFormat::Uint32);
 
         self.pass.draw_indexed(0..mesh.index_count as _, 0, 0..1);
+        self.stats.draw_calls += 1;
+        self.stats.instances += 1;
     }
 
     pub fn draw_mesh_instances(
         &mut self,
-        mesh: &'a Mesh,
+        mesh: &'a MeshView,
         instances: &'a InstanceArrayView,
         param: DrawParam,
         scale: bool,
     ) -> GameResult {
         self.flush_text();
+        self.set_vertex_layout(&mesh.layout);
 
         if instances.len == 0 {
             return Ok(());
@@ -373,28 +403,9 @@ impl<'a> InternalCanvas<'a> {
             ordered: instances.ordered,
         });
 
-        let alloc_size = u64::from(
-            self.wgpu
-                .device
-                .limits()
-                .min_uniform_buffer_offset_alignment,
-        );
-        let uniform_alloc = self.uniform_arena.allocate(&self.wgpu.device, alloc_size);
-
-        let (uniform_bind_group, _) = BindGroupBuilder::new()
-            .buffer(
-                &uniform_alloc.buffer,
-                0,
-                wgpu::ShaderStages::VERTEX,
-                wgpu::BufferBindingType::Uniform,
-                true,
-                Some(alloc_size),
-            )
-            .create(&self.wgpu.device, self.bind_group_cache);
-
         self.set_image(instances.image.clone());
 
-        let uniforms = InstanceUniforms {
+        self.set_instance_uniforms(InstanceUniforms {
             transform: (self.transform
                 * glam::Mat4::from(
                     // image scaling is non-sensical for instance array itself as the image scaling is applied locally (see below)
@@ -419,7 +430,130 @@ impl<'a> InternalCanvas<'a> {
                 glam::Vec2::ZERO
             }
             .into(),
-        };
+        });
+        self.pass.set_bind_group(2, &instances.bind_group, &[]);
+
+        self.pass.set_vertex_buffer(0, mesh.verts.slice(..));
+        self.pass
+            .set_index_buffer(mesh.inds.slice(..), wgpu::IndexFormat::Uint32);
+
+        self.pass
+            .draw_indexed(0..mesh.index_count as _, 0, 0..instances.len as _);
+        self.stats.draw_calls += 1;
+        self.stats.instances += instances.len;
+
+        Ok(())
+    }
+
+    /// Draws `mesh` textured with `image` once for each of `draws`, with a single instanced
+    /// draw call. `draws` are the parameters of each draw and whether the mesh is scaled
+    /// to the size of the image, and there must be at most [`BATCH_CAPACITY`] of them.
+    pub fn draw_mesh_batch(
+        &mut self,
+        mesh: &'a MeshView,
+        image: &Image,
+        draws: impl Iterator<Item = (DrawParam, bool)>,
+    ) {
+        self.flush_text();
+
+        let image_scale = glam::Vec2::new(image.width() as f32, image.height() as f32).into();
+        let uniforms = draws
+            .map(|(param, scale)| {
+                DrawUniforms::from_param(&param, scale.then_some(image_scale)).as_std140()
+            })
+            .collect::<Vec<_>>();
+        if uniforms.is_empty() {
+            return;
+        }
+        assert!(uniforms.len() <= BATCH_CAPACITY);
+
+        self.set_vertex_layout(&mesh.layout);
+        self.update_pipeline(ShaderType::Instance { ordered: false });
+
+        let instance_alloc = self.instance_arena.allocate(
+            &self.wgpu.device,
+            std::mem::size_of_val(uniforms.as_slice()) as u64,
+        );
+        self.wgpu.queue.write_buffer(
+            &instance_alloc.buffer,
+            instance_alloc.offset,
+            bytemuck::cast_slice(uniforms.as_slice()),
+        );
+        // Binding the rest of the buffer instead of just this batch keeps the number of
+        // distinct (cached) bind groups down. The unordered instance shader doesn't read
+        // the indices or instance data, but the layout needs buffers for them.
+        let (instance_bind_group, _) = BindGroupBuilder::new()
+            .buffer(
+                &instance_alloc.buffer,
+                instance_alloc.offset,
+                wgpu::ShaderStages::VERTEX,
+                wgpu::BufferBindingType::Storage { read_only: true },
+                false,
+                None,
+            )
+            .buffer(
+                &instance_alloc.buffer,
+                instance_alloc.offset,
+                wgpu::ShaderStages::VERTEX,
+                wgpu::BufferBindingType::Storage { read_only: true },
+                false,
+                None,
+            )
+            .buffer(
+                &instance_alloc.buffer,
+                instance_alloc.offset,
+                wgpu::ShaderStages::VERTEX,
+                wgpu::BufferBindingType::Storage { read_only: true },
+                false,
+                None,
+            )
+            .create(&self.wgpu.device, self.bind_group_cache);
+
+        self.set_image(image.clone());
+
+        // The instances already hold the complete transform, like `draw_mesh`'s uniforms.
+        self.set_instance_uniforms(InstanceUniforms {
+            transform: self.transform.into(),
+            color: mint::Vector4 {
+                x: 1.,
+                y: 1.,
+                z: 1.,
+                w: 1.,
+            },
+            scale: glam::Vec2::ZERO.into(),
+        });
+        self.pass
+            .set_bind_group(2, self.arenas.bind_groups.alloc(instance_bind_group), &[]);
+
+        self.pass.set_vertex_buffer(0, mesh.verts.slice(..));
+        self.pass
+            .set_index_buffer(mesh.inds.slice(..), wgpu::IndexFormat::Uint32);
+
+        self.pass
+            .draw_indexed(0..mesh.index_count as _, 0, 0..uniforms.len() as _);
+        self.stats.draw_calls += 1;
+        self.stats.instances += uniforms.len() as u32;
+    }
+
+    fn set_instance_uniforms(&mut self, uniforms: InstanceUniforms) {
+        let alloc_size = u64::from(
+            self.wgpu
+                .device
+                .limits()
+                .min_uniform_buffer_offset_alignment,
+        );
+        let uniform_alloc = self.uniform_arena.allocate(&self.wgpu.device, alloc_size);
+
+        let (uniform_bind_group, _) = BindGroupBuilder::new()
+            .buffer(
+                &uniform_alloc.buffer,
+                0,
+                wgpu::ShaderStages::VERTEX,
+                wgpu::BufferBindingType::Uniform,
+                true,
+                Some(alloc_size),
+            )
+            .create(&self.wgpu.device, self.bind_group_cache);
 
         self.wgpu.queue.write_buffer(
             &uniform_alloc.buffer,
@@ -432,16 +566,6 @@ impl<'a> InternalCanvas<'a> {
             self.arenas.bind_groups.alloc(uniform_bind_group),
             &[uniform_alloc.offset as u32],
         );
-        self.pass.set_bind_group(2, &instances.bind_group, &[]);
-
-        self.pass.set_vertex_buffer(0, mesh.verts.slice(..));
-        self.pass
-            .set_index_buffer(mesh.inds.slice(..), wgpu::IndexFormat::Uint32);
-
-        self.pass
-            .draw_indexed(0..mesh.index_count as _, 0, 0..instances.len as _);
-
-        Ok(())
     }
 
     pub fn draw_bounded_text(&mut self, text: &Text, mut param: DrawParam) -> GameResult {
@@ -456,6 +580,7 @@ impl<'a> InternalCanvas<'a> {
 
         self.text_renderer
             .queue(text.as_section(self.fonts, param)?);
+        self.stats.text_sections += 1;
 
         self.set_text_image(self.text_renderer.cache_view.clone());
 
@@ -495,6 +620,7 @@ impl<'a> InternalCanvas<'a> {
                 &self.wgpu.queue,
                 self.arenas,
                 &mut self.pass,
+                &mut self.stats,
             );
             if premul {
                 self.set_blend_mode(BlendMode::ALPHA);
@@ -502,22 +628,39 @@ impl<'a> InternalCanvas<'a> {
         }
     }
 
-    pub fn finish(mut self) {
+    /// Flushes the queued text and returns what was drawn.
+    pub fn finish(mut self) -> CanvasStats {
         self.finalize();
+        std::mem::take(&mut self.stats)
     }
 
     fn finalize(&mut self) {
         self.flush_text();
     }
 
+    fn set_vertex_layout(&mut self, layout: &wgpu::VertexBufferLayout<'static>) {
+        if self.vertex_layout != *layout {
+            self.dirty_pipeline = true;
+            self.vertex_layout = layout.clone();
+        }
+    }
+
     fn update_pipeline(&mut self, ty: ShaderType) {
         if self.dirty_pipeline || self.shader_ty != Some(ty) {
             self.dirty_pipeline = false;
             self.shader_ty = Some(ty);
 
-            let texture_layout = BindGroupLayoutBuilder::new()
-                .image(wgpu::ShaderStages::FRAGMENT)
-                .sampler(wgpu::ShaderStages::FRAGMENT)
+            let images = match ty {
+                ShaderType::Text => 0,
+                _ => self.next_images.len(),
+            };
+            let texture_layout = (0..images)
+                .fold(
+                    BindGroupLayoutBuilder::new()
+                        .image(wgpu::ShaderStages::FRAGMENT)
+                        .sampler(wgpu::ShaderStages::FRAGMENT),
+                    |layout, _| layout.image(wgpu::ShaderStages::FRAGMENT),
+                )
                 .create(&self.wgpu.device, self.bind_group_cache);
 
             let instance_layout = BindGroupLayoutBuilder::new()
@@ -531,6 +674,11 @@ impl<'a> InternalCanvas<'a> {
                     wgpu::BufferBindingType::Storage { read_only: true },
                     false,
                 )
+                .buffer(
+                    wgpu::ShaderStages::VERTEX,
+                    wgpu::BufferBindingType::Storage { read_only: true },
+                    false,
+                )
                 .create(&self.wgpu.device, self.bind_group_cache);
 
             let uniform_layout = BindGroupLayoutBuilder::new()
@@ -583,7 +731,7 @@ impl<'a> InternalCanvas<'a> {
                 .render_pipelines
                 .alloc(self.pipeline_cache.render_pipeline(
                     &self.wgpu.device,
-                    layout.as_ref(),
+                    &layout,
                     RenderPipelineInfo {
                         vs: if let Some(vs_module) = &shader.vs_module {
                             vs_module.clone()
@@ -626,12 +774,13 @@ impl<'a> InternalCanvas<'a> {
                         },
                         vertex_layout: match ty {
                             ShaderType::Text => TextVertex::layout(),
-                            _ => Vertex::layout(),
+                            _ => self.vertex_layout.clone(),
                         },
                     },
                 ));
 
             self.pass.set_pipeline(pipeline);
+            self.stats.pipeline_switches += 1;
         }
     }
 
@@ -641,12 +790,27 @@ impl<'a> InternalCanvas<'a> {
                 .curr_image
                 .as_ref()
                 .map_or(true, |curr| curr.id() != image.view.id())
+            || self.curr_images != self.next_images
         {
             self.curr_sampler = self.next_sampler;
             let sample = self.sampler_cache.get(&self.wgpu.device, self.curr_sampler);
-            let image_bind = image.fetch_buffer(sample.id(), sample, &self.wgpu.device);
+            let image_bind = if self.next_images.is_empty() {
+                image.fetch_buffer(sample.id(), sample, &self.wgpu.device)
+            } else {
+                self.next_images
+                    .iter()
+                    .fold(
+                        BindGroupBuilder::new()
+                            .image(&image.view, wgpu::ShaderStages::FRAGMENT)
+                            .sampler(&sample, wgpu::ShaderStages::FRAGMENT),
+                        |group, view| group.image(view, wgpu::ShaderStages::FRAGMENT),
+                    )
+                    .create(&self.wgpu.device, self.bind_group_cache)
+                    .0
+            };
 
             self.curr_image = Some(image.view);
+            self.curr_images.clone_from(&self.next_images);
 
             self.pass
                 .set_bind_group(1, self.arenas.bind_groups.alloc(image_bind), &[]);
@@ -659,8 +823,10 @@ impl<'a> InternalCanvas<'a> {
                 .curr_image
                 .as_ref()
                 .map_or(true, |curr| curr.id() != view.id())
+            || !self.curr_images.is_empty()
         {
             self.curr_sampler = self.next_sampler;
+            self.curr_images.clear();
 
             let (image_bind, _) = BindGroupBuilder::new()
                 .image(&view, wgpu::ShaderStages::FRAGMENT)
@@ -684,6 +850,26 @@ impl<'a> Drop for InternalCanvas<'a> {
     }
 }
 
+/// A [`Mesh`] of any vertex type, as drawn by a canvas.
+#[derive(Debug, Clone)]
+pub struct MeshView {
+    pub verts: ArcBuffer,
+    pub inds: ArcBuffer,
+    pub index_count: usize,
+    pub layout: wgpu::VertexBufferLayout<'static>,
+}
+
+impl MeshView {
+    pub fn from_mesh<V: MeshVertex>(mesh: &Mesh<V>) -> Self {
+        MeshView {
+            verts: mesh.verts.clone(),
+            inds: mesh.inds.clone(),
+            index_count: mesh.index_count,
+            layout: V::layout(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct InstanceArrayView {
     pub buffer: ArcBuffer,
@@ -695,7 +881,7 @@ pub struct InstanceArrayView {
 }
 
 impl InstanceArrayView {
-    pub fn from_instances(ia: &InstanceArray) -> GameResult<Self> {
+    pub fn from_instances<T: bytemuck::Pod>(ia: &InstanceArray<T>) -> GameResult<Self> {
         Ok(InstanceArrayView {
             buffer: ia.buffer.lock().map_err(|_| GameError::LockError)?.clone(),
             indices: ia.indices.lock().map_err(|_| GameError::LockError)?.clone(),
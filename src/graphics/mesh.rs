@@ -1,11 +1,57 @@
 use super::{
-    context::GraphicsContext, gpu::arc::ArcBuffer, Canvas, Color, Draw, DrawMode, DrawParam,
-    Drawable, LinearColor, Rect, WgpuContext,
+    context::GraphicsContext, gpu::arc::ArcBuffer, internal_canvas::MeshView, Canvas, Color, Draw,
+    DrawMode, DrawParam, Drawable, LinearColor, Rect, WgpuContext,
 };
 use crate::{context::Has, GameError, GameResult};
 use lyon::{math::Point as LPoint, path::Polygon, tessellation as tess};
+use std::marker::PhantomData;
 use wgpu::util::DeviceExt;
 
+/// A type that can be used as the vertices of a [`Mesh`].
+///
+/// The built-in shaders take [`Vertex`] as input. Meshes of other vertex types, such as ones with
+/// normals or a second set of UVs, are drawn with a custom [`Shader`](super::Shader) whose vertex
+/// stage takes the attributes described by [`MeshVertex::layout`] as input:
+///
+/// ```rust
+/// # use ggez::graphics::*;
+/// #[repr(C)]
+/// #[derive(Debug, Clone, Copy, bytemuck::Zeroable, bytemuck::Pod)]
+/// struct LitVertex {
+///     position: [f32; 2],
+///     uv: [f32; 2],
+///     color: [f32; 4],
+///     normal: [f32; 3],
+/// }
+///
+/// impl MeshVertex for LitVertex {
+///     fn layout() -> wgpu::VertexBufferLayout<'static> {
+///         const ATTRIBUTES: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![
+///             0 => Float32x2, 1 => Float32x2, 2 => Float32x4, 3 => Float32x3
+///         ];
+///         wgpu::VertexBufferLayout {
+///             array_stride: std::mem::size_of::<LitVertex>() as u64,
+///             step_mode: wgpu::VertexStepMode::Vertex,
+///             attributes: &ATTRIBUTES,
+///         }
+///     }
+///
+///     fn position(&self) -> mint::Point2<f32> {
+///         self.position.into()
+///     }
+/// }
+/// ```
+///
+/// The uniforms, image and sampler bound for that shader are the same as in the built-in `draw.wgsl`.
+/// Such meshes are skipped, with a warning, when they're drawn without a custom vertex shader.
+pub trait MeshVertex: bytemuck::Pod {
+    /// Returns the layout of a vertex buffer holding these vertices.
+    fn layout() -> wgpu::VertexBufferLayout<'static>;
+
+    /// Returns the position of the vertex, used to compute the bounds of a mesh.
+    fn position(&self) -> mint::Point2<f32>;
+}
+
 /// Vertex format uploaded to vertex buffers.
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, bytemuck::Zeroable, bytemuck::Pod)]
@@ -18,8 +64,8 @@ pub struct Vertex {
     pub color: [f32; 4],
 }
 
-impl Vertex {
-    pub(crate) const fn layout() -> wgpu::VertexBufferLayout<'static> {
+impl MeshVertex for Vertex {
+    fn layout() -> wgpu::VertexBufferLayout<'static> {
         const ATTRIBUTES: [wgpu::VertexAttribute; 3] = [
             wgpu::VertexAttribute {
                 format: wgpu::VertexFormat::Float32x2,
@@ -44,16 +90,25 @@ impl Vertex {
             attributes: &ATTRIBUTES,
         }
     }
+
+    #[inline]
+    fn position(&self) -> mint::Point2<f32> {
+        self.position.into()
+    }
 }
 
 /// Mesh data stored on the GPU as a vertex and index buffer. Cheap to clone.
+///
+/// Meshes are made of [`Vertex`] unless they're created from vertices of another
+/// [`MeshVertex`] type with [`Mesh::from_vertices`].
 #[derive(Debug, Clone)]
-pub struct Mesh {
+pub struct Mesh<V: MeshVertex = Vertex> {
     pub(crate) verts: ArcBuffer,
     pub(crate) inds: ArcBuffer,
     pub(crate) vertex_count: usize,
     pub(crate) index_count: usize,
     pub(crate) bounds: Rect,
+    pub(crate) vertex: PhantomData<V>,
 }
 
 impl Mesh {
@@ -64,26 +119,7 @@ impl Mesh {
     }
 
     pub(crate) fn from_data_wgpu(wgpu: &WgpuContext, raw: MeshData) -> Self {
-        let [minx, miny, maxx, maxy] = raw.vertices.iter().fold(
-            [f32::MAX, f32::MAX, f32::MIN, f32::MIN],
-            |[minx, miny, maxx, maxy], vert| {
-                let [x, y] = vert.position;
-                [minx.min(x), miny.min(y), maxx.max(x), maxy.max(y)]
-            },
-        );
-
-        Mesh {
-            verts: Self::create_verts(wgpu, raw.vertices),
-            inds: Self::create_inds(wgpu, raw.indices),
-            vertex_count: raw.vertices.len(),
-            index_count: raw.indices.len(),
-            bounds: Rect {
-                x: minx,
-                y: miny,
-                w: maxx - minx,
-                h: maxy - miny,
-            },
-        }
+        Self::from_vertices_wgpu(wgpu, raw.vertices, raw.indices)
     }
 
     /// Create a new mesh for a line of one or more connected segments.
@@ -204,6 +240,42 @@ impl Mesh {
             MeshBuilder::new().triangles(triangles, color)?.build(),
         ))
     }
+}
+
+impl<V: MeshVertex> Mesh<V> {
+    /// Create a new mesh from vertices of any [`MeshVertex`] type and the indices into them.
+    pub fn from_vertices(gfx: &impl Has<GraphicsContext>, vertices: &[V], indices: &[u32]) -> Self {
+        let gfx = gfx.retrieve();
+        Self::from_vertices_wgpu(&gfx.wgpu, vertices, indices)
+    }
+
+    fn from_vertices_wgpu(wgpu: &WgpuContext, vertices: &[V], indices: &[u32]) -> Self {
+        Mesh {
+            verts: Self::create_verts(wgpu, vertices),
+            inds: Self::create_inds(wgpu, indices),
+            vertex_count: vertices.len(),
+            index_count: indices.len(),
+            bounds: Self::bounds(vertices),
+            vertex: PhantomData,
+        }
+    }
+
+    pub(crate) fn bounds(vertices: &[V]) -> Rect {
+        let [minx, miny, maxx, maxy] = vertices.iter().fold(
+            [f32::MAX, f32::MAX, f32::MIN, f32::MIN],
+            |[minx, miny, maxx, maxy], vert| {
+                let mint::Point2 { x, y } = vert.position();
+                [minx.min(x), miny.min(y), maxx.max(x), maxy.max(y)]
+            },
+        );
+
+        Rect {
+            x: minx,
+            y: miny,
+            w: maxx - minx,
+            h: maxy - miny,
+        }
+    }
 
     /// Returns the WGPU vertex and index buffers of this mesh, in that order.
     #[inline]
@@ -224,7 +296,7 @@ impl Mesh {
     }
 
     #[allow(unsafe_code)]
-    fn create_verts(wgpu: &WgpuContext, vertices: &[Vertex]) -> ArcBuffer {
+    fn create_verts(wgpu: &WgpuContext, vertices: &[V]) -> ArcBuffer {
         ArcBuffer::new(
             wgpu.device
                 .create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -248,11 +320,11 @@ impl Mesh {
     }
 }
 
-impl Drawable for Mesh {
+impl<V: MeshVertex> Drawable for Mesh<V> {
     fn draw(&self, canvas: &mut Canvas, param: impl Into<DrawParam>) {
         canvas.push_draw(
             Draw::Mesh {
-                mesh: self.clone(),
+                mesh: MeshView::from_mesh(self),
                 image: canvas.default_resources().image.clone(),
                 scale: false,
             },
@@ -691,3 +763,115 @@ impl tess::FillVertexConstructor<Vertex> for VertexBuilder {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        context::headless_ctx_for_tests,
+        graphics::{Quad, ShaderBuilder},
+    };
+
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, bytemuck::Zeroable, bytemuck::Pod)]
+    struct TintVertex {
+        position: [f32; 2],
+        tint: [f32; 3],
+    }
+
+    impl MeshVertex for TintVertex {
+        fn layout() -> wgpu::VertexBufferLayout<'static> {
+            const ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+                wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x3];
+            wgpu::VertexBufferLayout {
+                array_stride: std::mem::size_of::<TintVertex>() as u64,
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: &ATTRIBUTES,
+            }
+        }
+
+        fn position(&self) -> mint::Point2<f32> {
+            self.position.into()
+        }
+    }
+
+    const TINT_SHADER: &str = r"
+struct DrawUniforms {
+    color: vec4<f32>,
+    src_rect: vec4<f32>,
+    transform: mat4x4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: DrawUniforms;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(@location(0) position: vec2<f32>, @location(1) tint: vec3<f32>) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = uniforms.transform * vec4<f32>(position, 0.0, 1.0);
+    out.color = uniforms.color * vec4<f32>(tint, 1.0);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
+";
+
+    #[test]
+    fn headless_test_custom_vertex_mesh() {
+        let mut ctx = headless_ctx_for_tests(16, 16);
+
+        let vertex = |x, y| TintVertex {
+            position: [x, y],
+            tint: [1.0, 0.0, 1.0],
+        };
+        let mesh = Mesh::from_vertices(
+            &ctx,
+            &[
+                vertex(0.0, 0.0),
+                vertex(8.0, 0.0),
+                vertex(8.0, 16.0),
+                vertex(0.0, 16.0),
+            ],
+            &[0, 1, 2, 0, 2, 3],
+        );
+        assert_eq!(mesh.vertex_count(), 4);
+        assert_eq!(mesh.dimensions(&ctx), Some(Rect::new(0.0, 0.0, 8.0, 16.0)));
+
+        let shader = ShaderBuilder::from_code(TINT_SHADER)
+            .build(&ctx.gfx)
+            .unwrap();
+
+        ctx.gfx.begin_frame().unwrap();
+        let mut canvas = Canvas::from_frame(&ctx, Color::BLACK);
+        canvas.set_shader(&shader);
+        canvas.draw(&mesh, DrawParam::new());
+        canvas.set_default_shader();
+        // switching back to `Vertex` meshes
+        canvas.draw(
+            &Quad,
+            DrawParam::new()
+                .dest([8.0, 0.0])
+                .scale([8.0, 16.0])
+                .color(Color::GREEN),
+        );
+        // the built-in shaders can't take `TintVertex`
+        canvas.draw(&mesh, DrawParam::new().dest([8.0, 0.0]));
+        let stats = canvas.finish_with_stats(&mut ctx).unwrap();
+        assert_eq!(stats.pipeline_switches, 2);
+        assert_eq!(stats.draw_calls, 2);
+        ctx.gfx.end_frame().unwrap();
+
+        let frame = ctx.gfx.frame().to_pixels(&ctx).unwrap();
+        let pixel = |x: usize, y: usize| &frame[(y * 16 + x) * 4..][..4];
+        assert_eq!(pixel(4, 8), [255, 0, 255, 255]);
+        assert_eq!(pixel(12, 8), [0, 255, 0, 255]);
+    }
+}
@@ -21,23 +21,33 @@
 //! [custom shader]:Canvas::set_shader
 //! [blend mode]:Canvas::set_blend_mode
 
+pub(crate) mod animation;
+pub(crate) mod camera;
 pub(crate) mod canvas;
 pub(crate) mod context;
+pub(crate) mod debug_draw;
 pub(crate) mod draw;
+pub(crate) mod dynamic_mesh;
 pub(crate) mod gpu;
 pub(crate) mod image;
 pub(crate) mod instance;
 pub(crate) mod internal_canvas;
 pub(crate) mod mesh;
+pub(crate) mod nine_slice;
+pub(crate) mod postprocess;
+pub(crate) mod profiler;
 pub(crate) mod sampler;
 pub(crate) mod shader;
+pub(crate) mod sprite_sheet;
 pub(crate) mod text;
+pub(crate) mod tilemap;
 mod types;
 
 pub use lyon::tessellation::{FillOptions, FillRule, LineCap, LineJoin, StrokeOptions};
 pub use {
-    self::image::*, canvas::*, context::*, draw::*, instance::*, mesh::*, sampler::*, shader::*,
-    text::*, types::*,
+    self::image::*, animation::*, camera::*, canvas::*, context::*, debug_draw::*, draw::*,
+    dynamic_mesh::*, instance::*, mesh::*, nine_slice::*, postprocess::*, profiler::*, sampler::*,
+    shader::*, sprite_sheet::*, text::*, tilemap::*, types::*,
 };
 
 /// Applies `DrawParam` to `Rect`.
@@ -0,0 +1,356 @@
+use super::{Canvas, DrawParam, Drawable, GraphicsContext, Image, Rect};
+use crate::context::Has;
+
+/// The widths of the borders of a [`NineSlice`], in pixels of the image.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Insets {
+    /// Width of the left border.
+    pub left: f32,
+    /// Height of the top border.
+    pub top: f32,
+    /// Width of the right border.
+    pub right: f32,
+    /// Height of the bottom border.
+    pub bottom: f32,
+}
+
+impl Insets {
+    /// Creates insets with a different width for each border.
+    pub const fn new(left: f32, top: f32, right: f32, bottom: f32) -> Self {
+        Insets {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    /// Creates insets with the same width for every border.
+    pub const fn uniform(width: f32) -> Self {
+        Insets::new(width, width, width, width)
+    }
+}
+
+impl From<f32> for Insets {
+    fn from(width: f32) -> Self {
+        Insets::uniform(width)
+    }
+}
+
+/// How the edges and the center of a [`NineSlice`] fill the space between the corners.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum NineSliceMode {
+    /// The part of the image is stretched to fill the space.
+    #[default]
+    Stretch,
+    /// The part of the image is repeated at its original size, cutting off the last repetition.
+    Tile,
+}
+
+/// An image split into nine parts by [`Insets`], for drawing panels and frames of any size
+/// without distorting their borders.
+///
+/// The corners are always drawn at their original size, the edges are stretched or tiled
+/// along one axis and the center along both, see [`NineSliceMode`].
+///
+/// Like a [`Quad`](super::Quad), a nine-slice is one unit large: the scale of the `DrawParam`
+/// is the size it's drawn at, in pixels, so it's easiest to place with [`DrawParam::dest_rect`].
+/// Rotation and offset work as usual. If the size is smaller than the borders, the borders
+/// are shrunk to fit. The source rectangle of the `DrawParam` selects the part of the image
+/// to slice, e.g. a panel in a texture atlas, and the insets are relative to that part.
+///
+/// ```rust,no_run
+/// # use ggez::graphics::{self, Canvas, DrawParam, Image, Insets, NineSlice, NineSliceMode, Rect};
+/// # fn t(ctx: &mut ggez::Context, canvas: &mut Canvas) -> ggez::GameResult {
+/// let panel = NineSlice::new(Image::from_path(ctx, "/panel.png")?, Insets::uniform(8.0))
+///     .center_mode(NineSliceMode::Tile);
+/// canvas.draw(&panel, DrawParam::new().dest_rect(Rect::new(20.0, 20.0, 300.0, 120.0)));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The pieces are drawn one after another, so they are batched into a single draw call.
+#[derive(Debug, Clone)]
+pub struct NineSlice {
+    image: Image,
+    insets: Insets,
+    edge_mode: NineSliceMode,
+    center_mode: NineSliceMode,
+}
+
+impl NineSlice {
+    /// Creates a nine-slice of `image` that stretches its edges and center.
+    pub fn new(image: Image, insets: impl Into<Insets>) -> Self {
+        NineSlice {
+            image,
+            insets: insets.into(),
+            edge_mode: NineSliceMode::Stretch,
+            center_mode: NineSliceMode::Stretch,
+        }
+    }
+
+    /// Sets how the edges fill the space between the corners.
+    #[must_use]
+    pub fn edge_mode(mut self, mode: NineSliceMode) -> Self {
+        self.edge_mode = mode;
+        self
+    }
+
+    /// Sets how the center fills the space between the edges.
+    #[must_use]
+    pub fn center_mode(mut self, mode: NineSliceMode) -> Self {
+        self.center_mode = mode;
+        self
+    }
+
+    /// Returns the image that is sliced.
+    #[inline]
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    /// Returns the widths of the borders.
+    #[inline]
+    pub fn insets(&self) -> Insets {
+        self.insets
+    }
+
+    /// Sets the widths of the borders.
+    pub fn set_insets(&mut self, insets: impl Into<Insets>) {
+        self.insets = insets.into();
+    }
+
+    /// Computes the pieces to draw, as pairs of the area they cover within a panel of
+    /// `size` pixels and the pixels of the image they show, for the part of an
+    /// `image_size` image selected by `src`.
+    fn pieces(&self, image_size: (f32, f32), src: Rect, size: (f32, f32)) -> Vec<(Rect, Rect)> {
+        let src = Rect::new(
+            src.x * image_size.0,
+            src.y * image_size.1,
+            src.w * image_size.0,
+            src.h * image_size.1,
+        );
+        let columns = Self::spans(src.x, src.w, self.insets.left, self.insets.right, size.0);
+        let rows = Self::spans(src.y, src.h, self.insets.top, self.insets.bottom, size.1);
+
+        let mut pieces = Vec::new();
+        for (row, &(dest_y, dest_h, src_y, src_h)) in rows.iter().enumerate() {
+            for (column, &(dest_x, dest_w, src_x, src_w)) in columns.iter().enumerate() {
+                let mode = match (column == 1, row == 1) {
+                    (false, false) => NineSliceMode::Stretch,
+                    (true, true) => self.center_mode,
+                    _ => self.edge_mode,
+                };
+                let tile_x = column == 1 && mode == NineSliceMode::Tile;
+                let tile_y = row == 1 && mode == NineSliceMode::Tile;
+                for (x, w, sx, sw) in Self::tiles(dest_x, dest_w, src_x, src_w, tile_x) {
+                    for (y, h, sy, sh) in Self::tiles(dest_y, dest_h, src_y, src_h, tile_y) {
+                        pieces.push((Rect::new(x, y, w, h), Rect::new(sx, sy, sw, sh)));
+                    }
+                }
+            }
+        }
+        pieces
+    }
+
+    /// Splits `size` pixels into the start border, middle and end border along one axis,
+    /// as `(dest, dest_len, src, src_len)`.
+    fn spans(src: f32, src_len: f32, start: f32, end: f32, size: f32) -> [(f32, f32, f32, f32); 3] {
+        let start = start.clamp(0.0, src_len);
+        let end = end.clamp(0.0, src_len - start);
+        let border_scale = if start + end > size {
+            size / (start + end)
+        } else {
+            1.0
+        };
+        let (dest_start, dest_end) = (start * border_scale, end * border_scale);
+        [
+            (0.0, dest_start, src, start),
+            (
+                dest_start,
+                size - dest_start - dest_end,
+                src + start,
+                src_len - start - end,
+            ),
+            (size - dest_end, dest_end, src + src_len - end, end),
+        ]
+    }
+
+    /// Covers `dest_len` pixels with repetitions of `src_len` pixels of the image if `tile`
+    /// is set, or stretches them otherwise.
+    fn tiles(
+        dest: f32,
+        dest_len: f32,
+        src: f32,
+        src_len: f32,
+        tile: bool,
+    ) -> Vec<(f32, f32, f32, f32)> {
+        if dest_len <= 0.0 || src_len <= 0.0 {
+            return Vec::new();
+        }
+        if !tile {
+            return vec![(dest, dest_len, src, src_len)];
+        }
+        let mut tiles = Vec::new();
+        let mut covered = 0.0;
+        while covered < dest_len {
+            let len = src_len.min(dest_len - covered);
+            tiles.push((dest + covered, len, src, len));
+            covered += len;
+        }
+        tiles
+    }
+}
+
+impl Drawable for NineSlice {
+    fn draw(&self, canvas: &mut Canvas, param: impl Into<DrawParam>) {
+        let param = param.into();
+        // The transform maps the unit square onto the panel; its axes are the panel's size.
+        let transform = glam::Mat4::from(param.transform.to_bare_matrix());
+        let size = (
+            transform.x_axis.truncate().length(),
+            transform.y_axis.truncate().length(),
+        );
+        if size.0 <= 0.0 || size.1 <= 0.0 {
+            return;
+        }
+        let panel = transform * glam::Mat4::from_scale(glam::vec3(1.0 / size.0, 1.0 / size.1, 1.0));
+
+        let image_size = (self.image.width() as f32, self.image.height() as f32);
+        for (dest, src) in self.pieces(image_size, param.src, size) {
+            // Drawing an image scales it to the size of its source rectangle, so that
+            // is undone to make the piece cover `dest`.
+            let piece = panel
+                * glam::Mat4::from_translation(glam::vec3(dest.x, dest.y, 0.0))
+                * glam::Mat4::from_scale(glam::vec3(dest.w / src.w, dest.h / src.h, 1.0));
+            canvas.draw(
+                &self.image,
+                DrawParam {
+                    src: Rect::new(
+                        src.x / image_size.0,
+                        src.y / image_size.1,
+                        src.w / image_size.0,
+                        src.h / image_size.1,
+                    ),
+                    ..param
+                }
+                .transform(piece),
+            );
+        }
+    }
+
+    fn dimensions(&self, _gfx: &impl Has<GraphicsContext>) -> Option<Rect> {
+        Some(Rect::one())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        context::headless_ctx_for_tests,
+        graphics::{Color, ImageFormat, Sampler},
+    };
+
+    #[test]
+    fn headless_test_nine_slice_pieces() {
+        let ctx = headless_ctx_for_tests(16, 16);
+        let image = Image::from_pixels(&ctx, &[255; 4], ImageFormat::Rgba8Unorm, 1, 1);
+        let full = Rect::one();
+
+        let stretch = NineSlice::new(image.clone(), 4.0);
+        let pieces = stretch.pieces((12.0, 12.0), full, (20.0, 16.0));
+        assert_eq!(pieces.len(), 9);
+        assert_eq!(
+            pieces[0],
+            (Rect::new(0.0, 0.0, 4.0, 4.0), Rect::new(0.0, 0.0, 4.0, 4.0))
+        );
+        assert_eq!(
+            pieces[4],
+            (
+                Rect::new(4.0, 4.0, 12.0, 8.0),
+                Rect::new(4.0, 4.0, 4.0, 4.0)
+            )
+        );
+        assert_eq!(
+            pieces[8],
+            (
+                Rect::new(16.0, 12.0, 4.0, 4.0),
+                Rect::new(8.0, 8.0, 4.0, 4.0)
+            )
+        );
+
+        // Tiling repeats the middle 4 pixels; the last tile of the 8 pixel high center
+        // column fits exactly, the 12 pixel wide rows take three.
+        let tiled = stretch
+            .clone()
+            .edge_mode(NineSliceMode::Tile)
+            .center_mode(NineSliceMode::Tile);
+        let pieces = tiled.pieces((12.0, 12.0), full, (20.0, 16.0));
+        assert_eq!(pieces.len(), 4 + 2 * 3 + 2 * 2 + 3 * 2);
+        let partial = tiled.pieces((12.0, 12.0), full, (18.0, 12.0));
+        assert!(partial.contains(&(
+            Rect::new(12.0, 0.0, 2.0, 4.0),
+            Rect::new(4.0, 0.0, 2.0, 4.0)
+        )));
+
+        // Borders are shrunk to fit small panels.
+        let pieces = stretch.pieces((12.0, 12.0), full, (6.0, 6.0));
+        assert_eq!(pieces.len(), 4);
+        assert_eq!(
+            pieces[3],
+            (Rect::new(3.0, 3.0, 3.0, 3.0), Rect::new(8.0, 8.0, 4.0, 4.0))
+        );
+
+        // The source rectangle selects part of the image.
+        let pieces = stretch.pieces((24.0, 12.0), Rect::new(0.5, 0.0, 0.5, 1.0), (12.0, 12.0));
+        assert_eq!(
+            pieces[0],
+            (
+                Rect::new(0.0, 0.0, 4.0, 4.0),
+                Rect::new(12.0, 0.0, 4.0, 4.0)
+            )
+        );
+    }
+
+    #[test]
+    fn headless_test_nine_slice_draw() {
+        let mut ctx = headless_ctx_for_tests(16, 16);
+        // Each of the nine pixels gets a part of its own.
+        #[rustfmt::skip]
+        let pixels = [
+            10, 0, 0, 255,   20, 0, 0, 255,   30, 0, 0, 255,
+            40, 0, 0, 255,   50, 0, 0, 255,   60, 0, 0, 255,
+            70, 0, 0, 255,   80, 0, 0, 255,   90, 0, 0, 255,
+        ];
+        // In the frame's format, the values come out unchanged.
+        let image = Image::from_pixels(&ctx, &pixels, ctx.gfx.surface_format(), 3, 3);
+        let nine_slice = NineSlice::new(image, 1.0);
+
+        ctx.gfx.begin_frame().unwrap();
+        let mut canvas = Canvas::from_frame(&ctx, Color::BLACK);
+        canvas.set_sampler(Sampler::nearest_clamp());
+        canvas.draw(
+            &nine_slice,
+            DrawParam::new().dest_rect(Rect::new(2.0, 2.0, 8.0, 6.0)),
+        );
+        let stats = canvas.finish_with_stats(&mut ctx).unwrap();
+        assert_eq!(stats.draw_calls, 1);
+        assert_eq!(stats.instances, 9);
+        ctx.gfx.end_frame().unwrap();
+
+        let frame = ctx.gfx.frame().to_pixels(&ctx).unwrap();
+        let red = |x: usize, y: usize| frame[(y * 16 + x) * 4];
+        assert_eq!(red(1, 1), 0);
+        assert_eq!(red(2, 2), 10);
+        assert_eq!(red(5, 2), 20);
+        assert_eq!(red(9, 2), 30);
+        assert_eq!(red(2, 4), 40);
+        assert_eq!(red(5, 4), 50);
+        assert_eq!(red(9, 6), 60);
+        assert_eq!(red(2, 7), 70);
+        assert_eq!(red(5, 7), 80);
+        assert_eq!(red(9, 7), 90);
+        assert_eq!(red(10, 8), 0);
+    }
+}
@@ -0,0 +1,543 @@
+use super::{
+    gpu::arc::{ArcBindGroup, ArcBindGroupLayout, ArcBuffer},
+    BlendMode, Canvas, Color, DrawParam, GraphicsContext, Image, Sampler, ScreenImage, Shader,
+    ShaderBuilder, ShaderParams, ShaderParamsBuilder,
+};
+use crate::{
+    context::{Has, HasMut},
+    Context, GameError, GameResult,
+};
+use crevice::std140::{AsStd140, Std140};
+
+type RawShaderParams = (ArcBindGroup, ArcBindGroupLayout, u32);
+
+/// A fullscreen effect run by a [`PostProcessChain`]: a fragment [`Shader`] along with its
+/// [`ShaderParams`].
+///
+/// The shader samples the output of the previous pass (or the scene, for the first pass)
+/// through the usual `t` texture and `s` sampler of bind group 1, and its parameters are bound
+/// to group 3, just like when drawing with [`Canvas::set_shader`].
+#[derive(Debug, Clone)]
+pub struct PostProcessPass {
+    shader: Shader,
+    params: Option<RawShaderParams>,
+    builtin: Option<(Builtin, ArcBuffer)>,
+    /// Whether the pass is run; disabled passes are skipped.
+    pub enabled: bool,
+}
+
+impl PostProcessPass {
+    /// Creates a new pass running the fragment module of `shader`.
+    pub fn new(shader: Shader) -> Self {
+        PostProcessPass {
+            shader,
+            params: None,
+            builtin: None,
+            enabled: true,
+        }
+    }
+
+    /// Binds `params` to the pass's shader.
+    #[must_use]
+    pub fn with_params<Uniforms: AsStd140>(mut self, params: &ShaderParams<Uniforms>) -> Self {
+        self.set_params(params);
+        self
+    }
+
+    /// Binds `params` to the pass's shader.
+    ///
+    /// Like with [`Canvas::set_shader_params`], this needs to be called again after the
+    /// uniforms are updated for the new values to take effect.
+    pub fn set_params<Uniforms: AsStd140>(&mut self, params: &ShaderParams<Uniforms>) {
+        self.params = Some((
+            params.bind_group.clone().unwrap(/* always Some */),
+            params.layout.clone().unwrap(/* always Some */),
+            params.buffer_offset,
+        ));
+        self.builtin = None;
+    }
+
+    /// Returns the pass's shader.
+    #[inline]
+    pub fn shader(&self) -> &Shader {
+        &self.shader
+    }
+
+    fn builtin<Uniforms: AsStd140>(
+        ctx: &mut Context,
+        kind: Builtin,
+        uniforms: &Uniforms,
+    ) -> GameResult<Self> {
+        let code = match kind {
+            Builtin::Blur => include_str!("shader/blur.wgsl"),
+            Builtin::Bloom => include_str!("shader/bloom.wgsl"),
+            Builtin::Crt => include_str!("shader/crt.wgsl"),
+            Builtin::ColorGrading => include_str!("shader/color_grading.wgsl"),
+        };
+        let shader = ShaderBuilder::new().fragment_code(code).build(&ctx.gfx)?;
+        let params = ShaderParamsBuilder::new(uniforms).build(ctx);
+        let mut pass = PostProcessPass::new(shader).with_params(&params);
+        pass.builtin = Some((kind, params.uniform_buffer.unwrap(/* always Some */)));
+        Ok(pass)
+    }
+
+    /// Overwrites the uniforms of a built-in pass of the given kind, keeping its bind group.
+    fn write_builtin<Uniforms: AsStd140>(
+        &self,
+        gfx: &GraphicsContext,
+        kind: Builtin,
+        uniforms: &Uniforms,
+    ) -> GameResult {
+        match (&self.builtin, &self.params) {
+            (Some((builtin, buffer)), Some((_, _, offset))) if *builtin == kind => {
+                gfx.wgpu.queue.write_buffer(
+                    buffer,
+                    u64::from(*offset),
+                    uniforms.as_std140().as_bytes(),
+                );
+                Ok(())
+            }
+            _ => Err(GameError::RenderError(format!(
+                "post-processing pass is not a built-in {kind:?} pass"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Builtin {
+    Blur,
+    Bloom,
+    Crt,
+    ColorGrading,
+}
+
+/// Settings of the color grading pass, see [`PostProcessChain::push_color_grading`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ColorGrading {
+    /// Added to every color channel; `0.0` leaves colors unchanged.
+    pub brightness: f32,
+    /// Scales the distance of colors from mid-gray; `1.0` leaves colors unchanged.
+    pub contrast: f32,
+    /// Scales the distance of colors from their grayscale value; `0.0` is grayscale and `1.0`
+    /// leaves colors unchanged.
+    pub saturation: f32,
+    /// Multiplied with the final colors.
+    pub tint: Color,
+}
+
+impl Default for ColorGrading {
+    fn default() -> Self {
+        ColorGrading {
+            brightness: 0.0,
+            contrast: 1.0,
+            saturation: 1.0,
+            tint: Color::WHITE,
+        }
+    }
+}
+
+#[derive(AsStd140)]
+struct BlurUniforms {
+    direction: mint::Vector2<f32>,
+    radius: f32,
+}
+
+#[derive(AsStd140)]
+struct BloomUniforms {
+    threshold: f32,
+    intensity: f32,
+    radius: f32,
+}
+
+#[derive(AsStd140)]
+struct CrtUniforms {
+    scanlines: f32,
+    curvature: f32,
+    vignette: f32,
+}
+
+#[derive(AsStd140)]
+struct ColorGradingUniforms {
+    tint: mint::Vector4<f32>,
+    brightness: f32,
+    contrast: f32,
+    saturation: f32,
+}
+
+impl From<ColorGrading> for ColorGradingUniforms {
+    fn from(grading: ColorGrading) -> Self {
+        ColorGradingUniforms {
+            tint: <[f32; 4]>::from(grading.tint).into(),
+            brightness: grading.brightness,
+            contrast: grading.contrast,
+            saturation: grading.saturation,
+        }
+    }
+}
+
+const BLUR_DIRECTIONS: [[f32; 2]; 2] = [[1., 0.], [0., 1.]];
+
+/// An ordered list of fullscreen effects applied to a rendered scene.
+///
+/// Draw the scene onto [`PostProcessChain::scene_canvas`], then call
+/// [`PostProcessChain::present`] (or [`PostProcessChain::apply`] to draw more on top, e.g. a HUD).
+/// Each enabled pass renders the output of the previous one into one of two [`ScreenImage`]s,
+/// which are resized along with the window, and the last one renders onto the frame.
+///
+/// ```rust,no_run
+/// # use ggez::{graphics::*, Context, GameResult};
+/// # fn t(ctx: &mut Context) -> GameResult {
+/// let mut chain = PostProcessChain::new(ctx);
+/// let bloom = chain.push_bloom(ctx, 0.7, 1.0, 8.0)?;
+/// chain.push_crt(ctx, 0.25, 0.1, 0.3)?;
+///
+/// // then, every frame:
+/// chain.set_bloom(ctx, bloom, 0.7, 1.5, 8.0)?;
+/// let mut canvas = chain.scene_canvas(ctx, Color::BLACK);
+/// // draw the scene...
+/// canvas.finish(ctx)?;
+/// chain.present(ctx)
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct PostProcessChain {
+    images: [ScreenImage; 2],
+    passes: Vec<PostProcessPass>,
+}
+
+impl PostProcessChain {
+    /// Creates an empty chain, with images the size and format of the frame.
+    pub fn new(gfx: &impl Has<GraphicsContext>) -> Self {
+        PostProcessChain {
+            images: [
+                ScreenImage::new(gfx, None, 1., 1., 1),
+                ScreenImage::new(gfx, None, 1., 1., 1),
+            ],
+            passes: Vec::new(),
+        }
+    }
+
+    /// Appends a pass to the chain.
+    pub fn push(&mut self, pass: PostProcessPass) {
+        self.passes.push(pass);
+    }
+
+    /// Appends a gaussian blur spreading each pixel over `radius` pixels, returning the index
+    /// of its first pass.
+    ///
+    /// This is made of two passes, blurring horizontally and then vertically.
+    pub fn push_blur(&mut self, ctx: &mut Context, radius: f32) -> GameResult<usize> {
+        let index = self.passes.len();
+        for direction in BLUR_DIRECTIONS {
+            self.push(PostProcessPass::builtin(
+                ctx,
+                Builtin::Blur,
+                &BlurUniforms {
+                    direction: direction.into(),
+                    radius,
+                },
+            )?);
+        }
+        Ok(index)
+    }
+
+    /// Changes the radius of the blur whose first pass is at `index`, as returned by
+    /// [`PostProcessChain::push_blur`].
+    pub fn set_blur(
+        &mut self,
+        gfx: &impl Has<GraphicsContext>,
+        index: usize,
+        radius: f32,
+    ) -> GameResult {
+        for (direction, i) in BLUR_DIRECTIONS.into_iter().zip(index..) {
+            self.builtin_pass(i)?.write_builtin(
+                gfx.retrieve(),
+                Builtin::Blur,
+                &BlurUniforms {
+                    direction: direction.into(),
+                    radius,
+                },
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Appends a bloom pass, making the parts of the image brighter than `threshold` (in
+    /// `0.0..=1.0`) glow over roughly `radius` pixels, and returns the index of the pass.
+    pub fn push_bloom(
+        &mut self,
+        ctx: &mut Context,
+        threshold: f32,
+        intensity: f32,
+        radius: f32,
+    ) -> GameResult<usize> {
+        self.push(PostProcessPass::builtin(
+            ctx,
+            Builtin::Bloom,
+            &BloomUniforms {
+                threshold,
+                intensity,
+                radius,
+            },
+        )?);
+        Ok(self.passes.len() - 1)
+    }
+
+    /// Changes the settings of the bloom pass at `index`, as returned by
+    /// [`PostProcessChain::push_bloom`].
+    pub fn set_bloom(
+        &mut self,
+        gfx: &impl Has<GraphicsContext>,
+        index: usize,
+        threshold: f32,
+        intensity: f32,
+        radius: f32,
+    ) -> GameResult {
+        self.builtin_pass(index)?.write_builtin(
+            gfx.retrieve(),
+            Builtin::Bloom,
+            &BloomUniforms {
+                threshold,
+                intensity,
+                radius,
+            },
+        )
+    }
+
+    /// Appends a pass imitating a CRT screen, which darkens every other line by `scanlines`,
+    /// bulges the image by `curvature` and darkens its corners by `vignette` (all in `0.0..=1.0`),
+    /// and returns the index of the pass.
+    pub fn push_crt(
+        &mut self,
+        ctx: &mut Context,
+        scanlines: f32,
+        curvature: f32,
+        vignette: f32,
+    ) -> GameResult<usize> {
+        self.push(PostProcessPass::builtin(
+            ctx,
+            Builtin::Crt,
+            &CrtUniforms {
+                scanlines,
+                curvature,
+                vignette,
+            },
+        )?);
+        Ok(self.passes.len() - 1)
+    }
+
+    /// Changes the settings of the CRT pass at `index`, as returned by
+    /// [`PostProcessChain::push_crt`].
+    pub fn set_crt(
+        &mut self,
+        gfx: &impl Has<GraphicsContext>,
+        index: usize,
+        scanlines: f32,
+        curvature: f32,
+        vignette: f32,
+    ) -> GameResult {
+        self.builtin_pass(index)?.write_builtin(
+            gfx.retrieve(),
+            Builtin::Crt,
+            &CrtUniforms {
+                scanlines,
+                curvature,
+                vignette,
+            },
+        )
+    }
+
+    /// Appends a pass adjusting brightness, contrast, saturation and tint, and returns its index.
+    pub fn push_color_grading(
+        &mut self,
+        ctx: &mut Context,
+        grading: ColorGrading,
+    ) -> GameResult<usize> {
+        self.push(PostProcessPass::builtin(
+            ctx,
+            Builtin::ColorGrading,
+            &ColorGradingUniforms::from(grading),
+        )?);
+        Ok(self.passes.len() - 1)
+    }
+
+    /// Changes the settings of the color grading pass at `index`, as returned by
+    /// [`PostProcessChain::push_color_grading`].
+    pub fn set_color_grading(
+        &mut self,
+        gfx: &impl Has<GraphicsContext>,
+        index: usize,
+        grading: ColorGrading,
+    ) -> GameResult {
+        self.builtin_pass(index)?.write_builtin(
+            gfx.retrieve(),
+            Builtin::ColorGrading,
+            &ColorGradingUniforms::from(grading),
+        )
+    }
+
+    fn builtin_pass(&self, index: usize) -> GameResult<&PostProcessPass> {
+        self.passes.get(index).ok_or_else(|| {
+            GameError::RenderError(format!("no post-processing pass at index {index}"))
+        })
+    }
+
+    /// Returns the passes of the chain, in the order they're run.
+    #[inline]
+    pub fn passes(&self) -> &[PostProcessPass] {
+        &self.passes
+    }
+
+    /// Returns mutable access to the passes of the chain, e.g. to disable or reorder them.
+    #[inline]
+    pub fn passes_mut(&mut self) -> &mut Vec<PostProcessPass> {
+        &mut self.passes
+    }
+
+    /// Returns the image the scene should be drawn onto.
+    pub fn scene_image(&mut self, gfx: &impl Has<GraphicsContext>) -> Image {
+        self.images[0].image(gfx)
+    }
+
+    /// Returns a canvas drawing onto [`PostProcessChain::scene_image`].
+    pub fn scene_canvas(
+        &mut self,
+        gfx: &impl Has<GraphicsContext>,
+        clear: impl Into<Option<Color>>,
+    ) -> Canvas {
+        Canvas::from_screen_image(gfx, &mut self.images[0], clear)
+    }
+
+    /// Runs the chain over the scene image, drawing the result onto `target`, which is left
+    /// with the shader, shader params and sampler it had before.
+    ///
+    /// `target` should have the same size as the frame. The last pass is drawn with the
+    /// target's blend mode, while the others replace the contents of their image.
+    pub fn apply(
+        &mut self,
+        gfx: &mut impl HasMut<GraphicsContext>,
+        target: &mut Canvas,
+    ) -> GameResult {
+        let gfx = gfx.retrieve_mut();
+        let mut images = [self.images[0].image(gfx), self.images[1].image(gfx)];
+        let mut passes = self.passes.iter().filter(|pass| pass.enabled).peekable();
+
+        while let Some(pass) = passes.next() {
+            if passes.peek().is_none() {
+                let (shader, params, sampler) = (
+                    target.shader(),
+                    target.raw_shader_params(),
+                    target.sampler(),
+                );
+                target.set_shader(&pass.shader);
+                target.set_raw_shader_params(pass.params.clone());
+                target.set_sampler(Sampler::linear_clamp());
+                target.draw(&images[0], DrawParam::default());
+                target.set_shader(&shader);
+                target.set_raw_shader_params(params);
+                target.set_sampler(sampler);
+                return Ok(());
+            }
+
+            let mut canvas = Canvas::from_image(gfx, images[1].clone(), None);
+            canvas.set_shader(&pass.shader);
+            canvas.set_raw_shader_params(pass.params.clone());
+            canvas.set_sampler(Sampler::linear_clamp());
+            canvas.set_blend_mode(BlendMode::REPLACE);
+            canvas.draw(&images[0], DrawParam::default());
+            canvas.finish(gfx)?;
+            images.swap(0, 1);
+        }
+
+        // no passes are enabled
+        target.draw(&images[0], DrawParam::default());
+        Ok(())
+    }
+
+    /// Runs the chain over the scene image, drawing the result onto the frame.
+    pub fn present(&mut self, gfx: &mut impl HasMut<GraphicsContext>) -> GameResult {
+        let gfx = gfx.retrieve_mut();
+        let mut canvas = Canvas::from_frame(gfx, None);
+        self.apply(gfx, &mut canvas)?;
+        canvas.finish(gfx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{context::headless_ctx_for_tests, graphics::ImageFormat};
+
+    #[test]
+    fn headless_test_post_process_chain() {
+        let mut ctx = headless_ctx_for_tests(8, 8);
+
+        let mut chain = PostProcessChain::new(&ctx);
+        assert_eq!(chain.push_blur(&mut ctx, 2.).unwrap(), 0);
+        let grading = chain
+            .push_color_grading(&mut ctx, ColorGrading::default())
+            .unwrap();
+        assert_eq!(grading, 2);
+        assert_eq!(chain.passes().len(), 3);
+
+        // the uniforms are updated in place, without rebinding the params
+        let params = chain.passes()[grading].params.clone();
+        chain
+            .set_color_grading(
+                &ctx,
+                grading,
+                ColorGrading {
+                    saturation: 0.0,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(chain.passes()[grading].params, params);
+        chain.set_blur(&ctx, 0, 2.).unwrap();
+        assert!(chain.set_bloom(&ctx, grading, 0.5, 1.0, 4.0).is_err());
+        assert!(chain.set_blur(&ctx, 1, 2.).is_err());
+
+        ctx.gfx.begin_frame().unwrap();
+        let canvas = chain.scene_canvas(&ctx, Color::new(1., 0., 0., 1.));
+        canvas.finish(&mut ctx).unwrap();
+        let target = Image::new_canvas_image(&ctx, ImageFormat::Rgba8UnormSrgb, 8, 8, 1);
+        let mut canvas = Canvas::from_image(&ctx, target.clone(), Color::BLACK);
+        chain.apply(&mut ctx, &mut canvas).unwrap();
+        canvas.finish(&mut ctx).unwrap();
+        ctx.gfx.end_frame().unwrap();
+
+        // a blurred flat color stays flat, and then turns gray
+        let pixels = target.to_pixels(&ctx).unwrap();
+        let first = [pixels[0], pixels[1], pixels[2], pixels[3]];
+        assert!(first[0] > 0 && first[0] < 255, "{first:?}");
+        assert_eq!(first[0], first[1]);
+        assert_eq!(first[1], first[2]);
+        assert!(pixels.chunks_exact(4).all(|p| p == first));
+
+        // with every pass disabled the scene is copied as is
+        chain
+            .passes_mut()
+            .iter_mut()
+            .for_each(|pass| pass.enabled = false);
+        ctx.gfx.begin_frame().unwrap();
+        let mut canvas = Canvas::from_image(&ctx, target.clone(), Color::BLACK);
+        chain.apply(&mut ctx, &mut canvas).unwrap();
+        canvas.finish(&mut ctx).unwrap();
+        ctx.gfx.end_frame().unwrap();
+        let pixels = target.to_pixels(&ctx).unwrap();
+        assert!(pixels.chunks_exact(4).all(|p| p == [255, 0, 0, 255]));
+
+        // the other built-in passes compile and run
+        let mut chain = PostProcessChain::new(&ctx);
+        let bloom = chain.push_bloom(&mut ctx, 0.5, 1.0, 4.0).unwrap();
+        let crt = chain.push_crt(&mut ctx, 0.5, 0.1, 0.3).unwrap();
+        chain.set_bloom(&ctx, bloom, 0.6, 2.0, 2.0).unwrap();
+        chain.set_crt(&ctx, crt, 0.2, 0.0, 0.5).unwrap();
+        ctx.gfx.begin_frame().unwrap();
+        let canvas = chain.scene_canvas(&ctx, Color::WHITE);
+        canvas.finish(&mut ctx).unwrap();
+        chain.present(&mut ctx).unwrap();
+        ctx.gfx.end_frame().unwrap();
+    }
+}
@@ -0,0 +1,599 @@
+use super::{
+    context::GraphicsContext, Canvas, CanvasStats, Color, DrawMode, DrawParam, Mesh, MeshBuilder,
+    Rect, Text,
+};
+use crate::GameResult;
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// The CPU scopes the [`Profiler`] measures.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ProfileScope {
+    /// [`EventHandler::update`](crate::event::EventHandler::update), including every fixed timestep update.
+    Update,
+    /// [`EventHandler::draw`](crate::event::EventHandler::draw).
+    Draw,
+    /// [`GraphicsContext::end_frame`]: submitting the frame and presenting it.
+    Present,
+}
+
+/// Things that happened while rendering a frame, counted by the [`Profiler`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct FrameCounters {
+    /// Render passes, i.e. finished [`Canvas`]es.
+    pub canvases: u32,
+    /// Draw calls issued to the GPU.
+    pub draw_calls: u32,
+    /// Times a different render pipeline had to be bound.
+    pub pipeline_switches: u32,
+    /// Bind groups that were found in the cache.
+    pub bind_group_cache_hits: u32,
+    /// Bind groups that had to be created because they weren't in the cache.
+    pub bind_group_cache_misses: u32,
+    /// Glyph images uploaded to the text cache texture.
+    pub glyph_uploads: u32,
+}
+
+/// The measurements for a single frame.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct FrameProfile {
+    /// CPU time spent in `update`.
+    pub update: Duration,
+    /// CPU time spent in `draw`.
+    pub draw: Duration,
+    /// CPU time spent submitting and presenting the frame.
+    pub present: Duration,
+    /// GPU time spent rendering [`Canvas`]es, if the GPU supports timestamp queries.
+    ///
+    /// The GPU reports this asynchronously, so it's the measurement that arrived while the
+    /// frame ended, which is usually from one or two frames earlier. It's `None` when no new
+    /// measurement arrived, since frames are skipped while the previous one is read back.
+    pub gpu: Option<Duration>,
+    /// What happened while rendering.
+    pub counters: FrameCounters,
+}
+
+impl FrameProfile {
+    /// Returns the total CPU time of the frame's scopes.
+    pub fn cpu(&self) -> Duration {
+        self.update + self.draw + self.present
+    }
+}
+
+/// Records how long each frame spends updating, drawing and presenting, plus a few
+/// rendering counters, and optionally shows them in an overlay.
+///
+/// It's found in [`GraphicsContext::profiler`] and is disabled by default:
+///
+/// ```rust,no_run
+/// # fn t(ctx: &mut ggez::Context) {
+/// ctx.gfx.profiler_mut().set_enabled(true);
+/// // Graph the last frames in the top left corner of the screen.
+/// ctx.gfx.profiler_mut().set_overlay_visible(true);
+///
+/// // ... some frames later:
+/// if let Some(frame) = ctx.gfx.profiler().last_frame() {
+///     println!("{:?} spent drawing, {} draw calls", frame.draw, frame.counters.draw_calls);
+/// }
+/// # }
+/// ```
+///
+/// [`event::run()`](crate::event::run) records the `update` and `draw` scopes itself. If you're
+/// writing your own event loop, use [`Profiler::record`] for them; the present scope and the
+/// counters are recorded by the [`GraphicsContext`].
+#[derive(Debug)]
+pub struct Profiler {
+    enabled: bool,
+    overlay_visible: bool,
+    history_len: usize,
+    history: VecDeque<FrameProfile>,
+    current: FrameProfile,
+    gpu: Option<GpuTimer>,
+}
+
+impl Profiler {
+    /// How many frames are kept by default.
+    pub const DEFAULT_HISTORY_LEN: usize = 120;
+
+    pub(crate) fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        Profiler {
+            enabled: false,
+            overlay_visible: false,
+            history_len: Self::DEFAULT_HISTORY_LEN,
+            history: VecDeque::new(),
+            current: FrameProfile::default(),
+            gpu: GpuTimer::new(device, queue),
+        }
+    }
+
+    /// Returns `true` if frames are being recorded.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Starts or stops recording frames. Stopping keeps the frames recorded so far.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Returns `true` if the overlay is drawn over each frame while the profiler is enabled.
+    pub fn is_overlay_visible(&self) -> bool {
+        self.overlay_visible
+    }
+
+    /// Shows or hides the overlay. Showing it enables the profiler.
+    pub fn set_overlay_visible(&mut self, visible: bool) {
+        self.overlay_visible = visible;
+        self.enabled |= visible;
+    }
+
+    /// Shows the overlay if it's hidden and hides it if it's visible.
+    pub fn toggle_overlay(&mut self) {
+        self.set_overlay_visible(!self.overlay_visible);
+    }
+
+    /// Returns `true` if the GPU supports timestamp queries, which are needed for
+    /// [`FrameProfile::gpu`].
+    pub fn supports_gpu_timing(&self) -> bool {
+        self.gpu.is_some()
+    }
+
+    /// Returns how many frames are kept.
+    pub fn history_len(&self) -> usize {
+        self.history_len
+    }
+
+    /// Sets how many frames are kept, dropping the oldest ones if there are too many.
+    pub fn set_history_len(&mut self, len: usize) {
+        self.history_len = len;
+        while self.history.len() > len {
+            let _ = self.history.pop_front();
+        }
+    }
+
+    /// Returns the recorded frames, oldest first.
+    pub fn frames(&self) -> impl ExactSizeIterator<Item = &FrameProfile> + '_ {
+        self.history.iter()
+    }
+
+    /// Returns the most recently recorded frame.
+    pub fn last_frame(&self) -> Option<&FrameProfile> {
+        self.history.back()
+    }
+
+    /// Returns the average of the recorded frames.
+    ///
+    /// The GPU time is averaged over the frames that have one.
+    pub fn average(&self) -> FrameProfile {
+        let count = self.history.len().max(1) as u32;
+        let mut sum = FrameProfile::default();
+        let mut counters = [0u64; 6];
+        let (mut gpu, mut gpu_frames) = (Duration::ZERO, 0);
+        for frame in &self.history {
+            sum.update += frame.update;
+            sum.draw += frame.draw;
+            sum.present += frame.present;
+            if let Some(time) = frame.gpu {
+                gpu += time;
+                gpu_frames += 1;
+            }
+            let c = &frame.counters;
+            for (total, value) in counters.iter_mut().zip([
+                c.canvases,
+                c.draw_calls,
+                c.pipeline_switches,
+                c.bind_group_cache_hits,
+                c.bind_group_cache_misses,
+                c.glyph_uploads,
+            ]) {
+                *total += u64::from(value);
+            }
+        }
+        let [canvases, draw_calls, pipeline_switches, hits, misses, glyph_uploads] =
+            counters.map(|total| (total / u64::from(count)) as u32);
+        FrameProfile {
+            update: sum.update / count,
+            draw: sum.draw / count,
+            present: sum.present / count,
+            gpu: (gpu_frames > 0).then(|| gpu / gpu_frames),
+            counters: FrameCounters {
+                canvases,
+                draw_calls,
+                pipeline_switches,
+                bind_group_cache_hits: hits,
+                bind_group_cache_misses: misses,
+                glyph_uploads,
+            },
+        }
+    }
+
+    /// Forgets all recorded frames.
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+
+    /// Adds `duration` to the time spent in `scope` in the current frame.
+    pub fn record(&mut self, scope: ProfileScope, duration: Duration) {
+        let time = match scope {
+            ProfileScope::Update => &mut self.current.update,
+            ProfileScope::Draw => &mut self.current.draw,
+            ProfileScope::Present => &mut self.current.present,
+        };
+        *time += duration;
+    }
+
+    /// The counters of the current frame, for the renderer to update.
+    pub(crate) fn counters(&mut self) -> &mut FrameCounters {
+        &mut self.current.counters
+    }
+
+    /// Adds what a finished canvas drew to the current frame's counters.
+    pub(crate) fn add_canvas(&mut self, stats: &CanvasStats) {
+        let counters = &mut self.current.counters;
+        counters.canvases += 1;
+        counters.draw_calls += stats.draw_calls;
+        counters.pipeline_switches += stats.pipeline_switches;
+        counters.glyph_uploads += stats.glyph_uploads;
+    }
+
+    /// Picks up the GPU timings that have come back since the last frame.
+    pub(crate) fn begin_frame(&mut self, device: &wgpu::Device) {
+        if let Some(gpu) = &mut self.gpu {
+            gpu.poll(device);
+        }
+    }
+
+    /// Writes a timestamp before a render pass, if GPU timing is possible in this frame.
+    /// Returns the query index to pass to [`end_gpu_scope`](Self::end_gpu_scope).
+    pub(crate) fn begin_gpu_scope(&mut self, cmd: &mut wgpu::CommandEncoder) -> Option<u32> {
+        match &mut self.gpu {
+            Some(gpu) if self.enabled => gpu.begin_scope(cmd),
+            _ => None,
+        }
+    }
+
+    /// Writes the timestamp after a render pass.
+    pub(crate) fn end_gpu_scope(&mut self, cmd: &mut wgpu::CommandEncoder, query: Option<u32>) {
+        if let (Some(gpu), Some(query)) = (&self.gpu, query) {
+            cmd.write_timestamp(&gpu.query_set, query);
+        }
+    }
+
+    /// Copies this frame's timestamps to be read back, right before the commands are submitted.
+    pub(crate) fn resolve_gpu_scopes(&mut self, cmd: &mut wgpu::CommandEncoder) {
+        if let Some(gpu) = &mut self.gpu {
+            gpu.resolve(cmd);
+        }
+    }
+
+    /// Finishes the current frame, once its commands have been submitted, and adds it to the history.
+    pub(crate) fn end_frame(&mut self) {
+        let gpu = self.gpu.as_mut().and_then(|gpu| {
+            gpu.map();
+            gpu.last_time.take()
+        });
+        let frame = std::mem::take(&mut self.current);
+        if self.enabled && self.history_len > 0 {
+            if self.history.len() >= self.history_len {
+                let _ = self.history.pop_front();
+            }
+            self.history.push_back(FrameProfile { gpu, ..frame });
+        }
+    }
+
+    /// Draws the overlay onto the frame image.
+    pub(crate) fn draw_overlay(gfx: &mut GraphicsContext) -> GameResult {
+        const BAR_WIDTH: f32 = 2.0;
+        const GRAPH_HEIGHT: f32 = 80.0;
+        const PADDING: f32 = 8.0;
+        // The top of the graph is two 60 FPS frames.
+        let frame_budget = Duration::from_secs(1) / 60;
+        let graph_scale = GRAPH_HEIGHT / (2.0 * frame_budget.as_secs_f32());
+
+        let profiler = &gfx.profiler;
+        let width = profiler.history_len.max(1) as f32 * BAR_WIDTH;
+        let average = profiler.average();
+        let last = profiler.last_frame().copied().unwrap_or_default();
+        let ms = |time: Duration| time.as_secs_f64() * 1000.0;
+        let mut text = Text::new(format!(
+            "cpu {:.2} ms (update {:.2}, draw {:.2}, present {:.2})\n\
+             gpu {}\n\
+             {} canvases, {} draw calls, {} pipeline switches\n\
+             bind groups: {} cached, {} created; {} glyph uploads",
+            ms(average.cpu()),
+            ms(average.update),
+            ms(average.draw),
+            ms(average.present),
+            average
+                .gpu
+                .map_or_else(|| String::from("n/a"), |gpu| format!("{:.2} ms", ms(gpu))),
+            last.counters.canvases,
+            last.counters.draw_calls,
+            last.counters.pipeline_switches,
+            last.counters.bind_group_cache_hits,
+            last.counters.bind_group_cache_misses,
+            last.counters.glyph_uploads,
+        ));
+        let _ = text.set_scale(14.0);
+        let text_size = text.measure(gfx)?;
+        let panel = Rect::new(
+            PADDING,
+            PADDING,
+            width.max(text_size.x) + 2.0 * PADDING,
+            GRAPH_HEIGHT + text_size.y + 3.0 * PADDING,
+        );
+
+        let mut builder = MeshBuilder::new();
+        let _ = builder.rectangle(DrawMode::fill(), panel, Color::new(0.0, 0.0, 0.0, 0.7))?;
+        let graph_bottom = panel.y + PADDING + GRAPH_HEIGHT;
+        let scopes = [
+            (ProfileScope::Update, Color::new(0.3, 0.6, 1.0, 1.0)),
+            (ProfileScope::Draw, Color::new(0.3, 0.9, 0.4, 1.0)),
+            (ProfileScope::Present, Color::new(1.0, 0.6, 0.2, 1.0)),
+        ];
+        for (i, frame) in profiler.history.iter().enumerate() {
+            let x = panel.x + PADDING + i as f32 * BAR_WIDTH;
+            let mut y = graph_bottom;
+            for (scope, color) in scopes {
+                let time = match scope {
+                    ProfileScope::Update => frame.update,
+                    ProfileScope::Draw => frame.draw,
+                    ProfileScope::Present => frame.present,
+                };
+                let height =
+                    (time.as_secs_f32() * graph_scale).min(y - (graph_bottom - GRAPH_HEIGHT));
+                if height > 0.0 {
+                    y -= height;
+                    let _ = builder.rectangle(
+                        DrawMode::fill(),
+                        Rect::new(x, y, BAR_WIDTH, height),
+                        color,
+                    )?;
+                }
+            }
+        }
+        let budget_y = graph_bottom - frame_budget.as_secs_f32() * graph_scale;
+        let _ = builder.rectangle(
+            DrawMode::fill(),
+            Rect::new(panel.x + PADDING, budget_y, width, 1.0),
+            Color::new(1.0, 1.0, 1.0, 0.5),
+        )?;
+        let mesh = Mesh::from_data(gfx, builder.build());
+
+        // The overlay shouldn't show up in the numbers it displays.
+        let (hits, misses) = gfx.bind_group_cache.take_stats();
+        let mut counters = gfx.profiler.current.counters;
+        counters.bind_group_cache_hits += hits;
+        counters.bind_group_cache_misses += misses;
+        let enabled = std::mem::replace(&mut gfx.profiler.enabled, false);
+        let mut canvas = Canvas::from_frame(gfx, None);
+        canvas.draw(&mesh, DrawParam::new());
+        canvas.draw(
+            &text,
+            DrawParam::new().dest([panel.x + PADDING, graph_bottom + PADDING]),
+        );
+        let result = canvas.finish(gfx);
+        let _ = gfx.bind_group_cache.take_stats();
+        gfx.profiler.enabled = enabled;
+        gfx.profiler.current.counters = counters;
+        result
+    }
+}
+
+/// The state of reading back the timestamps of a frame.
+#[derive(Debug)]
+enum Readback {
+    Idle,
+    Pending { queries: u32, status: Arc<AtomicU8> },
+}
+
+const READBACK_WAITING: u8 = 0;
+const READBACK_MAPPED: u8 = 1;
+const READBACK_FAILED: u8 = 2;
+
+/// Measures the GPU time of render passes with timestamp queries.
+struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    /// Nanoseconds per timestamp tick.
+    period: f32,
+    /// Queries written in the current frame.
+    queries: u32,
+    readback: Readback,
+    last_time: Option<Duration>,
+}
+
+impl fmt::Debug for GpuTimer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GpuTimer")
+            .field("queries", &self.queries)
+            .field("readback", &self.readback)
+            .field("last_time", &self.last_time)
+            .finish_non_exhaustive()
+    }
+}
+
+impl GpuTimer {
+    /// Two timestamps per canvas; canvases beyond this aren't timed.
+    const MAX_QUERIES: u32 = 256;
+
+    fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+        let size = u64::from(Self::MAX_QUERIES) * std::mem::size_of::<u64>() as u64;
+        Some(GpuTimer {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("profiler"),
+                ty: wgpu::QueryType::Timestamp,
+                count: Self::MAX_QUERIES,
+            }),
+            resolve_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("profiler resolve"),
+                size,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            readback_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("profiler readback"),
+                size,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            period: queue.get_timestamp_period(),
+            queries: 0,
+            readback: Readback::Idle,
+            last_time: None,
+        })
+    }
+
+    fn begin_scope(&mut self, cmd: &mut wgpu::CommandEncoder) -> Option<u32> {
+        // The readback buffer is still busy with an earlier frame, so this one isn't timed.
+        if !matches!(self.readback, Readback::Idle) || self.queries + 2 > Self::MAX_QUERIES {
+            return None;
+        }
+        cmd.write_timestamp(&self.query_set, self.queries);
+        self.queries += 2;
+        Some(self.queries - 1)
+    }
+
+    fn resolve(&mut self, cmd: &mut wgpu::CommandEncoder) {
+        if self.queries == 0 {
+            return;
+        }
+        cmd.resolve_query_set(&self.query_set, 0..self.queries, &self.resolve_buffer, 0);
+        cmd.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.readback_buffer,
+            0,
+            self.size(self.queries),
+        );
+    }
+
+    fn map(&mut self) {
+        if self.queries == 0 {
+            return;
+        }
+        let status = Arc::new(AtomicU8::new(READBACK_WAITING));
+        let callback_status = status.clone();
+        self.readback_buffer
+            .slice(..self.size(self.queries))
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let status = if result.is_ok() {
+                    READBACK_MAPPED
+                } else {
+                    READBACK_FAILED
+                };
+                callback_status.store(status, Ordering::Release);
+            });
+        self.readback = Readback::Pending {
+            queries: self.queries,
+            status,
+        };
+        self.queries = 0;
+    }
+
+    fn poll(&mut self, device: &wgpu::Device) {
+        let Readback::Pending { queries, status } = &self.readback else {
+            return;
+        };
+        let queries = *queries;
+        let _ = device.poll(wgpu::Maintain::Poll);
+        match status.load(Ordering::Acquire) {
+            READBACK_WAITING => return,
+            READBACK_MAPPED => {
+                let slice = self.readback_buffer.slice(..self.size(queries));
+                let ticks: u64 = {
+                    let data = slice.get_mapped_range();
+                    let timestamps: &[u64] = bytemuck::cast_slice(&data);
+                    timestamps
+                        .chunks_exact(2)
+                        .map(|pair| pair[1].saturating_sub(pair[0]))
+                        .sum()
+                };
+                self.readback_buffer.unmap();
+                self.last_time = Some(Duration::from_nanos(
+                    (ticks as f64 * f64::from(self.period)) as u64,
+                ));
+            }
+            _ => {}
+        }
+        self.readback = Readback::Idle;
+    }
+
+    fn size(&self, queries: u32) -> u64 {
+        u64::from(queries) * std::mem::size_of::<u64>() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{context::headless_ctx_for_tests, graphics};
+
+    #[test]
+    fn headless_test_profiler() {
+        let mut ctx = headless_ctx_for_tests(64, 64);
+        let text = graphics::Text::new("profiled");
+        let draw_frame = |ctx: &mut crate::Context| {
+            ctx.gfx.begin_frame().unwrap();
+            ctx.gfx
+                .profiler_mut()
+                .record(ProfileScope::Draw, Duration::from_millis(2));
+            let mut canvas = Canvas::from_frame(ctx, Color::BLACK);
+            canvas.draw(&graphics::Quad, DrawParam::new().scale([4.0, 4.0]));
+            canvas.draw(
+                &graphics::Quad,
+                DrawParam::new().dest([8.0, 8.0]).scale([4.0, 4.0]),
+            );
+            canvas.draw(&text, DrawParam::new());
+            canvas.finish(ctx).unwrap();
+            ctx.gfx.end_frame().unwrap();
+        };
+
+        // Nothing is recorded until the profiler is enabled.
+        draw_frame(&mut ctx);
+        assert!(ctx.gfx.profiler().last_frame().is_none());
+
+        ctx.gfx.profiler_mut().set_enabled(true);
+        ctx.gfx.profiler_mut().set_history_len(2);
+        for _ in 0..3 {
+            draw_frame(&mut ctx);
+        }
+        let profiler = ctx.gfx.profiler();
+        assert_eq!(profiler.frames().len(), 2);
+        let frame = *profiler.last_frame().unwrap();
+        assert_eq!(frame.draw, Duration::from_millis(2));
+        assert!(frame.present > Duration::ZERO);
+        assert_eq!(frame.counters.canvases, 1);
+        // The quads are batched.
+        assert_eq!(frame.counters.draw_calls, 2);
+        assert_eq!(frame.counters.pipeline_switches, 2);
+        assert!(frame.counters.bind_group_cache_hits > 0);
+        assert_eq!(frame.counters.bind_group_cache_misses, 0);
+        // The glyphs were uploaded in the first frame.
+        assert_eq!(frame.counters.glyph_uploads, 0);
+        assert_eq!(profiler.average().counters, frame.counters);
+
+        // The overlay is drawn but not counted.
+        ctx.gfx.profiler_mut().set_overlay_visible(true);
+        draw_frame(&mut ctx);
+        draw_frame(&mut ctx);
+        assert_eq!(
+            ctx.gfx.profiler().last_frame().unwrap().counters,
+            frame.counters
+        );
+        let pixels = ctx.gfx.frame().to_pixels(&ctx.gfx).unwrap();
+        // The overlay's background darkens the second quad.
+        assert!(pixels[(10 * 64 + 10) * 4] < 255);
+    }
+}
@@ -1,12 +1,17 @@
+use std::hash::{Hash, Hasher};
 use std::io::Read;
 use std::marker::PhantomData;
+use std::sync::{Arc, RwLock};
 
-use crate::{context::Has, Context, GameError, GameResult};
+use crate::{context::Has, filesystem::ReloadAsset, Context, GameError, GameResult};
 
 use super::{
     context::GraphicsContext,
     gpu::{
-        arc::{ArcBindGroup, ArcBindGroupLayout, ArcSampler, ArcShaderModule, ArcTextureView},
+        arc::{
+            ArcBindGroup, ArcBindGroupLayout, ArcBuffer, ArcSampler, ArcShaderModule,
+            ArcTextureView,
+        },
         bind_group::BindGroupBuilder,
         growing::GrowingBufferArena,
     },
@@ -15,7 +20,7 @@ use super::{
 };
 use crevice::std140::Std140;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum ShaderSource<'a> {
     None,
     Path(&'a str),
@@ -90,45 +95,87 @@ impl<'a> ShaderBuilder<'a> {
     }
 
     /// Create a Shader from the builder.
+    ///
+    /// WGSL errors are returned as a [`GameError::RenderError`]. Shaders with code from resource
+    /// paths are rebuilt when the files change if
+    /// [asset hot reloading](crate::filesystem::Filesystem::set_asset_hot_reload) is on.
     pub fn build(self, gfx: &impl Has<GraphicsContext>) -> GameResult<Shader> {
         let gfx = gfx.retrieve();
-        let load = |s: &str| {
-            Some(ArcShaderModule::new(gfx.wgpu.device.create_shader_module(
-                wgpu::ShaderModuleDescriptor {
-                    label: None,
-                    source: wgpu::ShaderSource::Wgsl(s.into()),
-                },
-            )))
-        };
-        let load_resource = |path: &str| -> GameResult<Option<ArcShaderModule>> {
-            let mut encoded = Vec::new();
-            _ = gfx.fs.open(path)?.read_to_end(&mut encoded)?;
-            Ok(load(
-                &String::from_utf8(encoded).map_err(GameError::ShaderEncodingError)?,
-            ))
-        };
         let load_any = |source| -> GameResult<Option<ArcShaderModule>> {
             Ok(match source {
-                ShaderSource::Code(source) => load(source),
-                ShaderSource::Path(source) => load_resource(source)?,
+                ShaderSource::Code(source) => Some(create_module(gfx, source, None)?),
+                ShaderSource::Path(source) => Some(load_module(gfx, source)?),
                 ShaderSource::None => None,
             })
         };
-        Ok(if self.vs == self.fs {
-            let module = load_any(self.vs)?;
-            Shader {
-                vs_module: module.clone(),
-                fs_module: module,
-            }
+        let vs_module = load_any(self.vs)?;
+        let fs_module = if self.vs == self.fs {
+            vs_module.clone()
         } else {
-            Shader {
-                vs_module: load_any(self.vs)?,
-                fs_module: load_any(self.fs)?,
+            load_any(self.fs)?
+        };
+
+        let watch = |source, module: &Option<ArcShaderModule>| match (source, module) {
+            (ShaderSource::Path(path), Some(module)) if gfx.fs.is_asset_hot_reload_enabled() => {
+                let watched = Arc::new(WatchedModule {
+                    path: path.to_owned(),
+                    module: RwLock::new(module.clone()),
+                });
+                gfx.fs.watch_asset(path, Arc::downgrade(&watched) as _);
+                Some(watched)
             }
+            _ => None,
+        };
+        let vs = watch(self.vs, &vs_module);
+        let fs = if self.vs == self.fs {
+            vs.clone()
+        } else {
+            watch(self.fs, &fs_module)
+        };
+        let watched = (vs.is_some() || fs.is_some()).then(|| Arc::new(WatchedShader { vs, fs }));
+
+        Ok(Shader {
+            vs_module,
+            fs_module,
+            watched,
         })
     }
 }
 
+fn create_module(
+    gfx: &GraphicsContext,
+    source: &str,
+    path: Option<&str>,
+) -> GameResult<ArcShaderModule> {
+    gfx.wgpu
+        .device
+        .push_error_scope(wgpu::ErrorFilter::Validation);
+    let module = gfx
+        .wgpu
+        .device
+        .create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: path,
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+    match pollster::block_on(gfx.wgpu.device.pop_error_scope()) {
+        Some(e) => Err(GameError::RenderError(format!(
+            "failed to compile shader {}: {e}",
+            path.unwrap_or("code")
+        ))),
+        None => Ok(ArcShaderModule::new(module)),
+    }
+}
+
+fn load_module(gfx: &GraphicsContext, path: &str) -> GameResult<ArcShaderModule> {
+    let mut encoded = Vec::new();
+    _ = gfx.fs.open(path)?.read_to_end(&mut encoded)?;
+    create_module(
+        gfx,
+        &String::from_utf8(encoded).map_err(GameError::ShaderEncodingError)?,
+        Some(path),
+    )
+}
+
 impl Default for ShaderBuilder<'_> {
     fn default() -> Self {
         Self::new()
@@ -187,10 +234,71 @@ impl Default for ShaderBuilder<'_> {
 ///     /* ... */
 /// }
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone)]
 pub struct Shader {
     pub(crate) vs_module: Option<ArcShaderModule>,
     pub(crate) fs_module: Option<ArcShaderModule>,
+    // Where the modules are reloaded from, for shaders built from resource paths.
+    pub(crate) watched: Option<Arc<WatchedShader>>,
+}
+
+impl Shader {
+    /// Returns this shader with the modules it was last (re)built with.
+    pub(crate) fn current(&self) -> Shader {
+        let Some(watched) = &self.watched else {
+            return self.clone();
+        };
+        let current = |watched: &Option<Arc<WatchedModule>>, module: &Option<ArcShaderModule>| {
+            watched
+                .as_ref()
+                .and_then(|watched| watched.module.read().ok().map(|module| module.clone()))
+                .or_else(|| module.clone())
+        };
+        Shader {
+            vs_module: current(&watched.vs, &self.vs_module),
+            fs_module: current(&watched.fs, &self.fs_module),
+            watched: self.watched.clone(),
+        }
+    }
+}
+
+// Shaders are the same if their modules are, wherever they're reloaded from.
+impl PartialEq for Shader {
+    fn eq(&self, other: &Self) -> bool {
+        self.vs_module == other.vs_module && self.fs_module == other.fs_module
+    }
+}
+
+impl Eq for Shader {}
+
+impl Hash for Shader {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.vs_module.hash(state);
+        self.fs_module.hash(state);
+    }
+}
+
+/// The modules of a [`Shader`] that were built from resource paths.
+#[derive(Debug)]
+pub(crate) struct WatchedShader {
+    vs: Option<Arc<WatchedModule>>,
+    fs: Option<Arc<WatchedModule>>,
+}
+
+/// The file a shader module was built from, see [`ShaderBuilder::build`].
+#[derive(Debug)]
+struct WatchedModule {
+    path: String,
+    module: RwLock<ArcShaderModule>,
+}
+
+impl ReloadAsset for WatchedModule {
+    fn reload(&self, gfx: &GraphicsContext, bytes: Vec<u8>) -> GameResult {
+        let code = String::from_utf8(bytes).map_err(GameError::ShaderEncodingError)?;
+        let module = create_module(gfx, &code, Some(&self.path))?;
+        *self.module.write().map_err(|_| GameError::LockError)? = module;
+        Ok(())
+    }
 }
 
 use crevice::std140::AsStd140;
@@ -221,6 +329,9 @@ impl<'a, Uniforms: AsStd140> ShaderParamsBuilder<'a, Uniforms> {
 
     /// Provides images to the shaders.
     ///
+    /// These are bound for every draw using the parameters; for images that differ from draw
+    /// to draw, see [`Canvas::draw_with_images`](crate::graphics::Canvas::draw_with_images).
+    ///
     /// # Arguments
     ///
     /// * `vs_visible` - If the images should also be visible to the vertex shader, rather
@@ -242,7 +353,11 @@ impl<'a, Uniforms: AsStd140> ShaderParamsBuilder<'a, Uniforms> {
 
     /// Produce a [`ShaderParams`] from the builder.
     pub fn build(self, ctx: &mut Context) -> ShaderParams<Uniforms> {
-        let images = self.images.iter().map(|image| image.view.clone()).collect();
+        let images = self
+            .images
+            .iter()
+            .map(|image| image.current().view)
+            .collect();
         let samplers = self
             .samplers
             .iter()
@@ -270,6 +385,7 @@ impl<'a, Uniforms: AsStd140> ShaderParamsBuilder<'a, Uniforms> {
             layout: None,
             bind_group: None,
             buffer_offset: 0,
+            uniform_buffer: None,
             images,
             samplers,
             images_vs_visible: self.images_vs_visible,
@@ -309,6 +425,7 @@ pub struct ShaderParams<Uniforms: AsStd140> {
     pub(crate) layout: Option<ArcBindGroupLayout>,
     pub(crate) bind_group: Option<ArcBindGroup>,
     pub(crate) buffer_offset: u32,
+    pub(crate) uniform_buffer: Option<ArcBuffer>,
     images: Vec<ArcTextureView>,
     samplers: Vec<ArcSampler>,
     images_vs_visible: bool,
@@ -340,6 +457,7 @@ impl<Uniforms: AsStd140> ShaderParams<Uniforms> {
         );
 
         self.buffer_offset = alloc.offset as u32;
+        self.uniform_buffer = Some(alloc.buffer.clone());
 
         let mut builder = BindGroupBuilder::new();
         builder = builder.buffer(
@@ -515,3 +633,65 @@ impl BlendMode {
         },
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        context::headless_ctx_for_tests,
+        filesystem::rewrite_for_tests,
+        graphics::{Canvas, Color, DrawParam, Quad, Rect},
+    };
+
+    #[test]
+    fn headless_test_shader_hot_reload() {
+        let mut ctx = headless_ctx_for_tests(8, 8);
+
+        let path = "/headless_test_shader_hot_reload.wgsl";
+        let write = |ctx: &Context, code: &str| rewrite_for_tests(&ctx.fs, path, code.as_bytes());
+        let fs_main = |color: &str| {
+            format!(
+                "@fragment fn fs_main(@location(0) uv: vec2<f32>, @location(1) color: vec4<f32>) \
+                 -> @location(0) vec4<f32> {{ return {color}; }}"
+            )
+        };
+        let draw = |ctx: &mut Context, shader: &Shader| {
+            ctx.gfx.begin_frame().unwrap();
+            let mut canvas = Canvas::from_frame(ctx, Color::BLACK);
+            canvas.set_shader(shader);
+            canvas.draw(
+                &Quad,
+                DrawParam::new().dest_rect(Rect::new(0.0, 0.0, 8.0, 8.0)),
+            );
+            canvas.finish(ctx).unwrap();
+            ctx.gfx.end_frame().unwrap();
+            ctx.gfx.frame().to_pixels(ctx).unwrap()[..4].to_vec()
+        };
+
+        ctx.fs.set_asset_hot_reload(true);
+        write(&ctx, &fs_main("vec4<f32>(1.0, 0.0, 0.0, 1.0)"));
+        let shader = ShaderBuilder::new()
+            .fragment_path(path)
+            .build(&ctx)
+            .unwrap();
+        assert_eq!(draw(&mut ctx, &shader), [255, 0, 0, 255]);
+        assert_eq!(ctx.gfx.reload_assets().unwrap(), 0);
+
+        // changes are picked up at the start of the next frame
+        write(&ctx, &fs_main("vec4<f32>(0.0, 1.0, 0.0, 1.0)"));
+        assert_eq!(draw(&mut ctx, &shader), [0, 255, 0, 255]);
+        let current = shader.current();
+
+        // broken code is reported, and the last working modules are kept
+        write(&ctx, &fs_main("oops"));
+        assert!(ctx.gfx.reload_assets().is_err());
+        assert_eq!(ctx.gfx.reload_assets().unwrap(), 0);
+        assert_eq!(draw(&mut ctx, &shader), [0, 255, 0, 255]);
+
+        // copies with the current modules, like the one `Canvas::shader` returns, keep reloading
+        write(&ctx, &fs_main("vec4<f32>(0.0, 0.0, 1.0, 1.0)"));
+        assert_eq!(draw(&mut ctx, &current), [0, 0, 255, 255]);
+
+        ctx.fs.delete(path).unwrap();
+    }
+}
@@ -0,0 +1,42 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+struct Bloom {
+    threshold: f32,
+    intensity: f32,
+    radius: f32,
+}
+
+@group(1) @binding(0)
+var t: texture_2d<f32>;
+
+@group(1) @binding(1)
+var s: sampler;
+
+@group(3) @binding(0)
+var<uniform> bloom: Bloom;
+
+// The part of the color brighter than the threshold.
+fn bright(color: vec4<f32>) -> vec3<f32> {
+    let luma = dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+    return color.rgb * max(luma - bloom.threshold, 0.0) / max(luma, 0.0001);
+}
+
+// Adds the bright parts of two rings of samples around each pixel, spread over `radius` pixels.
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let step = bloom.radius / vec2<f32>(textureDimensions(t));
+    let color = textureSample(t, s, in.uv);
+    var glow = vec3<f32>(0.0);
+    for (var i = 0; i < 8; i += 1) {
+        let angle = f32(i) * 0.7853981634;
+        let offset = vec2<f32>(cos(angle), sin(angle)) * step;
+        glow += bright(textureSample(t, s, in.uv + offset * 0.5)) * 2.0;
+        glow += bright(textureSample(t, s, in.uv + offset));
+    }
+    glow = glow / 24.0 + bright(color);
+    return vec4<f32>(color.rgb + glow * bloom.intensity, color.a) * in.color;
+}
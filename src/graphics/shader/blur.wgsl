@@ -0,0 +1,31 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+struct Blur {
+    direction: vec2<f32>,
+    radius: f32,
+}
+
+@group(1) @binding(0)
+var t: texture_2d<f32>;
+
+@group(1) @binding(1)
+var s: sampler;
+
+@group(3) @binding(0)
+var<uniform> blur: Blur;
+
+// One direction of a separable 9-tap gaussian blur, with the taps spread over `radius` pixels.
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let step = blur.direction * (blur.radius / 4.0) / vec2<f32>(textureDimensions(t));
+    var color = textureSample(t, s, in.uv) * 0.2270270270;
+    color += (textureSample(t, s, in.uv + step) + textureSample(t, s, in.uv - step)) * 0.1945945946;
+    color += (textureSample(t, s, in.uv + step * 2.0) + textureSample(t, s, in.uv - step * 2.0)) * 0.1216216216;
+    color += (textureSample(t, s, in.uv + step * 3.0) + textureSample(t, s, in.uv - step * 3.0)) * 0.0540540541;
+    color += (textureSample(t, s, in.uv + step * 4.0) + textureSample(t, s, in.uv - step * 4.0)) * 0.0162162162;
+    return color * in.color;
+}
@@ -0,0 +1,36 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+struct Crt {
+    scanlines: f32,
+    curvature: f32,
+    vignette: f32,
+}
+
+@group(1) @binding(0)
+var t: texture_2d<f32>;
+
+@group(1) @binding(1)
+var s: sampler;
+
+@group(3) @binding(0)
+var<uniform> crt: Crt;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(t));
+
+    // bulge the screen outwards, like the glass of a CRT
+    let centered = in.uv * 2.0 - 1.0;
+    let uv = centered * (1.0 + crt.curvature * dot(centered.yx, centered.yx) * 0.25) * 0.5 + 0.5;
+    let color = textureSample(t, s, uv);
+
+    let scanline = select(1.0, 1.0 - crt.scanlines, fract(uv.y * size.y * 0.5) >= 0.5);
+    let vignette = 1.0 - crt.vignette * dot(centered, centered) * 0.5;
+    let inside = all(uv >= vec2<f32>(0.0)) && all(uv <= vec2<f32>(1.0));
+    let rgb = select(vec3<f32>(0.0), color.rgb * scanline * vignette, inside);
+    return vec4<f32>(rgb, color.a) * in.color;
+}
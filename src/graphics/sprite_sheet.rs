@@ -0,0 +1,485 @@
+use super::{Canvas, DrawParam, GraphicsContext, Image, InstanceArray, Rect, Transform};
+use crate::{context::Has, GameError, GameResult};
+use serde::de::{Deserializer, MapAccess, SeqAccess, Visitor};
+use std::{collections::HashMap, fmt, io::Read, path::Path, sync::Arc, time::Duration};
+
+/// A single named frame of a [`SpriteSheet`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpriteFrame {
+    /// The name of the frame, usually the file name of the original sprite.
+    pub name: String,
+    /// Location of the (possibly trimmed) frame in the sheet's image, in pixels.
+    pub rect: Rect,
+    /// Size of the sprite before it was trimmed, in pixels.
+    pub source_size: mint::Vector2<f32>,
+    /// Offset of the trimmed frame inside the untrimmed sprite, in pixels.
+    pub trim_offset: mint::Vector2<f32>,
+    /// Pivot point relative to the untrimmed sprite, e.g. `[0.5, 0.5]` is its center.
+    ///
+    /// Defaults to `[0.0, 0.0]` if the file doesn't specify one.
+    pub pivot: mint::Point2<f32>,
+    /// How long the frame should be shown for, if the file specifies it (as Aseprite does).
+    pub duration: Option<Duration>,
+}
+
+/// A named sequence of frames of a [`SpriteSheet`], as exported by Aseprite in `meta.frameTags`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpriteTag {
+    /// The name of the tag.
+    pub name: String,
+    /// Indices into [`SpriteSheet::frames`], in the order they should be played.
+    pub frames: Vec<usize>,
+    /// Whether the frames should be played back and forth.
+    pub ping_pong: bool,
+}
+
+/// An [`Image`] containing many sprites, along with the named frames describing where they are.
+///
+/// Traditionally known as a "texture atlas". Frame definitions are loaded from the JSON
+/// files exported by TexturePacker or Aseprite, in either their "hash" or "array" layout.
+/// Rotated frames are not supported.
+///
+/// Cloning a `SpriteSheet` is cheap, the image and frames are shared.
+#[derive(Debug, Clone)]
+pub struct SpriteSheet {
+    image: Image,
+    frames: Arc<[SpriteFrame]>,
+    indices: Arc<HashMap<String, usize>>,
+    tags: Arc<[SpriteTag]>,
+}
+
+impl SpriteSheet {
+    /// Loads a sprite sheet from the JSON file at the given path.
+    ///
+    /// The image is loaded from the path in the file's `meta.image` field, relative to the
+    /// directory of the JSON file.
+    pub fn new(gfx: &impl Has<GraphicsContext>, path: impl AsRef<Path>) -> GameResult<Self> {
+        let gfx = gfx.retrieve();
+        let path = path.as_ref();
+
+        let mut json = Vec::new();
+        let _ = gfx.fs.open(path)?.read_to_end(&mut json)?;
+        let sheet = parse_sheet(&json)?;
+
+        let image_path = sheet.image.ok_or_else(|| {
+            GameError::ResourceLoadError(format!(
+                "sprite sheet {path:?} doesn't specify an image in `meta.image`"
+            ))
+        })?;
+        let image_path = path
+            .parent()
+            .unwrap_or_else(|| Path::new("/"))
+            .join(image_path);
+        let image = Image::from_path(gfx, image_path)?;
+
+        Ok(Self::from_frames(image, sheet.frames).with_tags(sheet.tags))
+    }
+
+    /// Creates a sprite sheet from an already loaded image and the contents of a JSON file.
+    ///
+    /// The `meta.image` field of the file is ignored.
+    pub fn from_json(image: Image, json: &[u8]) -> GameResult<Self> {
+        let sheet = parse_sheet(json)?;
+        Ok(Self::from_frames(image, sheet.frames).with_tags(sheet.tags))
+    }
+
+    /// Creates a sprite sheet from an image and a list of frames.
+    pub fn from_frames(image: Image, frames: impl IntoIterator<Item = SpriteFrame>) -> Self {
+        let frames: Arc<[SpriteFrame]> = frames.into_iter().collect();
+        let indices = frames
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| (frame.name.clone(), i))
+            .collect();
+        SpriteSheet {
+            image,
+            frames,
+            indices: Arc::new(indices),
+            tags: Arc::new([]),
+        }
+    }
+
+    /// Replaces the sheet's tags.
+    #[must_use]
+    pub fn with_tags(mut self, tags: impl IntoIterator<Item = SpriteTag>) -> Self {
+        self.tags = tags.into_iter().collect();
+        self
+    }
+
+    /// Returns the image containing all the frames.
+    #[inline]
+    pub fn image(&self) -> Image {
+        self.image.clone()
+    }
+
+    /// Returns all frames, in the order they are listed in the file.
+    #[inline]
+    pub fn frames(&self) -> &[SpriteFrame] {
+        &self.frames
+    }
+
+    /// Returns the frame with the given name, if it exists.
+    pub fn frame(&self, name: &str) -> Option<&SpriteFrame> {
+        self.frame_index(name).map(|i| &self.frames[i])
+    }
+
+    /// Returns the index of the frame with the given name in [`SpriteSheet::frames`], if it exists.
+    pub fn frame_index(&self, name: &str) -> Option<usize> {
+        self.indices.get(name).copied()
+    }
+
+    /// Returns all tags, in the order they are listed in the file.
+    #[inline]
+    pub fn tags(&self) -> &[SpriteTag] {
+        &self.tags
+    }
+
+    /// Returns the tag with the given name, if it exists.
+    pub fn tag(&self, name: &str) -> Option<&SpriteTag> {
+        self.tags.iter().find(|tag| tag.name == name)
+    }
+
+    /// Returns `param` modified to draw the frame at `index` out of the sheet's image.
+    ///
+    /// `src` is replaced by the frame's location and `offset` is made relative to the untrimmed
+    /// sprite and added to its pivot, so trimmed frames are drawn where the untrimmed sprite would be.
+    /// If the transform is a matrix, the pivot and trim offset are applied before it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn frame_param(&self, index: usize, param: impl Into<DrawParam>) -> DrawParam {
+        frame_param(
+            &self.frames[index],
+            (self.image.width() as f32, self.image.height() as f32),
+            param.into(),
+        )
+    }
+
+    /// Draws the frame with the given name onto the canvas.
+    ///
+    /// See [`SpriteSheet::frame_param`] for how `param` is applied.
+    pub fn draw_frame(
+        &self,
+        canvas: &mut Canvas,
+        name: &str,
+        param: impl Into<DrawParam>,
+    ) -> GameResult {
+        let index = self.expect_frame(name)?;
+        canvas.draw(&self.image, self.frame_param(index, param));
+        Ok(())
+    }
+
+    /// Pushes the frame with the given name as a new instance onto `array`.
+    ///
+    /// The array has to be created with this sheet's image, see [`SpriteSheet::image`].
+    /// See [`SpriteSheet::frame_param`] for how `param` is applied.
+    pub fn push_frame(
+        &self,
+        array: &mut InstanceArray,
+        name: &str,
+        param: impl Into<DrawParam>,
+    ) -> GameResult {
+        if array.image.view.id() != self.image.view.id() {
+            return Err(GameError::RenderError(String::from(
+                "cannot push a sprite sheet frame onto an instance array with a different image",
+            )));
+        }
+        let index = self.expect_frame(name)?;
+        array.push(self.frame_param(index, param));
+        Ok(())
+    }
+
+    fn expect_frame(&self, name: &str) -> GameResult<usize> {
+        self.frame_index(name).ok_or_else(|| {
+            GameError::RenderError(format!("sprite sheet has no frame named {name:?}"))
+        })
+    }
+}
+
+fn frame_param(
+    frame: &SpriteFrame,
+    (width, height): (f32, f32),
+    mut param: DrawParam,
+) -> DrawParam {
+    param.src = Rect::new(
+        frame.rect.x / width,
+        frame.rect.y / height,
+        frame.rect.w / width,
+        frame.rect.h / height,
+    );
+
+    match &mut param.transform {
+        Transform::Values { offset, .. } => {
+            // `offset` is relative to the drawn (trimmed) frame, so move the pivot over from the untrimmed sprite.
+            offset.x = ((frame.pivot.x + offset.x) * frame.source_size.x - frame.trim_offset.x)
+                / frame.rect.w;
+            offset.y = ((frame.pivot.y + offset.y) * frame.source_size.y - frame.trim_offset.y)
+                / frame.rect.h;
+        }
+        Transform::Matrix(m) => {
+            let translation = glam::vec3(
+                frame.trim_offset.x - frame.pivot.x * frame.source_size.x,
+                frame.trim_offset.y - frame.pivot.y * frame.source_size.y,
+                0.,
+            );
+            *m = (glam::Mat4::from(*m) * glam::Mat4::from_translation(translation)).into();
+        }
+    }
+
+    param
+}
+
+#[derive(serde::Deserialize)]
+struct RawSheet {
+    frames: RawFrames,
+    #[serde(default)]
+    meta: RawMeta,
+}
+
+#[derive(Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawMeta {
+    image: Option<String>,
+    #[serde(default)]
+    frame_tags: Vec<RawTag>,
+}
+
+#[derive(serde::Deserialize)]
+struct RawTag {
+    name: String,
+    from: usize,
+    to: usize,
+    #[serde(default)]
+    direction: String,
+}
+
+#[derive(Clone, Copy, serde::Deserialize)]
+struct RawRect {
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+}
+
+#[derive(Clone, Copy, serde::Deserialize)]
+struct RawSize {
+    w: f32,
+    h: f32,
+}
+
+#[derive(Clone, Copy, serde::Deserialize)]
+struct RawPoint {
+    x: f32,
+    y: f32,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawFrame {
+    filename: Option<String>,
+    frame: RawRect,
+    #[serde(default)]
+    rotated: bool,
+    sprite_source_size: Option<RawRect>,
+    source_size: Option<RawSize>,
+    pivot: Option<RawPoint>,
+    duration: Option<u64>,
+}
+
+/// Frames in file order; either a map of name to frame ("hash" layout)
+/// or a list of frames with a `filename` field ("array" layout).
+struct RawFrames(Vec<(Option<String>, RawFrame)>);
+
+impl<'de> serde::Deserialize<'de> for RawFrames {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FramesVisitor;
+
+        impl<'de> Visitor<'de> for FramesVisitor {
+            type Value = RawFrames;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a map or a list of frames")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<RawFrames, A::Error> {
+                let mut frames = Vec::new();
+                while let Some((name, frame)) = map.next_entry::<String, RawFrame>()? {
+                    frames.push((Some(name), frame));
+                }
+                Ok(RawFrames(frames))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<RawFrames, A::Error> {
+                let mut frames = Vec::new();
+                while let Some(frame) = seq.next_element::<RawFrame>()? {
+                    frames.push((None, frame));
+                }
+                Ok(RawFrames(frames))
+            }
+        }
+
+        deserializer.deserialize_any(FramesVisitor)
+    }
+}
+
+struct ParsedSheet {
+    frames: Vec<SpriteFrame>,
+    tags: Vec<SpriteTag>,
+    image: Option<String>,
+}
+
+fn parse_sheet(json: &[u8]) -> GameResult<ParsedSheet> {
+    let sheet: RawSheet = serde_json::from_slice(json)
+        .map_err(|e| GameError::ResourceLoadError(format!("failed to parse sprite sheet: {e}")))?;
+
+    let frames = sheet
+        .frames
+        .0
+        .into_iter()
+        .enumerate()
+        .map(|(i, (name, raw))| {
+            if raw.rotated {
+                return Err(GameError::ResourceLoadError(String::from(
+                    "rotated sprite sheet frames are not supported",
+                )));
+            }
+            let name = name.or(raw.filename).ok_or_else(|| {
+                GameError::ResourceLoadError(format!("sprite sheet frame {i} has no `filename`"))
+            })?;
+            let source_size = raw.source_size.unwrap_or(RawSize {
+                w: raw.frame.w,
+                h: raw.frame.h,
+            });
+            let trim_offset = raw.sprite_source_size.map_or([0., 0.], |r| [r.x, r.y]);
+            let pivot = raw.pivot.map_or([0., 0.], |p| [p.x, p.y]);
+            Ok(SpriteFrame {
+                name,
+                rect: Rect::new(raw.frame.x, raw.frame.y, raw.frame.w, raw.frame.h),
+                source_size: [source_size.w, source_size.h].into(),
+                trim_offset: trim_offset.into(),
+                pivot: pivot.into(),
+                duration: raw.duration.map(Duration::from_millis),
+            })
+        })
+        .collect::<GameResult<Vec<_>>>()?;
+
+    let tags = sheet
+        .meta
+        .frame_tags
+        .into_iter()
+        .map(|raw| {
+            if raw.from > raw.to || raw.to >= frames.len() {
+                return Err(GameError::ResourceLoadError(format!(
+                    "sprite sheet tag {:?} has an invalid frame range",
+                    raw.name
+                )));
+            }
+            let range = raw.from..=raw.to;
+            let (frames, ping_pong) = match raw.direction.as_str() {
+                "reverse" => (range.rev().collect(), false),
+                "pingpong" => (range.collect(), true),
+                "pingpong_reverse" => (range.rev().collect(), true),
+                _ => (range.collect(), false),
+            };
+            Ok(SpriteTag {
+                name: raw.name,
+                frames,
+                ping_pong,
+            })
+        })
+        .collect::<GameResult<_>>()?;
+
+    Ok(ParsedSheet {
+        frames,
+        tags,
+        image: sheet.meta.image,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = r#"{
+        "frames": {
+            "walk_1.png": {
+                "frame": {"x": 0, "y": 0, "w": 16, "h": 32},
+                "rotated": false,
+                "trimmed": true,
+                "spriteSourceSize": {"x": 8, "y": 0, "w": 16, "h": 32},
+                "sourceSize": {"w": 32, "h": 32},
+                "pivot": {"x": 0.5, "y": 1.0}
+            },
+            "walk_0.png": {
+                "frame": {"x": 16, "y": 0, "w": 32, "h": 32},
+                "sourceSize": {"w": 32, "h": 32},
+                "duration": 100
+            }
+        },
+        "meta": {"image": "walk.png", "size": {"w": 64, "h": 32}}
+    }"#;
+
+    const ARRAY: &str = r#"{
+        "frames": [
+            {"filename": "a", "frame": {"x": 0, "y": 0, "w": 8, "h": 8}, "duration": 50},
+            {"filename": "b", "frame": {"x": 8, "y": 0, "w": 8, "h": 8}, "duration": 75}
+        ],
+        "meta": {"frameTags": [{"name": "idle", "from": 0, "to": 1, "direction": "pingpong_reverse"}]}
+    }"#;
+
+    #[test]
+    fn headless_test_parse_hash() {
+        let ParsedSheet {
+            frames,
+            image,
+            tags,
+        } = parse_sheet(HASH.as_bytes()).unwrap();
+        assert_eq!(image.as_deref(), Some("walk.png"));
+        assert!(tags.is_empty());
+        // file order is preserved
+        assert_eq!(frames[0].name, "walk_1.png");
+        assert_eq!(frames[1].name, "walk_0.png");
+        assert_eq!(frames[0].trim_offset, [8., 0.].into());
+        assert_eq!(frames[0].pivot, [0.5, 1.0].into());
+        assert_eq!(frames[1].duration, Some(Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn headless_test_parse_array() {
+        let ParsedSheet {
+            frames,
+            image,
+            tags,
+        } = parse_sheet(ARRAY.as_bytes()).unwrap();
+        assert_eq!(image, None);
+        assert_eq!(
+            tags,
+            [SpriteTag {
+                name: String::from("idle"),
+                frames: vec![1, 0],
+                ping_pong: true,
+            }]
+        );
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1].name, "b");
+        assert_eq!(frames[1].source_size, [8., 8.].into());
+        assert_eq!(frames[1].duration, Some(Duration::from_millis(75)));
+
+        let rotated = r#"{"frames": [{"filename": "a", "frame": {"x": 0, "y": 0, "w": 8, "h": 8}, "rotated": true}]}"#;
+        assert!(parse_sheet(rotated.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn headless_test_frame_param() {
+        let frames = parse_sheet(HASH.as_bytes()).unwrap().frames;
+        let param = frame_param(&frames[0], (64., 32.), DrawParam::new().dest([100., 100.]));
+        assert_eq!(param.src, Rect::new(0., 0., 0.25, 1.));
+        // pivot (16, 32) in the untrimmed sprite is (8, 32) in the trimmed frame
+        if let Transform::Values { offset, .. } = param.transform {
+            assert_eq!(offset, [0.5, 1.0].into());
+        } else {
+            unreachable!()
+        }
+    }
+}
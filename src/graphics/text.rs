@@ -2,26 +2,46 @@ use super::{
     gpu::text::{Extra, TextRenderer},
     Canvas, Color, Draw, DrawParam, Drawable, GraphicsContext, Rect,
 };
-use crate::{context::Has, filesystem::Filesystem, GameError, GameResult};
+use crate::{
+    context::Has,
+    filesystem::{Filesystem, ReloadAsset},
+    GameError, GameResult,
+};
 use glyph_brush::{ab_glyph, FontId, GlyphCruncher};
-use std::{collections::HashMap, io::Read, path::Path};
+use std::{
+    collections::HashMap,
+    io::Read,
+    path::Path,
+    sync::{Arc, Mutex},
+};
 
 /// Font data that can be used to create a new font in [`GraphicsContext`].
 #[derive(Debug)]
 pub struct FontData {
     pub(crate) font: ab_glyph::FontArc,
+    // The file the font was loaded from, for fonts loaded while asset hot reloading is on.
+    pub(crate) watched: Option<Arc<WatchedFont>>,
 }
 
 impl FontData {
     /// Loads font data from a given path in the filesystem.
+    ///
+    /// With [asset hot reloading](Filesystem::set_asset_hot_reload) on, the font is reloaded
+    /// wherever it was [added](GraphicsContext::add_font) when the file changes.
     #[allow(unused_results)]
     pub fn from_path(fs: &impl Has<Filesystem>, path: impl AsRef<Path>) -> GameResult<Self> {
         let fs = fs.retrieve();
 
         let mut bytes = vec![];
-        fs.open(path)?.read_to_end(&mut bytes)?;
+        fs.open(&path)?.read_to_end(&mut bytes)?;
+        let watched = fs.is_asset_hot_reload_enabled().then(|| {
+            let watched = Arc::new(WatchedFont::default());
+            fs.watch_asset(path, Arc::downgrade(&watched) as _);
+            watched
+        });
         Ok(FontData {
             font: ab_glyph::FontArc::try_from_vec(bytes)?,
+            watched,
         })
     }
 
@@ -29,6 +49,7 @@ impl FontData {
     pub fn from_vec(data: Vec<u8>) -> GameResult<Self> {
         Ok(FontData {
             font: ab_glyph::FontArc::try_from_vec(data)?,
+            watched: None,
         })
     }
 
@@ -36,10 +57,45 @@ impl FontData {
     pub fn from_slice(data: &'static [u8]) -> GameResult<Self> {
         Ok(FontData {
             font: ab_glyph::FontArc::try_from_slice(data)?,
+            watched: None,
         })
     }
 }
 
+/// The file a [`FontData`] was loaded from, and the fonts it was added as.
+#[derive(Debug, Default)]
+pub(crate) struct WatchedFont {
+    ids: Mutex<Vec<FontId>>,
+}
+
+impl WatchedFont {
+    pub(crate) fn add(&self, id: FontId) {
+        if let Ok(mut ids) = self.ids.lock() {
+            ids.push(id);
+        }
+    }
+}
+
+impl ReloadAsset for WatchedFont {
+    fn reload(&self, gfx: &GraphicsContext, bytes: Vec<u8>) -> GameResult {
+        let font = ab_glyph::FontArc::try_from_vec(bytes)?;
+        let ids = self.ids.lock().map_err(|_| GameError::LockError)?.clone();
+
+        // Fonts can't be replaced in a glyph brush, only in a new one with the same settings.
+        let mut glyph_brush = gfx.text.glyph_brush.borrow_mut();
+        glyph_brush
+            .to_builder()
+            .replace_fonts(|mut fonts| {
+                for id in ids {
+                    fonts[id.0] = font.clone();
+                }
+                fonts
+            })
+            .rebuild(&mut glyph_brush);
+        Ok(())
+    }
+}
+
 pub use glyph_brush::ab_glyph::PxScale;
 
 /// Parameters of a single piece ("fragment") of text, including font, color, and size.
@@ -0,0 +1,1410 @@
+use super::{Canvas, DrawParam, Drawable, GraphicsContext, Image, InstanceArray, Rect, Transform};
+use crate::{context::Has, GameError, GameResult};
+use base64::Engine;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    fmt,
+    io::Read,
+    path::{Component, Path, PathBuf},
+};
+
+/// Width and height, in tiles, of the chunks tile layers are split into for culling.
+pub const TILE_CHUNK_SIZE: u32 = 16;
+
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+const ROTATED_HEXAGONAL: u32 = 0x1000_0000;
+const FLAGS: u32 =
+    FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY | ROTATED_HEXAGONAL;
+
+/// A tile placed in a [`TileMap`], see [`TileLayer::tile`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Tile {
+    /// The global id of the tile, identifying both its tileset and its index in the tileset.
+    pub id: u32,
+    /// Whether the tile is mirrored left to right.
+    pub flip_horizontal: bool,
+    /// Whether the tile is mirrored upside down.
+    pub flip_vertical: bool,
+    /// Whether the tile is mirrored along its top-left to bottom-right diagonal,
+    /// which is applied before the other two flips.
+    pub flip_diagonal: bool,
+}
+
+impl Tile {
+    /// Decodes a global id as stored by Tiled, with the flip flags in its highest bits.
+    /// Returns `None` for empty tiles.
+    pub fn from_gid(gid: u32) -> Option<Self> {
+        let id = gid & !FLAGS;
+        (id != 0).then_some(Tile {
+            id,
+            flip_horizontal: gid & FLIPPED_HORIZONTALLY != 0,
+            flip_vertical: gid & FLIPPED_VERTICALLY != 0,
+            flip_diagonal: gid & FLIPPED_DIAGONALLY != 0,
+        })
+    }
+
+    /// Returns the `(scale, rotation)` to draw the tile with (around its center) to apply its flips.
+    fn scale_rotation(&self) -> (mint::Vector2<f32>, f32) {
+        let h = if self.flip_horizontal { -1. } else { 1. };
+        let v = if self.flip_vertical { -1. } else { 1. };
+        if self.flip_diagonal {
+            // Flipping along the diagonal maps (x, y) to (y, x), so (x, y) ends up at
+            // (h * y, v * x), which is the same as scaling by (v, -h) and rotating by 90°.
+            ([v, -h].into(), std::f32::consts::FRAC_PI_2)
+        } else {
+            ([h, v].into(), 0.)
+        }
+    }
+}
+
+/// A tileset used by a [`TileMap`]: an image split into a grid of equally sized tiles.
+#[derive(Debug, Clone)]
+pub struct Tileset {
+    /// The name of the tileset.
+    pub name: String,
+    /// The global id of the first tile of the tileset.
+    pub first_gid: u32,
+    /// The number of tiles in the tileset.
+    pub tile_count: u32,
+    /// The image containing the tiles.
+    pub image: Image,
+    geometry: TilesetGeometry,
+}
+
+impl Tileset {
+    /// Returns whether the tile with this global id belongs to this tileset.
+    #[inline]
+    pub fn contains(&self, id: u32) -> bool {
+        (self.first_gid..self.first_gid + self.tile_count).contains(&id)
+    }
+
+    /// Returns the location of the tile with the given global id in the tileset's image, in
+    /// pixels, or `None` if it doesn't belong to this tileset.
+    pub fn tile_rect(&self, id: u32) -> Option<Rect> {
+        self.geometry.tile_rect(id)
+    }
+}
+
+/// Just what's needed to turn tiles into draws, kept separate from the [`Image`] so the
+/// layout math doesn't need a graphics context.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct TilesetGeometry {
+    first_gid: u32,
+    tile_count: u32,
+    tile_width: u32,
+    tile_height: u32,
+    columns: u32,
+    spacing: u32,
+    margin: u32,
+    offset: mint::Vector2<f32>,
+    image_width: u32,
+    image_height: u32,
+}
+
+impl TilesetGeometry {
+    fn tile_rect(&self, id: u32) -> Option<Rect> {
+        let local = id.checked_sub(self.first_gid)?;
+        if local >= self.tile_count {
+            return None;
+        }
+        // columns is checked to be non-zero when the tileset is loaded
+        let (column, row) = (local % self.columns, local / self.columns);
+        Some(Rect::new(
+            (self.margin + column * (self.tile_width + self.spacing)) as f32,
+            (self.margin + row * (self.tile_height + self.spacing)) as f32,
+            self.tile_width as f32,
+            self.tile_height as f32,
+        ))
+    }
+}
+
+/// The shape of a [`MapObject`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ObjectShape {
+    /// A rectangle filling [`MapObject::rect`]. Also used for tile and text objects.
+    Rectangle,
+    /// An ellipse filling [`MapObject::rect`].
+    Ellipse,
+    /// A single point at the top-left of [`MapObject::rect`].
+    Point,
+    /// A closed polygon, with points relative to the top-left of [`MapObject::rect`].
+    Polygon(Vec<mint::Point2<f32>>),
+    /// An open line, with points relative to the top-left of [`MapObject::rect`].
+    Polyline(Vec<mint::Point2<f32>>),
+}
+
+/// An object placed in an object layer of a [`TileMap`], e.g. a spawn point or a trigger area.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapObject {
+    /// The unique id of the object.
+    pub id: u32,
+    /// The name of the object.
+    pub name: String,
+    /// The class (formerly "type") of the object.
+    pub class: String,
+    /// The position and size of the object, in pixels.
+    ///
+    /// Note that Tiled positions tile objects by their bottom-left corner.
+    pub rect: Rect,
+    /// The clockwise rotation of the object around its position, in radians.
+    pub rotation: f32,
+    /// Whether the object is shown.
+    pub visible: bool,
+    /// The tile shown by a tile object.
+    pub tile: Option<Tile>,
+    /// The shape of the object.
+    pub shape: ObjectShape,
+    /// The custom properties of the object, with their values converted to strings.
+    pub properties: HashMap<String, String>,
+}
+
+/// A layer of tiles in a [`TileMap`].
+#[derive(Debug)]
+pub struct TileLayer {
+    /// The width of the layer, in tiles.
+    pub width: u32,
+    /// The height of the layer, in tiles.
+    pub height: u32,
+    tiles: Vec<u32>,
+    chunks: Vec<TileChunk>,
+}
+
+impl TileLayer {
+    /// Returns the tile at the given position, or `None` if it's empty or out of bounds.
+    pub fn tile(&self, x: u32, y: u32) -> Option<Tile> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        Tile::from_gid(self.tiles[(y * self.width + x) as usize])
+    }
+}
+
+#[derive(Debug)]
+struct TileChunk {
+    bounds: Rect,
+    arrays: Vec<InstanceArray>,
+}
+
+/// What a [`MapLayer`] contains.
+#[derive(Debug)]
+pub enum MapLayerKind {
+    /// A grid of tiles.
+    Tiles(TileLayer),
+    /// Free-form objects, which aren't drawn by the map.
+    Objects(Vec<MapObject>),
+}
+
+/// A layer of a [`TileMap`].
+///
+/// Group layers are flattened into the layers they contain, with their offset, opacity and
+/// visibility applied to them. Image layers are skipped.
+#[derive(Debug)]
+pub struct MapLayer {
+    /// The name of the layer.
+    pub name: String,
+    /// Whether the layer is drawn.
+    pub visible: bool,
+    /// The opacity the layer is drawn with.
+    pub opacity: f32,
+    /// The offset of the layer, in pixels. It's applied to the tiles when the map is loaded.
+    pub offset: mint::Vector2<f32>,
+    /// What the layer contains.
+    pub kind: MapLayerKind,
+}
+
+/// An orthogonal map made with the [Tiled](https://www.mapeditor.org/) editor.
+///
+/// Maps are loaded from `.tmx` or `.json`/`.tmj` files, along with their tilesets, which may be
+/// embedded or in separate `.tsx` or `.json`/`.tsj` files. Tile layers are split into chunks of
+/// [`TILE_CHUNK_SIZE`] tiles, each drawn as one [`InstanceArray`] per tileset, and chunks
+/// that fall outside of the visible area are skipped when drawing.
+///
+/// Infinite maps, non-orthogonal maps and "collection of images" tilesets are not supported.
+#[derive(Debug)]
+pub struct TileMap {
+    width: u32,
+    height: u32,
+    tile_width: u32,
+    tile_height: u32,
+    tilesets: Vec<Tileset>,
+    layers: Vec<MapLayer>,
+}
+
+impl TileMap {
+    /// Loads a map, its tilesets and their images from the given path.
+    ///
+    /// Tilesets and images are looked up relative to the file referencing them.
+    pub fn new(gfx: &impl Has<GraphicsContext>, path: impl AsRef<Path>) -> GameResult<Self> {
+        let gfx = gfx.retrieve();
+        let path = path.as_ref();
+
+        let map = read(gfx, path)?;
+        let map = if has_extension(path, "tmx") {
+            parse_tmx(&map)?
+        } else {
+            parse_json_map(&map)?
+        };
+
+        let mut tilesets = Vec::with_capacity(map.tilesets.len());
+        for tileset in map.tilesets {
+            let (tileset, tileset_path) = match tileset {
+                RawTilesetRef::Embedded(tileset) => (tileset, path.to_path_buf()),
+                RawTilesetRef::External { first_gid, source } => {
+                    let tileset_path = resolve_path(path, &source);
+                    let data = read(gfx, &tileset_path)?;
+                    let mut tileset = if has_extension(&tileset_path, "tsx") {
+                        parse_tsx(&data)?
+                    } else {
+                        parse_json_tileset(&data)?
+                    };
+                    tileset.geometry.first_gid = first_gid;
+                    (tileset, tileset_path)
+                }
+            };
+            let image = Image::from_path(gfx, resolve_path(&tileset_path, &tileset.image))?;
+            tilesets.push(Tileset {
+                name: tileset.name,
+                first_gid: tileset.geometry.first_gid,
+                tile_count: tileset.geometry.tile_count,
+                image,
+                geometry: tileset.geometry,
+            });
+        }
+        tilesets.sort_by_key(|tileset| tileset.first_gid);
+
+        let geometry: Vec<_> = tilesets.iter().map(|tileset| tileset.geometry).collect();
+        let mut layers = map.layers;
+        for layer in &mut layers {
+            if let MapLayerKind::Tiles(tiles) = &mut layer.kind {
+                let tile_size = (map.tile_width, map.tile_height);
+                tiles.chunks = chunk_instances(tiles, layer.offset, tile_size, &geometry)
+                    .into_iter()
+                    .map(|(bounds, instances)| TileChunk {
+                        bounds,
+                        arrays: instances
+                            .into_iter()
+                            .filter(|(_, params)| !params.is_empty())
+                            .map(|(tileset, params)| {
+                                let mut array =
+                                    InstanceArray::new(gfx, tilesets[tileset].image.clone());
+                                array.set(params);
+                                array
+                            })
+                            .collect(),
+                    })
+                    .collect();
+            }
+        }
+
+        Ok(TileMap {
+            width: map.width,
+            height: map.height,
+            tile_width: map.tile_width,
+            tile_height: map.tile_height,
+            tilesets,
+            layers,
+        })
+    }
+
+    /// Returns the width of the map, in tiles.
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of the map, in tiles.
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the width of a tile of the map's grid, in pixels.
+    #[inline]
+    pub fn tile_width(&self) -> u32 {
+        self.tile_width
+    }
+
+    /// Returns the height of a tile of the map's grid, in pixels.
+    #[inline]
+    pub fn tile_height(&self) -> u32 {
+        self.tile_height
+    }
+
+    /// Returns the map's tilesets, ordered by their first global id.
+    #[inline]
+    pub fn tilesets(&self) -> &[Tileset] {
+        &self.tilesets
+    }
+
+    /// Returns the tileset the tile with the given global id belongs to.
+    pub fn tileset_for(&self, id: u32) -> Option<&Tileset> {
+        self.tilesets.iter().find(|tileset| tileset.contains(id))
+    }
+
+    /// Returns the map's layers, from back to front.
+    #[inline]
+    pub fn layers(&self) -> &[MapLayer] {
+        &self.layers
+    }
+
+    /// Returns the first layer with the given name.
+    pub fn layer(&self, name: &str) -> Option<&MapLayer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    /// Returns mutable access to the first layer with the given name, e.g. to hide it.
+    pub fn layer_mut(&mut self, name: &str) -> Option<&mut MapLayer> {
+        self.layers.iter_mut().find(|layer| layer.name == name)
+    }
+
+    /// Draws the tile layer at the given index (into [`TileMap::layers`]), if it's visible.
+    ///
+    /// Only chunks overlapping `visible`, in the coordinates of the canvas, are drawn; `None`
+    /// draws all of them. This is handy to draw other things between layers, or to cull with a
+    /// custom projection, e.g. with [`Camera2D::visible_rect`](super::Camera2D::visible_rect).
+    pub fn draw_layer(
+        &self,
+        canvas: &mut Canvas,
+        layer: usize,
+        visible: Option<Rect>,
+        param: impl Into<DrawParam>,
+    ) {
+        let Some(MapLayer {
+            visible: true,
+            opacity,
+            kind: MapLayerKind::Tiles(tiles),
+            ..
+        }) = self.layers.get(layer)
+        else {
+            return;
+        };
+
+        let mut param = param.into();
+        param.color.a *= opacity;
+        let visible = visible.map(|rect| inverse_transform_rect(rect, param.transform));
+        for chunk in &tiles.chunks {
+            if let Some(visible) = visible {
+                if !visible.overlaps(&chunk.bounds) {
+                    continue;
+                }
+            }
+            for array in &chunk.arrays {
+                canvas.draw(array, param);
+            }
+        }
+    }
+}
+
+impl Drawable for TileMap {
+    /// Draws all visible tile layers, skipping chunks outside of what the canvas's projection
+    /// shows, e.g. through a [`Camera2D`](super::Camera2D).
+    fn draw(&self, canvas: &mut Canvas, param: impl Into<DrawParam>) {
+        let param = param.into();
+        let visible = projected_rect(canvas.projection().into());
+        for layer in 0..self.layers.len() {
+            self.draw_layer(canvas, layer, visible, param);
+        }
+    }
+
+    fn dimensions(&self, _gfx: &impl Has<GraphicsContext>) -> Option<Rect> {
+        Some(Rect::new(
+            0.,
+            0.,
+            (self.width * self.tile_width) as f32,
+            (self.height * self.tile_height) as f32,
+        ))
+    }
+}
+
+/// Returns the bounding box of the area a projection shows, or `None` if it can't be inverted.
+fn projected_rect(projection: glam::Mat4) -> Option<Rect> {
+    if projection.determinant() == 0. {
+        return None;
+    }
+    let inverse = projection.inverse();
+    let corners = [(-1., -1.), (1., -1.), (-1., 1.), (1., 1.)]
+        .map(|(x, y)| inverse.project_point3(glam::vec3(x, y, 0.)).truncate());
+    let min = corners.iter().fold(corners[0], |min, c| min.min(*c));
+    let max = corners.iter().fold(corners[0], |max, c| max.max(*c));
+    Some(Rect::new(min.x, min.y, max.x - min.x, max.y - min.y))
+}
+
+/// Maps a rectangle back through a transform, returning the bounding box of the result.
+fn inverse_transform_rect(rect: Rect, transform: Transform) -> Rect {
+    let inverse = glam::Mat4::from(transform.to_bare_matrix()).inverse();
+    let corners = [
+        (rect.left(), rect.top()),
+        (rect.right(), rect.top()),
+        (rect.left(), rect.bottom()),
+        (rect.right(), rect.bottom()),
+    ]
+    .map(|(x, y)| inverse.transform_point3(glam::vec3(x, y, 0.)).truncate());
+    let min = corners.iter().fold(corners[0], |min, c| min.min(*c));
+    let max = corners.iter().fold(corners[0], |max, c| max.max(*c));
+    Rect::new(min.x, min.y, max.x - min.x, max.y - min.y)
+}
+
+/// Splits a tile layer into chunks, returning the bounds of each non-empty chunk along with
+/// the draws of its tiles, grouped by index into `tilesets`.
+#[allow(clippy::type_complexity)]
+fn chunk_instances(
+    layer: &TileLayer,
+    offset: mint::Vector2<f32>,
+    (tile_width, tile_height): (u32, u32),
+    tilesets: &[TilesetGeometry],
+) -> Vec<(Rect, Vec<(usize, Vec<DrawParam>)>)> {
+    let mut chunks = Vec::new();
+    for chunk_y in (0..layer.height).step_by(TILE_CHUNK_SIZE as usize) {
+        for chunk_x in (0..layer.width).step_by(TILE_CHUNK_SIZE as usize) {
+            let mut bounds: Option<Rect> = None;
+            let mut instances: Vec<_> = (0..tilesets.len()).map(|i| (i, Vec::new())).collect();
+
+            for y in chunk_y..(chunk_y + TILE_CHUNK_SIZE).min(layer.height) {
+                for x in chunk_x..(chunk_x + TILE_CHUNK_SIZE).min(layer.width) {
+                    let Some(tile) = layer.tile(x, y) else {
+                        continue;
+                    };
+                    // tilesets are sorted, so this is the last one starting at or before the id,
+                    // and ids past its end don't belong to any
+                    let Some(index) = tilesets.iter().rposition(|t| t.first_gid <= tile.id) else {
+                        continue;
+                    };
+                    let tileset = &tilesets[index];
+                    let Some(mut src) = tileset.tile_rect(tile.id) else {
+                        continue;
+                    };
+
+                    // Tiles larger than the grid stick out of the top and right of their cell.
+                    let (w, h) = (tileset.tile_width as f32, tileset.tile_height as f32);
+                    let x = (x * tile_width) as f32 + offset.x + tileset.offset.x;
+                    let y = ((y + 1) * tile_height) as f32 - h + offset.y + tileset.offset.y;
+                    let center = mint::Point2 {
+                        x: x + w / 2.,
+                        y: y + h / 2.,
+                    };
+
+                    src.x /= tileset.image_width as f32;
+                    src.w /= tileset.image_width as f32;
+                    src.y /= tileset.image_height as f32;
+                    src.h /= tileset.image_height as f32;
+
+                    let (scale, rotation) = tile.scale_rotation();
+                    instances[index].1.push(
+                        DrawParam::new()
+                            .src(src)
+                            .dest(center)
+                            .offset([0.5, 0.5])
+                            .scale(scale)
+                            .rotation(rotation),
+                    );
+
+                    // diagonal flips rotate the tile by 90°, swapping its width and height
+                    let (w, h) = if tile.flip_diagonal { (h, w) } else { (w, h) };
+                    let rect = Rect::new(center.x - w / 2., center.y - h / 2., w, h);
+                    bounds = Some(bounds.map_or(rect, |bounds| bounds.combine_with(rect)));
+                }
+            }
+
+            if let Some(bounds) = bounds {
+                chunks.push((bounds, instances));
+            }
+        }
+    }
+    chunks
+}
+
+fn read(gfx: &GraphicsContext, path: &Path) -> GameResult<Vec<u8>> {
+    let mut data = Vec::new();
+    let _ = gfx.fs.open(path)?.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+/// Resolves a path relative to the directory of `file`, including any `..`s in it, since the
+/// filesystem only accepts absolute paths.
+fn resolve_path(file: &Path, relative: &str) -> PathBuf {
+    let mut resolved = file
+        .parent()
+        .map_or_else(|| PathBuf::from("/"), Path::to_path_buf);
+    for component in Path::new(relative).components() {
+        match component {
+            Component::RootDir => resolved = PathBuf::from("/"),
+            Component::ParentDir => {
+                let _ = resolved.pop();
+            }
+            Component::Normal(name) => resolved.push(name),
+            Component::CurDir | Component::Prefix(_) => {}
+        }
+    }
+    resolved
+}
+
+fn invalid(msg: impl fmt::Display) -> GameError {
+    GameError::ResourceLoadError(format!("invalid Tiled map: {msg}"))
+}
+
+#[derive(Debug)]
+struct RawMap {
+    width: u32,
+    height: u32,
+    tile_width: u32,
+    tile_height: u32,
+    tilesets: Vec<RawTilesetRef>,
+    layers: Vec<MapLayer>,
+}
+
+#[derive(Debug)]
+enum RawTilesetRef {
+    Embedded(RawTileset),
+    External { first_gid: u32, source: String },
+}
+
+#[derive(Debug)]
+struct RawTileset {
+    name: String,
+    image: String,
+    geometry: TilesetGeometry,
+}
+
+impl RawTileset {
+    fn check(self) -> GameResult<Self> {
+        if self.geometry.columns == 0 {
+            return Err(invalid(format!("tileset {:?} has no columns", self.name)));
+        }
+        Ok(self)
+    }
+}
+
+fn check_map(orientation: &str, infinite: bool) -> GameResult {
+    if !orientation.is_empty() && orientation != "orthogonal" {
+        return Err(invalid(format!("{orientation} maps are not supported")));
+    }
+    if infinite {
+        return Err(invalid("infinite maps are not supported"));
+    }
+    Ok(())
+}
+
+fn tile_layer(width: u32, height: u32, tiles: Vec<u32>) -> GameResult<MapLayerKind> {
+    if tiles.len() != (width * height) as usize {
+        return Err(invalid(format!(
+            "layer has {} tiles instead of {width}x{height}",
+            tiles.len()
+        )));
+    }
+    Ok(MapLayerKind::Tiles(TileLayer {
+        width,
+        height,
+        tiles,
+        chunks: Vec::new(),
+    }))
+}
+
+/// Decodes the text of a tile layer's data, as stored with the `csv` or `base64` encodings.
+fn decode_tiles(data: &str, encoding: &str, compression: Option<&str>) -> GameResult<Vec<u32>> {
+    match encoding {
+        "csv" => data
+            .split(',')
+            .map(|gid| gid.trim().parse().map_err(invalid))
+            .collect(),
+        "base64" => {
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(data.trim())
+                .map_err(invalid)?;
+            let bytes = match compression.unwrap_or_default() {
+                "" => bytes,
+                "zlib" => {
+                    let mut out = Vec::new();
+                    let _ = flate2::read::ZlibDecoder::new(&bytes[..])
+                        .read_to_end(&mut out)
+                        .map_err(invalid)?;
+                    out
+                }
+                "gzip" => {
+                    let mut out = Vec::new();
+                    let _ = flate2::read::GzDecoder::new(&bytes[..])
+                        .read_to_end(&mut out)
+                        .map_err(invalid)?;
+                    out
+                }
+                other => return Err(invalid(format!("unsupported compression {other:?}"))),
+            };
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|gid| u32::from_le_bytes([gid[0], gid[1], gid[2], gid[3]]))
+                .collect())
+        }
+        other => Err(invalid(format!("unsupported encoding {other:?}"))),
+    }
+}
+
+/// The accumulated offset, opacity and visibility of the groups a layer is nested in.
+#[derive(Debug, Copy, Clone)]
+struct LayerGroup {
+    visible: bool,
+    opacity: f32,
+    offset: mint::Vector2<f32>,
+}
+
+impl LayerGroup {
+    const ROOT: LayerGroup = LayerGroup {
+        visible: true,
+        opacity: 1.,
+        offset: mint::Vector2 { x: 0., y: 0. },
+    };
+
+    /// Combines the group with the attributes of a layer (or group) inside of it.
+    fn nest(self, visible: bool, opacity: f32, offset: mint::Vector2<f32>) -> Self {
+        LayerGroup {
+            visible: self.visible && visible,
+            opacity: self.opacity * opacity,
+            offset: mint::Vector2 {
+                x: self.offset.x + offset.x,
+                y: self.offset.y + offset.y,
+            },
+        }
+    }
+
+    fn layer(self, name: String, kind: MapLayerKind) -> MapLayer {
+        MapLayer {
+            name,
+            visible: self.visible,
+            opacity: self.opacity,
+            offset: self.offset,
+            kind,
+        }
+    }
+}
+
+// TMX (XML) files
+
+type XmlNode<'a, 'input> = roxmltree::Node<'a, 'input>;
+
+fn attr<T: std::str::FromStr>(node: XmlNode, name: &str) -> GameResult<Option<T>> {
+    node.attribute(name)
+        .map(|value| {
+            value.parse().map_err(|_| {
+                invalid(format!(
+                    "invalid `{name}` attribute {value:?} in <{}>",
+                    node.tag_name().name()
+                ))
+            })
+        })
+        .transpose()
+}
+
+fn required_attr<T: std::str::FromStr>(node: XmlNode, name: &str) -> GameResult<T> {
+    attr(node, name)?.ok_or_else(|| {
+        invalid(format!(
+            "missing `{name}` attribute in <{}>",
+            node.tag_name().name()
+        ))
+    })
+}
+
+fn parse_xml(data: &[u8]) -> GameResult<roxmltree::Document<'_>> {
+    let text = std::str::from_utf8(data).map_err(invalid)?;
+    roxmltree::Document::parse(text).map_err(invalid)
+}
+
+fn parse_tmx(data: &[u8]) -> GameResult<RawMap> {
+    let doc = parse_xml(data)?;
+    let map = doc.root_element();
+    if !map.has_tag_name("map") {
+        return Err(invalid("the root element isn't <map>"));
+    }
+    check_map(
+        map.attribute("orientation").unwrap_or_default(),
+        map.attribute("infinite") == Some("1"),
+    )?;
+
+    let mut tilesets = Vec::new();
+    for tileset in map.children().filter(|n| n.has_tag_name("tileset")) {
+        let first_gid = required_attr(tileset, "firstgid")?;
+        tilesets.push(match tileset.attribute("source") {
+            Some(source) => RawTilesetRef::External {
+                first_gid,
+                source: source.to_string(),
+            },
+            None => {
+                let mut tileset = tsx_tileset(tileset)?;
+                tileset.geometry.first_gid = first_gid;
+                RawTilesetRef::Embedded(tileset)
+            }
+        });
+    }
+
+    let mut layers = Vec::new();
+    tmx_layers(map, LayerGroup::ROOT, &mut layers)?;
+
+    Ok(RawMap {
+        width: required_attr(map, "width")?,
+        height: required_attr(map, "height")?,
+        tile_width: required_attr(map, "tilewidth")?,
+        tile_height: required_attr(map, "tileheight")?,
+        tilesets,
+        layers,
+    })
+}
+
+fn parse_tsx(data: &[u8]) -> GameResult<RawTileset> {
+    let doc = parse_xml(data)?;
+    let tileset = doc.root_element();
+    if !tileset.has_tag_name("tileset") {
+        return Err(invalid("the root element of the tileset isn't <tileset>"));
+    }
+    tsx_tileset(tileset)
+}
+
+fn tsx_tileset(tileset: XmlNode) -> GameResult<RawTileset> {
+    let name: String = attr(tileset, "name")?.unwrap_or_default();
+    let image = tileset
+        .children()
+        .find(|n| n.has_tag_name("image"))
+        .ok_or_else(|| invalid(format!("tileset {name:?} isn't a single image")))?;
+    let offset = match tileset.children().find(|n| n.has_tag_name("tileoffset")) {
+        Some(offset) => mint::Vector2 {
+            x: attr(offset, "x")?.unwrap_or_default(),
+            y: attr(offset, "y")?.unwrap_or_default(),
+        },
+        None => mint::Vector2 { x: 0., y: 0. },
+    };
+    RawTileset {
+        image: required_attr(image, "source")?,
+        geometry: TilesetGeometry {
+            first_gid: 1,
+            tile_count: required_attr(tileset, "tilecount")?,
+            tile_width: required_attr(tileset, "tilewidth")?,
+            tile_height: required_attr(tileset, "tileheight")?,
+            columns: required_attr(tileset, "columns")?,
+            spacing: attr(tileset, "spacing")?.unwrap_or_default(),
+            margin: attr(tileset, "margin")?.unwrap_or_default(),
+            offset,
+            image_width: required_attr(image, "width")?,
+            image_height: required_attr(image, "height")?,
+        },
+        name,
+    }
+    .check()
+}
+
+fn tmx_layers(parent: XmlNode, group: LayerGroup, layers: &mut Vec<MapLayer>) -> GameResult {
+    for node in parent.children().filter(|n| n.is_element()) {
+        let group = group.nest(
+            node.attribute("visible") != Some("0"),
+            attr(node, "opacity")?.unwrap_or(1.),
+            mint::Vector2 {
+                x: attr(node, "offsetx")?.unwrap_or_default(),
+                y: attr(node, "offsety")?.unwrap_or_default(),
+            },
+        );
+        let kind = match node.tag_name().name() {
+            "layer" => {
+                let data = node
+                    .children()
+                    .find(|n| n.has_tag_name("data"))
+                    .ok_or_else(|| invalid("tile layer without <data>"))?;
+                if data.children().any(|n| n.has_tag_name("chunk")) {
+                    return Err(invalid("infinite maps are not supported"));
+                }
+                let tiles = match data.attribute("encoding") {
+                    Some(encoding) => decode_tiles(
+                        data.text().unwrap_or_default(),
+                        encoding,
+                        data.attribute("compression"),
+                    )?,
+                    None => data
+                        .children()
+                        .filter(|n| n.has_tag_name("tile"))
+                        .map(|tile| Ok(attr(tile, "gid")?.unwrap_or_default()))
+                        .collect::<GameResult<_>>()?,
+                };
+                tile_layer(
+                    required_attr(node, "width")?,
+                    required_attr(node, "height")?,
+                    tiles,
+                )?
+            }
+            "objectgroup" => MapLayerKind::Objects(
+                node.children()
+                    .filter(|n| n.has_tag_name("object"))
+                    .map(tmx_object)
+                    .collect::<GameResult<_>>()?,
+            ),
+            "group" => {
+                tmx_layers(node, group, layers)?;
+                continue;
+            }
+            _ => continue,
+        };
+        layers.push(group.layer(attr(node, "name")?.unwrap_or_default(), kind));
+    }
+    Ok(())
+}
+
+fn tmx_object(object: XmlNode) -> GameResult<MapObject> {
+    let points = |node: XmlNode| -> GameResult<Vec<mint::Point2<f32>>> {
+        node.attribute("points")
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(|point| {
+                let (x, y) = point
+                    .split_once(',')
+                    .ok_or_else(|| invalid(format!("invalid point {point:?}")))?;
+                Ok(mint::Point2 {
+                    x: x.parse().map_err(invalid)?,
+                    y: y.parse().map_err(invalid)?,
+                })
+            })
+            .collect()
+    };
+
+    let mut shape = ObjectShape::Rectangle;
+    let mut properties = HashMap::new();
+    for child in object.children().filter(|n| n.is_element()) {
+        match child.tag_name().name() {
+            "ellipse" => shape = ObjectShape::Ellipse,
+            "point" => shape = ObjectShape::Point,
+            "polygon" => shape = ObjectShape::Polygon(points(child)?),
+            "polyline" => shape = ObjectShape::Polyline(points(child)?),
+            "properties" => {
+                for property in child.children().filter(|n| n.has_tag_name("property")) {
+                    let value = property
+                        .attribute("value")
+                        .or_else(|| property.text())
+                        .unwrap_or_default();
+                    let _ = properties.insert(required_attr(property, "name")?, value.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(MapObject {
+        id: attr(object, "id")?.unwrap_or_default(),
+        name: attr(object, "name")?.unwrap_or_default(),
+        class: object
+            .attribute("class")
+            .or_else(|| object.attribute("type"))
+            .unwrap_or_default()
+            .to_string(),
+        rect: Rect::new(
+            attr(object, "x")?.unwrap_or_default(),
+            attr(object, "y")?.unwrap_or_default(),
+            attr(object, "width")?.unwrap_or_default(),
+            attr(object, "height")?.unwrap_or_default(),
+        ),
+        rotation: attr::<f32>(object, "rotation")?
+            .unwrap_or_default()
+            .to_radians(),
+        visible: object.attribute("visible") != Some("0"),
+        tile: attr(object, "gid")?.and_then(Tile::from_gid),
+        shape,
+        properties,
+    })
+}
+
+// JSON files
+
+#[derive(Deserialize)]
+struct JsonMap {
+    width: u32,
+    height: u32,
+    tilewidth: u32,
+    tileheight: u32,
+    #[serde(default)]
+    orientation: String,
+    #[serde(default)]
+    infinite: bool,
+    #[serde(default)]
+    tilesets: Vec<JsonTileset>,
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+}
+
+#[derive(Deserialize)]
+struct JsonTileset {
+    firstgid: Option<u32>,
+    source: Option<String>,
+    #[serde(default)]
+    name: String,
+    tilewidth: Option<u32>,
+    tileheight: Option<u32>,
+    columns: Option<u32>,
+    tilecount: Option<u32>,
+    #[serde(default)]
+    spacing: u32,
+    #[serde(default)]
+    margin: u32,
+    image: Option<String>,
+    imagewidth: Option<u32>,
+    imageheight: Option<u32>,
+    tileoffset: Option<JsonPoint>,
+}
+
+#[derive(Deserialize)]
+struct JsonLayer {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    visible: Option<bool>,
+    opacity: Option<f32>,
+    #[serde(default)]
+    offsetx: f32,
+    #[serde(default)]
+    offsety: f32,
+    #[serde(default)]
+    width: u32,
+    #[serde(default)]
+    height: u32,
+    data: Option<serde_json::Value>,
+    #[serde(default)]
+    encoding: Option<String>,
+    #[serde(default)]
+    compression: Option<String>,
+    chunks: Option<serde_json::Value>,
+    #[serde(default)]
+    objects: Vec<JsonObject>,
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+}
+
+#[derive(Deserialize)]
+struct JsonObject {
+    #[serde(default)]
+    id: u32,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    class: Option<String>,
+    #[serde(default, rename = "type")]
+    kind: Option<String>,
+    #[serde(default)]
+    x: f32,
+    #[serde(default)]
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    #[serde(default)]
+    rotation: f32,
+    visible: Option<bool>,
+    gid: Option<u32>,
+    #[serde(default)]
+    ellipse: bool,
+    #[serde(default)]
+    point: bool,
+    polygon: Option<Vec<JsonPoint>>,
+    polyline: Option<Vec<JsonPoint>>,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Deserialize)]
+struct JsonPoint {
+    x: f32,
+    y: f32,
+}
+
+impl From<JsonPoint> for mint::Point2<f32> {
+    fn from(point: JsonPoint) -> Self {
+        mint::Point2 {
+            x: point.x,
+            y: point.y,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct JsonProperty {
+    name: String,
+    value: serde_json::Value,
+}
+
+fn parse_json_map(data: &[u8]) -> GameResult<RawMap> {
+    let map: JsonMap = serde_json::from_slice(data).map_err(invalid)?;
+    check_map(&map.orientation, map.infinite)?;
+
+    let tilesets = map
+        .tilesets
+        .into_iter()
+        .map(|tileset| {
+            let first_gid = tileset
+                .firstgid
+                .ok_or_else(|| invalid("tileset without `firstgid`"))?;
+            Ok(match tileset.source {
+                Some(source) => RawTilesetRef::External { first_gid, source },
+                None => {
+                    let mut tileset = json_tileset(tileset)?;
+                    tileset.geometry.first_gid = first_gid;
+                    RawTilesetRef::Embedded(tileset)
+                }
+            })
+        })
+        .collect::<GameResult<_>>()?;
+
+    let mut layers = Vec::new();
+    json_layers(map.layers, LayerGroup::ROOT, &mut layers)?;
+
+    Ok(RawMap {
+        width: map.width,
+        height: map.height,
+        tile_width: map.tilewidth,
+        tile_height: map.tileheight,
+        tilesets,
+        layers,
+    })
+}
+
+fn parse_json_tileset(data: &[u8]) -> GameResult<RawTileset> {
+    json_tileset(serde_json::from_slice(data).map_err(invalid)?)
+}
+
+fn json_tileset(tileset: JsonTileset) -> GameResult<RawTileset> {
+    let name = tileset.name;
+    let missing = |field: &str| invalid(format!("tileset {name:?} has no `{field}`"));
+    let image = tileset
+        .image
+        .ok_or_else(|| invalid(format!("tileset {name:?} isn't a single image")))?;
+    RawTileset {
+        image,
+        geometry: TilesetGeometry {
+            first_gid: 1,
+            tile_count: tileset.tilecount.ok_or_else(|| missing("tilecount"))?,
+            tile_width: tileset.tilewidth.ok_or_else(|| missing("tilewidth"))?,
+            tile_height: tileset.tileheight.ok_or_else(|| missing("tileheight"))?,
+            columns: tileset.columns.ok_or_else(|| missing("columns"))?,
+            spacing: tileset.spacing,
+            margin: tileset.margin,
+            offset: tileset
+                .tileoffset
+                .map_or(mint::Vector2 { x: 0., y: 0. }, |offset| mint::Vector2 {
+                    x: offset.x,
+                    y: offset.y,
+                }),
+            image_width: tileset.imagewidth.ok_or_else(|| missing("imagewidth"))?,
+            image_height: tileset.imageheight.ok_or_else(|| missing("imageheight"))?,
+        },
+        name,
+    }
+    .check()
+}
+
+fn json_layers(json: Vec<JsonLayer>, group: LayerGroup, layers: &mut Vec<MapLayer>) -> GameResult {
+    for layer in json {
+        let group = group.nest(
+            layer.visible.unwrap_or(true),
+            layer.opacity.unwrap_or(1.),
+            mint::Vector2 {
+                x: layer.offsetx,
+                y: layer.offsety,
+            },
+        );
+        let kind = match layer.kind.as_str() {
+            "tilelayer" => {
+                if layer.chunks.is_some() {
+                    return Err(invalid("infinite maps are not supported"));
+                }
+                let tiles = match layer.data {
+                    Some(serde_json::Value::String(data)) => decode_tiles(
+                        &data,
+                        layer.encoding.as_deref().unwrap_or("base64"),
+                        layer.compression.as_deref(),
+                    )?,
+                    Some(data) => serde_json::from_value(data).map_err(invalid)?,
+                    None => return Err(invalid("tile layer without `data`")),
+                };
+                tile_layer(layer.width, layer.height, tiles)?
+            }
+            "objectgroup" => {
+                MapLayerKind::Objects(layer.objects.into_iter().map(json_object).collect())
+            }
+            "group" => {
+                json_layers(layer.layers, group, layers)?;
+                continue;
+            }
+            _ => continue,
+        };
+        layers.push(group.layer(layer.name, kind));
+    }
+    Ok(())
+}
+
+fn json_object(object: JsonObject) -> MapObject {
+    let shape = if let Some(points) = object.polygon {
+        ObjectShape::Polygon(points.into_iter().map(Into::into).collect())
+    } else if let Some(points) = object.polyline {
+        ObjectShape::Polyline(points.into_iter().map(Into::into).collect())
+    } else if object.ellipse {
+        ObjectShape::Ellipse
+    } else if object.point {
+        ObjectShape::Point
+    } else {
+        ObjectShape::Rectangle
+    };
+
+    MapObject {
+        id: object.id,
+        name: object.name,
+        class: object.class.or(object.kind).unwrap_or_default(),
+        rect: Rect::new(object.x, object.y, object.width, object.height),
+        rotation: object.rotation.to_radians(),
+        visible: object.visible.unwrap_or(true),
+        tile: object.gid.and_then(Tile::from_gid),
+        shape,
+        properties: object
+            .properties
+            .into_iter()
+            .map(|property| {
+                let value = match property.value {
+                    serde_json::Value::String(value) => value,
+                    value => value.to_string(),
+                };
+                (property.name, value)
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const TMX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="20" height="2" tilewidth="16" tileheight="16" infinite="0">
+ <tileset firstgid="1" source="../tilesets/terrain.tsx"/>
+ <tileset firstgid="101" name="props" tilewidth="16" tileheight="32" tilecount="4" columns="4" spacing="1" margin="1">
+  <image source="props.png" width="69" height="34"/>
+ </tileset>
+ <group name="world" offsetx="4" opacity="0.5">
+  <layer id="1" name="ground" width="20" height="2" offsety="2">
+   <data encoding="csv">
+1,2,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,
+0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,2147483749,3
+</data>
+  </layer>
+ </group>
+ <objectgroup name="spawns" visible="0">
+  <object id="3" name="player" type="spawn" x="8" y="24" rotation="90">
+   <properties><property name="health" type="int" value="3"/></properties>
+   <point/>
+  </object>
+  <object id="4" x="0" y="0"><polygon points="0,0 16,0 8,-8"/></object>
+ </objectgroup>
+</map>"#;
+
+    const JSON: &str = r#"{
+        "width": 2, "height": 2, "tilewidth": 8, "tileheight": 8,
+        "orientation": "orthogonal", "infinite": false,
+        "tilesets": [{"firstgid": 1, "source": "terrain.tsj"}],
+        "layers": [
+            {"type": "tilelayer", "name": "a", "width": 2, "height": 2, "data": [1, 0, 0, 1610612738]},
+            {"type": "group", "name": "g", "visible": false, "layers": [
+                {"type": "tilelayer", "name": "b", "width": 2, "height": 2, "encoding": "base64",
+                 "compression": "zlib", "data": "ZLIB"}
+            ]},
+            {"type": "imagelayer", "name": "sky", "image": "sky.png"},
+            {"type": "objectgroup", "name": "o", "objects": [
+                {"id": 7, "class": "door", "x": 1, "y": 2, "width": 8, "height": 16, "ellipse": true,
+                 "properties": [{"name": "locked", "type": "bool", "value": true}]}
+            ]}
+        ]
+    }"#;
+
+    fn tiles(layer: &MapLayer) -> &TileLayer {
+        match &layer.kind {
+            MapLayerKind::Tiles(tiles) => tiles,
+            MapLayerKind::Objects(_) => panic!("not a tile layer"),
+        }
+    }
+
+    #[test]
+    fn headless_test_parse_tmx() {
+        let map = parse_tmx(TMX.as_bytes()).unwrap();
+        assert_eq!((map.width, map.height, map.tile_width), (20, 2, 16));
+        assert!(matches!(
+            &map.tilesets[0],
+            RawTilesetRef::External { first_gid: 1, source } if source == "../tilesets/terrain.tsx"
+        ));
+        let RawTilesetRef::Embedded(props) = &map.tilesets[1] else {
+            panic!("props tileset should be embedded")
+        };
+        assert_eq!(props.geometry.first_gid, 101);
+        assert_eq!(
+            props.geometry.tile_rect(102),
+            Some(Rect::new(18., 1., 16., 32.))
+        );
+        assert_eq!(props.geometry.tile_rect(100), None);
+        assert_eq!(props.geometry.tile_rect(105), None);
+
+        assert_eq!(map.layers.len(), 2);
+        let ground = &map.layers[0];
+        assert_eq!(ground.name, "ground");
+        assert_eq!(ground.opacity, 0.5);
+        assert_eq!(ground.offset, mint::Vector2 { x: 4., y: 2. });
+        let ground = tiles(ground);
+        assert_eq!(ground.tile(1, 0).unwrap().id, 2);
+        assert_eq!(ground.tile(2, 0), None);
+        assert_eq!(
+            ground.tile(18, 1),
+            Some(Tile {
+                id: 101,
+                flip_horizontal: true,
+                flip_vertical: false,
+                flip_diagonal: false,
+            })
+        );
+
+        let spawns = &map.layers[1];
+        assert!(!spawns.visible);
+        let MapLayerKind::Objects(objects) = &spawns.kind else {
+            panic!("not an object layer")
+        };
+        assert_eq!(objects[0].class, "spawn");
+        assert_eq!(objects[0].shape, ObjectShape::Point);
+        assert_eq!(objects[0].properties["health"], "3");
+        assert_eq!(objects[0].rotation, std::f32::consts::FRAC_PI_2);
+        assert_eq!(
+            objects[1].shape,
+            ObjectShape::Polygon(vec![[0., 0.].into(), [16., 0.].into(), [8., -8.].into()])
+        );
+    }
+
+    #[test]
+    fn headless_test_parse_json() {
+        let gids: Vec<u8> = [3u32, 0, 0, 4]
+            .iter()
+            .flat_map(|gid| gid.to_le_bytes())
+            .collect();
+        let mut zlib = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::fast());
+        zlib.write_all(&gids).unwrap();
+        let zlib = base64::engine::general_purpose::STANDARD.encode(zlib.finish().unwrap());
+
+        let map = parse_json_map(JSON.replace("ZLIB", &zlib).as_bytes()).unwrap();
+        assert!(matches!(
+            &map.tilesets[0],
+            RawTilesetRef::External { first_gid: 1, source } if source == "terrain.tsj"
+        ));
+        assert_eq!(map.layers.len(), 3);
+        assert_eq!(
+            tiles(&map.layers[0]).tile(1, 1),
+            Some(Tile {
+                id: 2,
+                flip_horizontal: false,
+                flip_vertical: true,
+                flip_diagonal: true,
+            })
+        );
+        assert_eq!(map.layers[1].name, "b");
+        assert!(!map.layers[1].visible);
+        assert_eq!(tiles(&map.layers[1]).tiles, [3, 0, 0, 4]);
+
+        let MapLayerKind::Objects(objects) = &map.layers[2].kind else {
+            panic!("not an object layer")
+        };
+        assert_eq!(objects[0].class, "door");
+        assert_eq!(objects[0].rect, Rect::new(1., 2., 8., 16.));
+        assert_eq!(objects[0].shape, ObjectShape::Ellipse);
+        assert_eq!(objects[0].properties["locked"], "true");
+
+        let infinite = JSON.replace(r#""infinite": false"#, r#""infinite": true"#);
+        assert!(parse_json_map(infinite.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn headless_test_chunk_instances() {
+        let layer = TileLayer {
+            width: 20,
+            height: 2,
+            tiles: (0..40)
+                .map(|i| match i {
+                    0 => 1,
+                    39 => 2 | FLIPPED_DIAGONALLY,
+                    // past the end of the tileset
+                    20 => 3,
+                    _ => 0,
+                })
+                .collect(),
+            chunks: Vec::new(),
+        };
+        let tileset = TilesetGeometry {
+            first_gid: 1,
+            tile_count: 2,
+            tile_width: 8,
+            tile_height: 8,
+            columns: 2,
+            spacing: 0,
+            margin: 0,
+            offset: mint::Vector2 { x: 0., y: 0. },
+            image_width: 16,
+            image_height: 8,
+        };
+        let chunks = chunk_instances(&layer, [1., 0.].into(), (8, 8), &[tileset]);
+        assert_eq!(chunks.len(), 2);
+
+        let (bounds, instances) = &chunks[0];
+        assert_eq!(*bounds, Rect::new(1., 0., 8., 8.));
+        assert_eq!(instances[0].1.len(), 1);
+
+        let (bounds, instances) = &chunks[1];
+        assert_eq!(*bounds, Rect::new(153., 8., 8., 8.));
+        let param = instances[0].1[0];
+        assert_eq!(param.src, Rect::new(0.5, 0., 0.5, 1.));
+        if let Transform::Values {
+            dest,
+            rotation,
+            scale,
+            ..
+        } = param.transform
+        {
+            assert_eq!(dest, [157., 12.].into());
+            assert_eq!(rotation, std::f32::consts::FRAC_PI_2);
+            assert_eq!(scale, [1., -1.].into());
+        } else {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn headless_test_zero_columns() {
+        let tsx = r#"<tileset name="empty" tilewidth="8" tileheight="8" tilecount="0" columns="0">
+ <image source="empty.png" width="0" height="0"/>
+</tileset>"#;
+        let err = parse_tsx(tsx.as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("has no columns"), "{err}");
+    }
+
+    #[test]
+    fn headless_test_projected_rect() {
+        let screen = Rect::new(10., 20., 100., 50.);
+        let visible =
+            projected_rect(crate::graphics::internal_canvas::screen_to_mat(screen)).unwrap();
+        assert!((visible.x - screen.x).abs() < 1e-3);
+        assert!((visible.y - screen.y).abs() < 1e-3);
+        assert!((visible.w - screen.w).abs() < 1e-3);
+        assert!((visible.h - screen.h).abs() < 1e-3);
+        assert_eq!(projected_rect(glam::Mat4::ZERO), None);
+    }
+
+    #[test]
+    fn headless_test_resolve_path() {
+        assert_eq!(
+            resolve_path(Path::new("/maps/level1.tmx"), "../tilesets/./terrain.tsx"),
+            Path::new("/tilesets/terrain.tsx")
+        );
+        assert_eq!(
+            resolve_path(Path::new("/level1.tmx"), "props.png"),
+            Path::new("/props.png")
+        );
+    }
+}
@@ -5,21 +5,61 @@
 //! cross-platform support.  Why not give it a hand?
 #![cfg(feature = "gamepad")]
 
-use gilrs::ConnectedGamepadsIterator;
-use std::fmt;
+use gilrs::{
+    ff::{self, BaseEffect, BaseEffectType, EffectBuilder, Replay, Ticks},
+    ConnectedGamepadsIterator,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    hash::Hash,
+    time::Duration,
+};
 
-pub use gilrs::{self, Event, Gamepad, Gilrs};
+pub use gilrs::{self, Axis, Button, Event, Gamepad, Gilrs};
 
 /// A unique identifier for a particular gamepad
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
-pub struct GamepadId(pub(crate) gilrs::GamepadId);
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct GamepadId(GamepadIdKind);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+enum GamepadIdKind {
+    /// A gamepad reported by gilrs.
+    Gilrs(gilrs::GamepadId),
+    /// A gamepad only fed to a [`TestHarness`](crate::testing::TestHarness), unknown to gilrs.
+    Virtual(usize),
+}
+
+impl GamepadId {
+    pub(crate) fn from_gilrs(id: gilrs::GamepadId) -> Self {
+        GamepadId(GamepadIdKind::Gilrs(id))
+    }
+
+    pub(crate) fn from_index(index: usize) -> Self {
+        GamepadId(GamepadIdKind::Virtual(index))
+    }
+
+    fn gilrs_id(self) -> Option<gilrs::GamepadId> {
+        match self.0 {
+            GamepadIdKind::Gilrs(id) => Some(id),
+            GamepadIdKind::Virtual(_) => None,
+        }
+    }
+}
 
 use crate::context::Context;
 use crate::error::GameResult;
 
 /// A structure that contains gamepad state using `gilrs`.
 pub struct GamepadContext {
-    pub(crate) gilrs: Gilrs,
+    // `None` for headless contexts on machines where gilrs couldn't be initialized.
+    pub(crate) gilrs: Option<Gilrs>,
+    rumble: RumbleScheduler<Gilrs>,
+    pressed_buttons: HashSet<(GamepadId, Button)>,
+    axes: HashMap<(GamepadId, Axis), f32>,
+    // Represents the state of the buttons and axes last frame.
+    previously_pressed_buttons: HashSet<(GamepadId, Button)>,
+    previous_axes: HashMap<(GamepadId, Axis), f32>,
 }
 
 impl fmt::Debug for GamepadContext {
@@ -31,39 +71,450 @@ impl fmt::Debug for GamepadContext {
 impl GamepadContext {
     pub(crate) fn new() -> GameResult<Self> {
         let gilrs = Gilrs::new()?;
-        Ok(GamepadContext { gilrs })
+        Ok(Self::from(gilrs))
+    }
+
+    /// Creates a context without gilrs, which only knows about the gamepads fed to it, like the
+    /// virtual ones of a [`TestHarness`](crate::testing::TestHarness).
+    pub(crate) fn without_gilrs() -> Self {
+        Self {
+            gilrs: None,
+            rumble: RumbleScheduler::new(),
+            pressed_buttons: HashSet::new(),
+            axes: HashMap::new(),
+            previously_pressed_buttons: HashSet::new(),
+            previous_axes: HashMap::new(),
+        }
     }
 }
 
 impl From<Gilrs> for GamepadContext {
     /// Converts from a `Gilrs` custom instance to a `GilrsGamepadContext`
     fn from(gilrs: Gilrs) -> Self {
-        Self { gilrs }
+        Self {
+            gilrs: Some(gilrs),
+            ..Self::without_gilrs()
+        }
     }
 }
 
 impl GamepadContext {
     /// Returns a gamepad event.
+    ///
+    /// Button and axis events also update the state returned by
+    /// [`GamepadContext::is_button_pressed`] and [`GamepadContext::axis_value`].
     pub fn next_event(&mut self) -> Option<Event> {
-        self.gilrs.next_event()
+        let event = self.gilrs.as_mut()?.next_event()?;
+        let id = GamepadId::from_gilrs(event.id);
+        match event.event {
+            gilrs::EventType::ButtonPressed(button, _) => self.set_button(id, button, true),
+            gilrs::EventType::ButtonReleased(button, _) => self.set_button(id, button, false),
+            gilrs::EventType::AxisChanged(axis, value, _) => self.set_axis(id, axis, value),
+            gilrs::EventType::Disconnected => {
+                self.pressed_buttons.retain(|(pad, _)| *pad != id);
+                self.axes.retain(|(pad, _), _| *pad != id);
+            }
+            _ => (),
+        }
+        Some(event)
+    }
+
+    /// Checks if a button on the gamepad is currently pressed down.
+    pub fn is_button_pressed(&self, id: GamepadId, button: Button) -> bool {
+        self.pressed_buttons.contains(&(id, button))
+    }
+
+    /// Checks if a button on the gamepad has been pressed down this frame.
+    pub fn is_button_just_pressed(&self, id: GamepadId, button: Button) -> bool {
+        self.pressed_buttons.contains(&(id, button))
+            && !self.previously_pressed_buttons.contains(&(id, button))
+    }
+
+    /// Checks if a button on the gamepad has been released this frame.
+    pub fn is_button_just_released(&self, id: GamepadId, button: Button) -> bool {
+        !self.pressed_buttons.contains(&(id, button))
+            && self.previously_pressed_buttons.contains(&(id, button))
+    }
+
+    /// Returns the current value of an axis on the gamepad, from `-1.0` to `1.0`.
+    pub fn axis_value(&self, id: GamepadId, axis: Axis) -> f32 {
+        self.axes.get(&(id, axis)).copied().unwrap_or(0.0)
+    }
+
+    /// Copies the current state of the gamepad buttons and axes into the context. If you are writing
+    /// your own event loop you need to call this at the end of every update in order to use the
+    /// functions `is_button_just_pressed` and `is_button_just_released`. Otherwise this is handled for you.
+    pub fn save_gamepad_state(&mut self) {
+        self.previously_pressed_buttons = self.pressed_buttons.clone();
+        self.previous_axes = self.axes.clone();
+    }
+
+    pub(crate) fn set_button(&mut self, id: GamepadId, button: Button, pressed: bool) {
+        if pressed {
+            let _ = self.pressed_buttons.insert((id, button));
+        } else {
+            let _ = self.pressed_buttons.remove(&(id, button));
+        }
+    }
+
+    pub(crate) fn set_axis(&mut self, id: GamepadId, axis: Axis, value: f32) {
+        let _ = self.axes.insert((id, axis), value);
+    }
+
+    /// Returns whether the button was pressed last frame and whether it is pressed now,
+    /// on the given gamepad or on any gamepad.
+    pub(crate) fn button_state(&self, id: Option<GamepadId>, button: Button) -> (bool, bool) {
+        let matches =
+            |(pad, b): &(GamepadId, Button)| *b == button && (id.is_none() || id == Some(*pad));
+        (
+            self.previously_pressed_buttons.iter().any(matches),
+            self.pressed_buttons.iter().any(matches),
+        )
+    }
+
+    /// Returns the value of the axis last frame and now, on the given gamepad or, for
+    /// any gamepad, the one pushed the furthest.
+    pub(crate) fn axis_state(&self, id: Option<GamepadId>, axis: Axis) -> (f32, f32) {
+        let furthest = |axes: &HashMap<(GamepadId, Axis), f32>| {
+            axes.iter()
+                .filter(|((pad, a), _)| *a == axis && (id.is_none() || id == Some(*pad)))
+                .map(|(_, value)| *value)
+                .fold(0.0f32, |acc, v| if v.abs() > acc.abs() { v } else { acc })
+        };
+        (furthest(&self.previous_axes), furthest(&self.axes))
     }
 
     /// Returns the `Gamepad` associated with an `id`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the id wasn't reported by gilrs, e.g. one fed to a
+    /// [`TestHarness`](crate::testing::TestHarness).
     pub fn gamepad(&self, id: GamepadId) -> Gamepad {
-        self.gilrs.gamepad(id.0)
+        let gilrs_id = id
+            .gilrs_id()
+            .unwrap_or_else(|| panic!("{id:?} is not a gilrs gamepad"));
+        self.gilrs
+            .as_ref()
+            .expect("ids of gilrs gamepads only come from gilrs")
+            .gamepad(gilrs_id)
     }
 
     /// Return an iterator of all the `Gamepads` that are connected.
     pub fn gamepads(&self) -> GamepadsIterator {
         GamepadsIterator {
-            wrapped: self.gilrs.gamepads(),
+            wrapped: self.gilrs.as_ref().map(Gilrs::gamepads),
+        }
+    }
+
+    /// Returns `true` if the gamepad is connected and supports force feedback.
+    pub fn supports_rumble(&self, id: GamepadId) -> bool {
+        self.gilrs
+            .as_ref()
+            .is_some_and(|gilrs| gilrs.supports_rumble(id))
+    }
+
+    /// Starts rumbling the gamepad, replacing any rumble that is already playing on it.
+    ///
+    /// Returns `Ok(false)` and does nothing if the gamepad is disconnected or
+    /// doesn't support force feedback, so this can be called for any pad.
+    pub fn rumble(&mut self, id: GamepadId, rumble: Rumble) -> GameResult<bool> {
+        match &mut self.gilrs {
+            Some(gilrs) => self.rumble.rumble(gilrs, id, rumble),
+            None => Ok(false),
+        }
+    }
+
+    /// Stops the rumble playing on the gamepad, if any.
+    pub fn stop_rumble(&mut self, id: GamepadId) -> GameResult {
+        match &mut self.gilrs {
+            Some(gilrs) => self.rumble.stop(gilrs, id),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns `true` if a rumble started with [`GamepadContext::rumble`] is still playing.
+    pub fn is_rumbling(&self, id: GamepadId) -> bool {
+        self.rumble.is_rumbling(id)
+    }
+
+    /// Advances the running rumbles by `dt`, stopping the ones that are over.
+    ///
+    /// [`event::run`](crate::event::run) calls this every frame; you only need to call it
+    /// when rolling your own event loop.
+    pub fn update_rumble(&mut self, dt: Duration) -> GameResult {
+        match &mut self.gilrs {
+            Some(gilrs) => self.rumble.advance(gilrs, dt),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A rumble (force feedback) effect for a gamepad.
+///
+/// Magnitudes range from `0.0` (off) to `1.0` (full strength). Most gamepads have a
+/// strong, low-frequency motor and a weak, high-frequency one.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rumble {
+    /// Magnitude of the strong (low-frequency) motor.
+    pub strong: f32,
+    /// Magnitude of the weak (high-frequency) motor.
+    pub weak: f32,
+    /// How long the rumble plays for.
+    pub duration: Duration,
+}
+
+impl Rumble {
+    /// Creates a new rumble, clamping the magnitudes to `0.0..=1.0`.
+    pub fn new(strong: f32, weak: f32, duration: Duration) -> Self {
+        Rumble {
+            strong: strong.clamp(0.0, 1.0),
+            weak: weak.clamp(0.0, 1.0),
+            duration,
+        }
+    }
+}
+
+/// Something that can play [`Rumble`]s on gamepads.
+///
+/// This is implemented for [`Gilrs`], which is what [`GamepadContext`] uses, and for
+/// [`TestRumbleBackend`], which just records what it was asked to do.
+pub trait RumbleBackend {
+    /// Identifies a gamepad.
+    type Id: Copy + Eq + Hash + fmt::Debug;
+    /// Handle to a playing rumble, kept alive until the rumble is over.
+    type Effect;
+
+    /// Returns `true` if the gamepad can rumble at all.
+    fn supports_rumble(&self, id: Self::Id) -> bool;
+
+    /// Starts playing `rumble` on the gamepad.
+    ///
+    /// Returns `Ok(None)` if the gamepad turned out not to support rumble.
+    fn start_rumble(&mut self, id: Self::Id, rumble: &Rumble) -> GameResult<Option<Self::Effect>>;
+
+    /// Stops a rumble started with [`RumbleBackend::start_rumble`].
+    fn stop_rumble(&mut self, id: Self::Id, effect: Self::Effect) -> GameResult;
+}
+
+impl RumbleBackend for Gilrs {
+    type Id = GamepadId;
+    type Effect = ff::Effect;
+
+    fn supports_rumble(&self, id: GamepadId) -> bool {
+        id.gilrs_id()
+            .and_then(|id| self.connected_gamepad(id))
+            .is_some_and(|gamepad| gamepad.is_ff_supported())
+    }
+
+    fn start_rumble(&mut self, id: GamepadId, rumble: &Rumble) -> GameResult<Option<ff::Effect>> {
+        let Some(gilrs_id) = id.gilrs_id() else {
+            return Ok(None);
+        };
+        let magnitude = |m: f32| (m.clamp(0.0, 1.0) * f32::from(u16::MAX)) as u16;
+        let scheduling = Replay {
+            play_for: Ticks::from_ms(
+                u32::try_from(rumble.duration.as_millis()).unwrap_or(u32::MAX),
+            ),
+            ..Replay::default()
+        };
+
+        let effect = EffectBuilder::new()
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Strong {
+                    magnitude: magnitude(rumble.strong),
+                },
+                scheduling,
+                ..BaseEffect::default()
+            })
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Weak {
+                    magnitude: magnitude(rumble.weak),
+                },
+                scheduling,
+                ..BaseEffect::default()
+            })
+            .gamepads(&[gilrs_id])
+            .finish(self);
+
+        match effect {
+            Ok(effect) => {
+                effect.play()?;
+                Ok(Some(effect))
+            }
+            Err(ff::Error::FfNotSupported(_) | ff::Error::Disconnected(_)) => Ok(None),
+            Err(e) => Err(e.into()),
         }
     }
+
+    fn stop_rumble(&mut self, _id: GamepadId, effect: ff::Effect) -> GameResult {
+        effect.stop().map_err(Into::into)
+    }
+}
+
+/// Keeps track of which gamepads are rumbling and stops each rumble once its duration is over.
+///
+/// [`GamepadContext`] drives one of these for you; it is public so the scheduling can be
+/// exercised with a [`TestRumbleBackend`].
+pub struct RumbleScheduler<B: RumbleBackend> {
+    active: HashMap<B::Id, (Duration, B::Effect)>,
+}
+
+impl<B: RumbleBackend> fmt::Debug for RumbleScheduler<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.active
+                    .iter()
+                    .map(|(id, (remaining, _))| (id, remaining)),
+            )
+            .finish()
+    }
+}
+
+impl<B: RumbleBackend> Default for RumbleScheduler<B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B: RumbleBackend> RumbleScheduler<B> {
+    /// Creates a scheduler with no rumbles playing.
+    pub fn new() -> Self {
+        RumbleScheduler {
+            active: HashMap::new(),
+        }
+    }
+
+    /// Starts rumbling the gamepad, replacing any rumble already playing on it.
+    ///
+    /// Returns `Ok(false)` if the gamepad doesn't support rumble.
+    pub fn rumble(&mut self, backend: &mut B, id: B::Id, rumble: Rumble) -> GameResult<bool> {
+        if !backend.supports_rumble(id) {
+            return Ok(false);
+        }
+        self.stop(backend, id)?;
+        if rumble.duration.is_zero() {
+            return Ok(true);
+        }
+        match backend.start_rumble(id, &rumble)? {
+            Some(effect) => {
+                let _ = self.active.insert(id, (rumble.duration, effect));
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Stops the rumble playing on the gamepad, if any.
+    pub fn stop(&mut self, backend: &mut B, id: B::Id) -> GameResult {
+        match self.active.remove(&id) {
+            Some((_, effect)) => backend.stop_rumble(id, effect),
+            None => Ok(()),
+        }
+    }
+
+    /// Stops all playing rumbles.
+    pub fn stop_all(&mut self, backend: &mut B) -> GameResult {
+        for (id, (_, effect)) in self.active.drain() {
+            backend.stop_rumble(id, effect)?;
+        }
+        Ok(())
+    }
+
+    /// Advances the playing rumbles by `dt`, stopping the ones that are over.
+    pub fn advance(&mut self, backend: &mut B, dt: Duration) -> GameResult {
+        let mut finished = Vec::new();
+        for (id, (remaining, _)) in &mut self.active {
+            *remaining = remaining.saturating_sub(dt);
+            if remaining.is_zero() {
+                finished.push(*id);
+            }
+        }
+        for id in finished {
+            self.stop(backend, id)?;
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if a rumble is playing on the gamepad.
+    pub fn is_rumbling(&self, id: B::Id) -> bool {
+        self.active.contains_key(&id)
+    }
+
+    /// Returns how much longer the rumble on the gamepad plays for, if any.
+    pub fn remaining(&self, id: B::Id) -> Option<Duration> {
+        self.active.get(&id).map(|(remaining, _)| *remaining)
+    }
+}
+
+/// A command received by a [`TestRumbleBackend`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RumbleCommand {
+    /// A rumble was started on a gamepad.
+    Start(usize, Rumble),
+    /// The rumble on a gamepad was stopped.
+    Stop(usize),
+}
+
+/// A [`RumbleBackend`] without any hardware, for testing rumble logic.
+///
+/// Gamepads are plain `usize`s and all of them support rumble unless marked otherwise
+/// with [`TestRumbleBackend::set_supported`].
+#[derive(Debug, Default)]
+pub struct TestRumbleBackend {
+    unsupported: HashSet<usize>,
+    commands: Vec<RumbleCommand>,
+}
+
+impl TestRumbleBackend {
+    /// Creates a backend where every gamepad supports rumble.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether the gamepad supports rumble.
+    pub fn set_supported(&mut self, id: usize, supported: bool) {
+        if supported {
+            let _ = self.unsupported.remove(&id);
+        } else {
+            let _ = self.unsupported.insert(id);
+        }
+    }
+
+    /// Returns the commands received so far, oldest first.
+    pub fn commands(&self) -> &[RumbleCommand] {
+        &self.commands
+    }
+
+    /// Returns and clears the commands received so far.
+    pub fn take_commands(&mut self) -> Vec<RumbleCommand> {
+        std::mem::take(&mut self.commands)
+    }
+}
+
+impl RumbleBackend for TestRumbleBackend {
+    type Id = usize;
+    type Effect = ();
+
+    fn supports_rumble(&self, id: usize) -> bool {
+        !self.unsupported.contains(&id)
+    }
+
+    fn start_rumble(&mut self, id: usize, rumble: &Rumble) -> GameResult<Option<()>> {
+        self.commands.push(RumbleCommand::Start(id, *rumble));
+        Ok(Some(()))
+    }
+
+    fn stop_rumble(&mut self, id: usize, _effect: ()) -> GameResult {
+        self.commands.push(RumbleCommand::Stop(id));
+        Ok(())
+    }
 }
 
 /// An iterator of the connected gamepads
 pub struct GamepadsIterator<'a> {
-    wrapped: ConnectedGamepadsIterator<'a>,
+    wrapped: Option<ConnectedGamepadsIterator<'a>>,
 }
 
 impl<'a> fmt::Debug for GamepadsIterator<'a> {
@@ -76,7 +527,10 @@ impl<'a> Iterator for GamepadsIterator<'a> {
     type Item = (GamepadId, Gamepad<'a>);
 
     fn next(&mut self) -> Option<(GamepadId, Gamepad<'a>)> {
-        self.wrapped.next().map(|(id, gp)| (GamepadId(id), gp))
+        self.wrapped
+            .as_mut()?
+            .next()
+            .map(|(id, gp)| (GamepadId::from_gilrs(id), gp))
     }
 }
 
@@ -97,7 +551,6 @@ pub fn gamepads(ctx: &Context) -> GamepadsIterator {
 // Number of axes
 // Name/ID
 // Is it connected?  (For consoles?)
-// Whether or not they support vibration
 
 /*
 /// Lists all gamepads.  With metainfo, maybe?
@@ -124,4 +577,63 @@ mod tests {
     fn gilrs_init() {
         assert!(GamepadContext::new().is_ok());
     }
+
+    #[test]
+    fn headless_test_rumble_scheduling() {
+        let mut backend = TestRumbleBackend::new();
+        let mut scheduler = RumbleScheduler::new();
+        let long = Rumble::new(1.0, 0.5, Duration::from_millis(300));
+        let short = Rumble::new(0.2, 0.0, Duration::from_millis(100));
+
+        assert!(scheduler.rumble(&mut backend, 0, long).unwrap());
+        assert!(scheduler.rumble(&mut backend, 1, short).unwrap());
+        assert_eq!(
+            backend.take_commands(),
+            [
+                RumbleCommand::Start(0, long),
+                RumbleCommand::Start(1, short)
+            ]
+        );
+
+        scheduler
+            .advance(&mut backend, Duration::from_millis(150))
+            .unwrap();
+        assert_eq!(backend.take_commands(), [RumbleCommand::Stop(1)]);
+        assert!(scheduler.is_rumbling(0));
+        assert!(!scheduler.is_rumbling(1));
+        assert_eq!(scheduler.remaining(0), Some(Duration::from_millis(150)));
+
+        // A new rumble on the same pad replaces the old one.
+        assert!(scheduler.rumble(&mut backend, 0, short).unwrap());
+        assert_eq!(
+            backend.take_commands(),
+            [RumbleCommand::Stop(0), RumbleCommand::Start(0, short)]
+        );
+        scheduler
+            .advance(&mut backend, Duration::from_millis(100))
+            .unwrap();
+        assert_eq!(backend.take_commands(), [RumbleCommand::Stop(0)]);
+        assert!(!scheduler.is_rumbling(0));
+    }
+
+    #[test]
+    fn headless_test_rumble_unsupported() {
+        let mut backend = TestRumbleBackend::new();
+        backend.set_supported(3, false);
+        let mut scheduler = RumbleScheduler::new();
+
+        let rumble = Rumble::new(2.0, -1.0, Duration::from_secs(1));
+        assert_eq!((rumble.strong, rumble.weak), (1.0, 0.0));
+        assert!(!scheduler.rumble(&mut backend, 3, rumble).unwrap());
+        assert!(!scheduler.is_rumbling(3));
+        scheduler.stop(&mut backend, 3).unwrap();
+        assert!(backend.commands().is_empty());
+
+        assert!(scheduler.rumble(&mut backend, 4, rumble).unwrap());
+        scheduler.stop_all(&mut backend).unwrap();
+        assert_eq!(
+            backend.take_commands(),
+            [RumbleCommand::Start(4, rumble), RumbleCommand::Stop(4)]
+        );
+    }
 }
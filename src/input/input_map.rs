@@ -0,0 +1,463 @@
+//! Action-based input mapping.
+//!
+//! Instead of checking for a specific key or button in game code, actions like `"jump"`
+//! and axes like `"move_x"` are bound to one or more inputs in an [`InputMap`], which
+//! is then queried by name. Since the bindings are plain data, they can be rebound at
+//! runtime and saved to TOML, either on their own or as part of the [`Conf`](crate::conf::Conf).
+//!
+//! ```rust,no_run
+//! # use ggez::{Context, GameResult};
+//! use ggez::input::keyboard::KeyCode;
+//! use ggez::input::{AxisBinding, Binding, InputMap};
+//!
+//! # fn t(ctx: &Context) -> GameResult {
+//! let mut input = InputMap::new();
+//! input.bind("jump", Binding::Key(KeyCode::Space));
+//! input.bind_axis(
+//!     "move_x",
+//!     AxisBinding::Buttons {
+//!         negative: Binding::Key(KeyCode::A),
+//!         positive: Binding::Key(KeyCode::D),
+//!     },
+//! );
+//!
+//! if input.action_just_pressed(ctx, "jump") {
+//!     // ...
+//! }
+//! let speed = 100.0 * input.axis_value(ctx, "move_x");
+//! # Ok(()) }
+//! ```
+
+#[cfg(feature = "gamepad")]
+use crate::input::gamepad::{Axis, Button, GamepadId};
+use crate::{
+    context::Context,
+    error::GameResult,
+    input::{
+        keyboard::{KeyCode, ScanCode},
+        mouse::MouseButton,
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, io};
+
+/// A single input that an action can be bound to.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "input")]
+pub enum Binding {
+    /// A key, by its meaning in the current keyboard layout.
+    Key(KeyCode),
+    /// A key, by its physical location on the keyboard.
+    ScanCode(ScanCode),
+    /// A mouse button.
+    Mouse(#[serde(with = "mouse_button_serde")] MouseButton),
+    /// A gamepad button.
+    #[cfg(feature = "gamepad")]
+    GamepadButton(Button),
+    /// One direction of a gamepad axis, which counts as pressed once it is pushed past the deadzone.
+    #[cfg(feature = "gamepad")]
+    GamepadAxis {
+        /// The axis.
+        axis: Axis,
+        /// `true` for the positive direction of the axis, `false` for the negative one.
+        positive: bool,
+        /// Values closer to the center than this are ignored, from `0.0` to `1.0`.
+        deadzone: f32,
+    },
+}
+
+/// An input that a (`-1.0` to `1.0`) axis can be bound to.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum AxisBinding {
+    /// A pair of digital inputs, such as the left and right arrow keys.
+    Buttons {
+        /// The input for `-1.0`.
+        negative: Binding,
+        /// The input for `1.0`.
+        positive: Binding,
+    },
+    /// An analog gamepad axis.
+    #[cfg(feature = "gamepad")]
+    Gamepad {
+        /// The axis.
+        axis: Axis,
+        /// Values closer to the center than this are ignored, from `0.0` to `1.0`.
+        deadzone: f32,
+        /// Whether to flip the direction of the axis.
+        inverted: bool,
+    },
+}
+
+/// Maps named actions and axes to inputs, see the [module docs](self).
+///
+/// Gamepad bindings react to any connected gamepad, unless restricted to a single one with
+/// [`InputMap::set_gamepad`].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputMap {
+    #[serde(default)]
+    actions: BTreeMap<String, Vec<Binding>>,
+    #[serde(default)]
+    axes: BTreeMap<String, Vec<AxisBinding>>,
+    #[cfg(feature = "gamepad")]
+    #[serde(skip)]
+    gamepad: Option<GamepadId>,
+}
+
+impl InputMap {
+    /// Creates an empty input map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a TOML file from the given `Read` and attempts to parse
+    /// an `InputMap` from it.
+    pub fn from_toml_file<R: io::Read>(file: &mut R) -> GameResult<InputMap> {
+        let mut s = String::new();
+        let _ = file.read_to_string(&mut s)?;
+        let decoded = toml::from_str(&s)?;
+        Ok(decoded)
+    }
+
+    /// Saves the `InputMap` to the given `Write` object,
+    /// formatted as TOML.
+    pub fn to_toml_file<W: io::Write>(&self, file: &mut W) -> GameResult {
+        let s = toml::to_vec(self)?;
+        file.write_all(&s)?;
+        Ok(())
+    }
+
+    /// Binds an input to the action. An action can have any number of bindings.
+    pub fn bind(&mut self, action: impl Into<String>, binding: Binding) {
+        let bindings = self.actions.entry(action.into()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Removes an input from the action.
+    pub fn unbind(&mut self, action: &str, binding: Binding) {
+        if let Some(bindings) = self.actions.get_mut(action) {
+            bindings.retain(|b| *b != binding);
+        }
+    }
+
+    /// Removes all inputs from the action.
+    pub fn clear_action(&mut self, action: &str) {
+        let _ = self.actions.remove(action);
+    }
+
+    /// Returns the inputs bound to the action.
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.actions.get(action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the names of all actions with bindings.
+    pub fn actions(&self) -> impl Iterator<Item = &str> {
+        self.actions.keys().map(String::as_str)
+    }
+
+    /// Binds an input to the axis. An axis can have any number of bindings.
+    pub fn bind_axis(&mut self, axis: impl Into<String>, binding: AxisBinding) {
+        let bindings = self.axes.entry(axis.into()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Removes an input from the axis.
+    pub fn unbind_axis(&mut self, axis: &str, binding: AxisBinding) {
+        if let Some(bindings) = self.axes.get_mut(axis) {
+            bindings.retain(|b| *b != binding);
+        }
+    }
+
+    /// Removes all inputs from the axis.
+    pub fn clear_axis(&mut self, axis: &str) {
+        let _ = self.axes.remove(axis);
+    }
+
+    /// Returns the inputs bound to the axis.
+    pub fn axis_bindings(&self, axis: &str) -> &[AxisBinding] {
+        self.axes.get(axis).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the names of all axes with bindings.
+    pub fn axes(&self) -> impl Iterator<Item = &str> {
+        self.axes.keys().map(String::as_str)
+    }
+
+    /// Restricts gamepad bindings to a single gamepad, e.g. for local multiplayer.
+    /// `None` (the default) reacts to any gamepad.
+    #[cfg(feature = "gamepad")]
+    pub fn set_gamepad(&mut self, gamepad: Option<GamepadId>) {
+        self.gamepad = gamepad;
+    }
+
+    /// Returns the gamepad the bindings are restricted to, if any.
+    #[cfg(feature = "gamepad")]
+    pub fn gamepad(&self) -> Option<GamepadId> {
+        self.gamepad
+    }
+
+    /// Checks if any input bound to the action is currently pressed down.
+    pub fn action_pressed(&self, ctx: &Context, action: &str) -> bool {
+        self.action_state(ctx, action).1
+    }
+
+    /// Checks if the action has been pressed down this frame.
+    pub fn action_just_pressed(&self, ctx: &Context, action: &str) -> bool {
+        let (previous, current) = self.action_state(ctx, action);
+        current && !previous
+    }
+
+    /// Checks if the action has been released this frame.
+    pub fn action_just_released(&self, ctx: &Context, action: &str) -> bool {
+        let (previous, current) = self.action_state(ctx, action);
+        !current && previous
+    }
+
+    /// Returns how strongly the action is pressed, from `0.0` to `1.0`.
+    ///
+    /// Digital inputs are either `0.0` or `1.0`; gamepad axes give the values in between.
+    pub fn action_value(&self, ctx: &Context, action: &str) -> f32 {
+        self.bindings(action)
+            .iter()
+            .map(|binding| self.binding_value(ctx, binding).1)
+            .fold(0.0, f32::max)
+    }
+
+    /// Returns the value of the axis, from `-1.0` to `1.0`.
+    ///
+    /// If several of its inputs are active, the one pushed the furthest wins.
+    pub fn axis_value(&self, ctx: &Context, axis: &str) -> f32 {
+        self.axis_bindings(axis)
+            .iter()
+            .map(|binding| self.axis_binding_value(ctx, binding))
+            .fold(0.0f32, |acc, v| if v.abs() > acc.abs() { v } else { acc })
+            .clamp(-1.0, 1.0)
+    }
+
+    /// Returns whether the action was pressed last frame and whether it is pressed now.
+    fn action_state(&self, ctx: &Context, action: &str) -> (bool, bool) {
+        self.bindings(action)
+            .iter()
+            .map(|binding| self.binding_value(ctx, binding))
+            .fold((false, false), |(previous, current), (p, c)| {
+                (previous || p > 0.0, current || c > 0.0)
+            })
+    }
+
+    /// Returns the value of the binding last frame and now.
+    fn binding_value(&self, ctx: &Context, binding: &Binding) -> (f32, f32) {
+        let digital = |previous: bool, current: bool| {
+            (f32::from(u8::from(previous)), f32::from(u8::from(current)))
+        };
+        match *binding {
+            Binding::Key(key) => {
+                let current = ctx.keyboard.is_key_pressed(key);
+                let previous = if current {
+                    !ctx.keyboard.is_key_just_pressed(key)
+                } else {
+                    ctx.keyboard.is_key_just_released(key)
+                };
+                digital(previous, current)
+            }
+            Binding::ScanCode(code) => {
+                let current = ctx.keyboard.is_scancode_pressed(code);
+                let previous = if current {
+                    !ctx.keyboard.is_scancode_just_pressed(code)
+                } else {
+                    ctx.keyboard.is_scancode_just_released(code)
+                };
+                digital(previous, current)
+            }
+            Binding::Mouse(button) => {
+                let current = ctx.mouse.button_pressed(button);
+                let previous = if current {
+                    !ctx.mouse.button_just_pressed(button)
+                } else {
+                    ctx.mouse.button_just_released(button)
+                };
+                digital(previous, current)
+            }
+            #[cfg(feature = "gamepad")]
+            Binding::GamepadButton(button) => {
+                let (previous, current) = ctx.gamepad.button_state(self.gamepad, button);
+                digital(previous, current)
+            }
+            #[cfg(feature = "gamepad")]
+            Binding::GamepadAxis {
+                axis,
+                positive,
+                deadzone,
+            } => {
+                let (previous, current) = ctx.gamepad.axis_state(self.gamepad, axis);
+                let directed =
+                    |v: f32| apply_deadzone(if positive { v } else { -v }, deadzone).max(0.0);
+                (directed(previous), directed(current))
+            }
+        }
+    }
+
+    fn axis_binding_value(&self, ctx: &Context, binding: &AxisBinding) -> f32 {
+        match *binding {
+            AxisBinding::Buttons { negative, positive } => {
+                self.binding_value(ctx, &positive).1 - self.binding_value(ctx, &negative).1
+            }
+            #[cfg(feature = "gamepad")]
+            AxisBinding::Gamepad {
+                axis,
+                deadzone,
+                inverted,
+            } => {
+                let value = apply_deadzone(ctx.gamepad.axis_state(self.gamepad, axis).1, deadzone);
+                if inverted {
+                    -value
+                } else {
+                    value
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "gamepad")]
+/// Zeroes values within the deadzone and rescales the rest back to the full `-1.0` to `1.0` range.
+fn apply_deadzone(value: f32, deadzone: f32) -> f32 {
+    let deadzone = deadzone.clamp(0.0, 0.99);
+    if value.abs() <= deadzone {
+        0.0
+    } else {
+        value.signum() * ((value.abs() - deadzone) / (1.0 - deadzone)).min(1.0)
+    }
+}
+
+/// TOML can't hold `MouseButton::Other(n)` as is, so mouse buttons are stored as
+/// `"Left"`, `"Right"`, `"Middle"` or the plain button number.
+mod mouse_button_serde {
+    use super::MouseButton;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Named(String),
+        Other(u16),
+    }
+
+    pub fn serialize<S: Serializer>(
+        button: &MouseButton,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match *button {
+            MouseButton::Left => Repr::Named(String::from("Left")),
+            MouseButton::Right => Repr::Named(String::from("Right")),
+            MouseButton::Middle => Repr::Named(String::from("Middle")),
+            MouseButton::Other(n) => Repr::Other(n),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<MouseButton, D::Error> {
+        match Repr::deserialize(deserializer)? {
+            Repr::Named(name) => match name.as_str() {
+                "Left" => Ok(MouseButton::Left),
+                "Right" => Ok(MouseButton::Right),
+                "Middle" => Ok(MouseButton::Middle),
+                _ => Err(serde::de::Error::unknown_variant(
+                    &name,
+                    &["Left", "Right", "Middle"],
+                )),
+            },
+            Repr::Other(n) => Ok(MouseButton::Other(n)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::headless_ctx_for_tests;
+
+    #[test]
+    fn headless_test_actions() {
+        let mut ctx = headless_ctx_for_tests(8, 8);
+        let mut input = InputMap::new();
+        input.bind("jump", Binding::Key(KeyCode::Space));
+        input.bind("jump", Binding::Mouse(MouseButton::Left));
+        input.bind_axis(
+            "move_x",
+            AxisBinding::Buttons {
+                negative: Binding::Key(KeyCode::A),
+                positive: Binding::Key(KeyCode::D),
+            },
+        );
+
+        ctx.keyboard.set_key(KeyCode::Space, true);
+        ctx.keyboard.set_key(KeyCode::A, true);
+        assert!(input.action_pressed(&ctx, "jump"));
+        assert!(input.action_just_pressed(&ctx, "jump"));
+        assert_eq!(input.action_value(&ctx, "jump"), 1.0);
+        assert_eq!(input.axis_value(&ctx, "move_x"), -1.0);
+        ctx.keyboard.save_keyboard_state();
+
+        // Holding the mouse button as well doesn't make the action pressed again.
+        ctx.mouse.set_button(MouseButton::Left, true);
+        assert!(input.action_pressed(&ctx, "jump"));
+        assert!(!input.action_just_pressed(&ctx, "jump"));
+        ctx.mouse.save_mouse_state();
+
+        ctx.keyboard.set_key(KeyCode::Space, false);
+        ctx.mouse.set_button(MouseButton::Left, false);
+        assert!(input.action_just_released(&ctx, "jump"));
+
+        input.unbind("jump", Binding::Key(KeyCode::Space));
+        assert_eq!(input.bindings("jump"), [Binding::Mouse(MouseButton::Left)]);
+        assert!(!input.action_pressed(&ctx, "unbound"));
+        assert_eq!(input.axis_value(&ctx, "unbound"), 0.0);
+    }
+
+    #[test]
+    fn headless_test_toml_round_trip() {
+        let mut input = InputMap::new();
+        input.bind("jump", Binding::Key(KeyCode::Space));
+        input.bind("jump", Binding::ScanCode(57));
+        input.bind("fire", Binding::Mouse(MouseButton::Other(4)));
+        input.bind_axis(
+            "move_x",
+            AxisBinding::Buttons {
+                negative: Binding::Key(KeyCode::Left),
+                positive: Binding::Key(KeyCode::Right),
+            },
+        );
+        #[cfg(feature = "gamepad")]
+        {
+            input.bind("jump", Binding::GamepadButton(Button::South));
+            input.bind_axis(
+                "move_x",
+                AxisBinding::Gamepad {
+                    axis: Axis::LeftStickX,
+                    deadzone: 0.25,
+                    inverted: false,
+                },
+            );
+        }
+
+        let mut writer = Vec::new();
+        input.to_toml_file(&mut writer).unwrap();
+        let mut reader = writer.as_slice();
+        assert_eq!(InputMap::from_toml_file(&mut reader).unwrap(), input);
+    }
+
+    #[cfg(feature = "gamepad")]
+    #[test]
+    fn headless_test_deadzone() {
+        assert_eq!(apply_deadzone(0.1, 0.5), 0.0);
+        assert_eq!(apply_deadzone(-0.5, 0.5), 0.0);
+        assert_eq!(apply_deadzone(0.75, 0.5), 0.5);
+        assert_eq!(apply_deadzone(-1.0, 0.5), -1.0);
+    }
+}
@@ -1,4 +1,8 @@
 //! Input handling modules for keyboard, mouse and gamepad.
 pub mod gamepad;
+pub mod input_map;
 pub mod keyboard;
 pub mod mouse;
+pub mod recording;
+
+pub use self::input_map::{AxisBinding, Binding, InputMap};
@@ -214,14 +214,18 @@ pub fn handle_move(ctx: &mut Context, new_x: f32, new_y: f32) {
 // TODO: Move to graphics context (This isn't input)
 pub fn set_cursor_hidden(ctx: &mut Context, hidden: bool) {
     ctx.mouse.cursor_hidden = hidden;
-    ctx.gfx.window.set_cursor_visible(!hidden);
+    if let Some(window) = &ctx.gfx.window {
+        window.set_cursor_visible(!hidden);
+    }
 }
 
 /// Modifies the mouse cursor type of the window.
 // TODO: Move to graphics context (This isn't input)
 pub fn set_cursor_type(ctx: &mut Context, cursor_type: CursorIcon) {
     ctx.mouse.cursor_type = cursor_type;
-    ctx.gfx.window.set_cursor_icon(cursor_type);
+    if let Some(window) = &ctx.gfx.window {
+        window.set_cursor_icon(cursor_type);
+    }
 }
 
 /// Get whether or not the mouse is grabbed.
@@ -237,8 +241,10 @@ pub fn cursor_grabbed(ctx: &Context) -> bool {
 #[allow(clippy::missing_errors_doc)]
 pub fn set_cursor_grabbed(ctx: &mut Context, grabbed: bool) -> GameResult {
     ctx.mouse.cursor_grabbed = grabbed;
-    ctx.gfx
-        .window
+    let Some(window) = &ctx.gfx.window else {
+        return Ok(());
+    };
+    window
         .set_cursor_grab(if grabbed {
             if cfg!(target_os = "macos") {
                 CursorGrabMode::Locked
@@ -263,8 +269,10 @@ where
 {
     let point = glam::Vec2::from(point.into());
     ctx.mouse.last_position = point;
-    ctx.gfx
-        .window
+    let Some(window) = &ctx.gfx.window else {
+        return Ok(());
+    };
+    window
         .set_cursor_position(dpi::LogicalPosition {
             x: f64::from(point.x),
             y: f64::from(point.y),
@@ -0,0 +1,645 @@
+//! Recording and replaying input, for reproducing a play session frame-for-frame.
+//!
+//! While recording, [`event::run()`](crate::event::run) stores every keyboard, mouse and
+//! gamepad event it receives, as well as window resizes and close requests, grouped by
+//! frame together with the frame's length and [`TimeContext::time_since_start`].
+//! Replaying feeds those events back through
+//! [`event::process_event()`](crate::event::process_event) and the
+//! [`EventHandler`](crate::event::EventHandler) callbacks instead of the live input,
+//! and ticks the [`TimeContext`] with the recorded timings, so a game that only
+//! depends on its input and `ctx.time` behaves exactly as it did when recorded.
+//!
+//! Replayed resizes only reach [`EventHandler::resize_event`](crate::event::EventHandler::resize_event):
+//! the frame keeps following the size of the real window. Closing the window stops the replay.
+//!
+//! Note that the game has to start out in the same state (e.g. the same random seed)
+//! for the replay to match.
+//!
+//! ```rust,no_run
+//! # use ggez::{Context, GameResult};
+//! # fn t(ctx: &mut Context) -> GameResult {
+//! // When the session starts:
+//! ctx.recorder.start_recording();
+//!
+//! // ... and when it's over (or when the player files a bug report):
+//! if let Some(recording) = ctx.recorder.stop_recording() {
+//!     recording.save(&ctx.fs, "/session.replay")?;
+//! }
+//!
+//! // Later, to watch it again:
+//! let recording = ggez::input::recording::InputRecording::load(&ctx.fs, "/session.replay")?;
+//! ctx.recorder.start_replay(recording);
+//! # Ok(()) }
+//! ```
+
+#[cfg(feature = "gamepad")]
+use crate::input::gamepad::{Axis, Button, GamepadId};
+use crate::{
+    error::{GameError, GameResult},
+    filesystem::Filesystem,
+    input::keyboard::{KeyCode, ScanCode},
+    timer::TimeContext,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, io::Write, path::Path, time::Duration};
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{
+        DeviceId, ElementState, KeyboardInput, ModifiersState, MouseButton, MouseScrollDelta,
+        Touch, TouchPhase, WindowEvent,
+    },
+};
+
+/// The version of the recording file format, bumped whenever it changes.
+const RECORDING_VERSION: u32 = 1;
+
+/// A single input event, as fed to ggez by the event loop.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RecordedEvent {
+    /// A key was pressed or released.
+    Key {
+        /// The scan code of the key.
+        scancode: ScanCode,
+        /// The key code of the key, if there is one.
+        keycode: Option<KeyCode>,
+        /// `true` if the key was pressed, `false` if it was released.
+        pressed: bool,
+    },
+    /// The active keyboard modifiers changed.
+    Modifiers(ModifiersState),
+    /// A character was typed.
+    Text(char),
+    /// The mouse moved to the given position, in physical pixels inside the window.
+    MouseMove {
+        /// Horizontal position.
+        x: f64,
+        /// Vertical position.
+        y: f64,
+    },
+    /// A mouse button was pressed or released.
+    MouseButton {
+        /// The button.
+        button: MouseButton,
+        /// `true` if the button was pressed, `false` if it was released.
+        pressed: bool,
+    },
+    /// The mouse wheel was scrolled.
+    MouseWheel(MouseScrollDelta),
+    /// The mouse entered (`true`) or left (`false`) the window.
+    MouseEnter(bool),
+    /// The window gained (`true`) or lost (`false`) focus.
+    Focus(bool),
+    /// A touch event.
+    Touch {
+        /// Which finger this is.
+        id: u64,
+        /// What happened to the touch.
+        phase: TouchPhase,
+        /// Horizontal position, in physical pixels.
+        x: f64,
+        /// Vertical position, in physical pixels.
+        y: f64,
+    },
+    /// The window was resized.
+    Resized {
+        /// The new width, in physical pixels.
+        width: u32,
+        /// The new height, in physical pixels.
+        height: u32,
+    },
+    /// The window was asked to close.
+    CloseRequested,
+    /// A gamepad button was pressed or released.
+    #[cfg(feature = "gamepad")]
+    GamepadButton {
+        /// The gamepad.
+        id: GamepadId,
+        /// The button.
+        button: Button,
+        /// `true` if the button was pressed, `false` if it was released.
+        pressed: bool,
+    },
+    /// A gamepad axis moved.
+    #[cfg(feature = "gamepad")]
+    GamepadAxis {
+        /// The gamepad.
+        id: GamepadId,
+        /// The axis.
+        axis: Axis,
+        /// The new value of the axis.
+        value: f32,
+    },
+}
+
+impl RecordedEvent {
+    /// Returns the recordable input in a window event, if there is any.
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        Some(match *event {
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        scancode,
+                        state,
+                        virtual_keycode,
+                        ..
+                    },
+                ..
+            } => RecordedEvent::Key {
+                scancode,
+                keycode: virtual_keycode,
+                pressed: state == ElementState::Pressed,
+            },
+            WindowEvent::ModifiersChanged(mods) => RecordedEvent::Modifiers(mods),
+            WindowEvent::ReceivedCharacter(ch) => RecordedEvent::Text(ch),
+            WindowEvent::CursorMoved { position, .. } => RecordedEvent::MouseMove {
+                x: position.x,
+                y: position.y,
+            },
+            WindowEvent::MouseInput { state, button, .. } => RecordedEvent::MouseButton {
+                button,
+                pressed: state == ElementState::Pressed,
+            },
+            WindowEvent::MouseWheel { delta, .. } => RecordedEvent::MouseWheel(delta),
+            WindowEvent::CursorEntered { .. } => RecordedEvent::MouseEnter(true),
+            WindowEvent::CursorLeft { .. } => RecordedEvent::MouseEnter(false),
+            WindowEvent::Focused(gained) => RecordedEvent::Focus(gained),
+            WindowEvent::Touch(Touch {
+                id,
+                phase,
+                location,
+                ..
+            }) => RecordedEvent::Touch {
+                id,
+                phase,
+                x: location.x,
+                y: location.y,
+            },
+            WindowEvent::Resized(size) => RecordedEvent::Resized {
+                width: size.width,
+                height: size.height,
+            },
+            WindowEvent::CloseRequested => RecordedEvent::CloseRequested,
+            _ => return None,
+        })
+    }
+
+    /// Returns the recordable input in a gamepad event, if there is any.
+    #[cfg(feature = "gamepad")]
+    pub fn from_gamepad_event(id: GamepadId, event: &gilrs::EventType) -> Option<Self> {
+        Some(match *event {
+            gilrs::EventType::ButtonPressed(button, _) => RecordedEvent::GamepadButton {
+                id,
+                button,
+                pressed: true,
+            },
+            gilrs::EventType::ButtonReleased(button, _) => RecordedEvent::GamepadButton {
+                id,
+                button,
+                pressed: false,
+            },
+            gilrs::EventType::AxisChanged(axis, value, _) => {
+                RecordedEvent::GamepadAxis { id, axis, value }
+            }
+            _ => return None,
+        })
+    }
+
+    /// Turns the event back into the window event it was recorded from,
+    /// or `None` for gamepad events.
+    #[allow(deprecated)] // The `modifiers` fields, which have to be filled in regardless.
+    pub(crate) fn to_window_event(self) -> Option<WindowEvent<'static>> {
+        // SAFETY: The device id is only passed on to `process_event` and the `EventHandler`,
+        // never back to winit.
+        #[allow(unsafe_code)]
+        let device_id = unsafe { DeviceId::dummy() };
+        let modifiers = ModifiersState::empty();
+        let element_state = |pressed| {
+            if pressed {
+                ElementState::Pressed
+            } else {
+                ElementState::Released
+            }
+        };
+
+        Some(match self {
+            RecordedEvent::Key {
+                scancode,
+                keycode,
+                pressed,
+            } => WindowEvent::KeyboardInput {
+                device_id,
+                input: KeyboardInput {
+                    scancode,
+                    state: element_state(pressed),
+                    virtual_keycode: keycode,
+                    modifiers,
+                },
+                is_synthetic: false,
+            },
+            RecordedEvent::Modifiers(mods) => WindowEvent::ModifiersChanged(mods),
+            RecordedEvent::Text(ch) => WindowEvent::ReceivedCharacter(ch),
+            RecordedEvent::MouseMove { x, y } => WindowEvent::CursorMoved {
+                device_id,
+                position: PhysicalPosition::new(x, y),
+                modifiers,
+            },
+            RecordedEvent::MouseButton { button, pressed } => WindowEvent::MouseInput {
+                device_id,
+                state: element_state(pressed),
+                button,
+                modifiers,
+            },
+            RecordedEvent::MouseWheel(delta) => WindowEvent::MouseWheel {
+                device_id,
+                delta,
+                phase: TouchPhase::Moved,
+                modifiers,
+            },
+            RecordedEvent::MouseEnter(true) => WindowEvent::CursorEntered { device_id },
+            RecordedEvent::MouseEnter(false) => WindowEvent::CursorLeft { device_id },
+            RecordedEvent::Focus(gained) => WindowEvent::Focused(gained),
+            RecordedEvent::Touch { id, phase, x, y } => WindowEvent::Touch(Touch {
+                device_id,
+                phase,
+                location: PhysicalPosition::new(x, y),
+                force: None,
+                id,
+            }),
+            RecordedEvent::Resized { width, height } => {
+                WindowEvent::Resized(PhysicalSize::new(width, height))
+            }
+            RecordedEvent::CloseRequested => WindowEvent::CloseRequested,
+            #[cfg(feature = "gamepad")]
+            RecordedEvent::GamepadButton { .. } | RecordedEvent::GamepadAxis { .. } => return None,
+        })
+    }
+}
+
+/// The input of a single frame.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    /// The real length of the frame, see [`TimeContext::real_delta`].
+    pub delta: Duration,
+    /// The time since the game started, see [`TimeContext::time_since_start`].
+    pub time: Duration,
+    /// The input events of the frame, in the order they were received.
+    pub events: Vec<RecordedEvent>,
+}
+
+/// A recorded play session, see the [module docs](self).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InputRecording {
+    version: u32,
+    frames: Vec<RecordedFrame>,
+}
+
+impl Default for InputRecording {
+    fn default() -> Self {
+        InputRecording {
+            version: RECORDING_VERSION,
+            frames: Vec::new(),
+        }
+    }
+}
+
+impl InputRecording {
+    /// Creates a recording out of the given frames.
+    pub fn new(frames: Vec<RecordedFrame>) -> Self {
+        InputRecording {
+            frames,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the recorded frames.
+    pub fn frames(&self) -> &[RecordedFrame] {
+        &self.frames
+    }
+
+    /// Returns the total length of the recording.
+    pub fn duration(&self) -> Duration {
+        self.frames.iter().map(|frame| frame.delta).sum()
+    }
+
+    /// Saves the recording to the given path in the user directory, as gzipped JSON.
+    pub fn save(&self, fs: &Filesystem, path: impl AsRef<Path>) -> GameResult {
+        let file = fs.create(path)?;
+        let mut encoder =
+            flate2::write::GzEncoder::new(std::io::BufWriter::new(file), Default::default());
+        serde_json::to_writer(&mut encoder, self)
+            .map_err(|e| GameError::CustomError(format!("failed to write recording: {e}")))?;
+        encoder.finish()?.flush()?;
+        Ok(())
+    }
+
+    /// Loads a recording saved with [`InputRecording::save`].
+    pub fn load(fs: &Filesystem, path: impl AsRef<Path>) -> GameResult<Self> {
+        let file = fs.open(path)?;
+        let decoder = flate2::read::GzDecoder::new(file);
+        let recording: InputRecording = serde_json::from_reader(decoder)
+            .map_err(|e| GameError::ResourceLoadError(format!("failed to read recording: {e}")))?;
+        if recording.version != RECORDING_VERSION {
+            return Err(GameError::ResourceLoadError(format!(
+                "unsupported recording version {}, expected {RECORDING_VERSION}",
+                recording.version
+            )));
+        }
+        Ok(recording)
+    }
+}
+
+#[derive(Debug)]
+enum Mode {
+    Idle,
+    Recording {
+        recording: InputRecording,
+        events: Vec<RecordedEvent>,
+    },
+    Replaying(VecDeque<RecordedFrame>),
+}
+
+/// Records and replays input, see the [module docs](self).
+#[derive(Debug)]
+pub struct InputRecorder {
+    mode: Mode,
+}
+
+impl Default for InputRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputRecorder {
+    pub(crate) fn new() -> Self {
+        InputRecorder { mode: Mode::Idle }
+    }
+
+    /// Starts recording, stopping any recording or replay in progress.
+    pub fn start_recording(&mut self) {
+        self.mode = Mode::Recording {
+            recording: InputRecording::default(),
+            events: Vec::new(),
+        };
+    }
+
+    /// Stops recording and returns what was recorded, or `None` if nothing was being recorded.
+    ///
+    /// Events received since the last complete frame are discarded.
+    pub fn stop_recording(&mut self) -> Option<InputRecording> {
+        match std::mem::replace(&mut self.mode, Mode::Idle) {
+            Mode::Recording { recording, .. } => Some(recording),
+            mode => {
+                self.mode = mode;
+                None
+            }
+        }
+    }
+
+    /// Returns `true` if input is being recorded.
+    pub fn is_recording(&self) -> bool {
+        matches!(self.mode, Mode::Recording { .. })
+    }
+
+    /// Starts replaying the recording, stopping any recording or replay in progress.
+    ///
+    /// Live input is ignored until the replay is over.
+    pub fn start_replay(&mut self, recording: InputRecording) {
+        self.mode = Mode::Replaying(recording.frames.into());
+    }
+
+    /// Stops the replay in progress, if any, and goes back to live input.
+    pub fn stop_replay(&mut self) {
+        if self.is_replaying() {
+            self.mode = Mode::Idle;
+        }
+    }
+
+    /// Returns `true` if a recording is being replayed.
+    pub fn is_replaying(&self) -> bool {
+        matches!(self.mode, Mode::Replaying(_))
+    }
+
+    /// Returns how many frames of the replay are left.
+    pub fn remaining_frames(&self) -> usize {
+        match &self.mode {
+            Mode::Replaying(frames) => frames.len(),
+            _ => 0,
+        }
+    }
+
+    /// Records an input event in the current frame, if recording.
+    ///
+    /// [`event::run()`](crate::event::run) calls this for you; you only need to call it
+    /// if you're writing your own event loop.
+    pub fn record(&mut self, event: RecordedEvent) {
+        if let Mode::Recording { events, .. } = &mut self.mode {
+            events.push(event);
+        }
+    }
+
+    /// Finishes recording the current frame, if recording. Call this after
+    /// [`TimeContext::tick`] and after recording all the events of the frame.
+    ///
+    /// [`event::run()`](crate::event::run) calls this for you; you only need to call it
+    /// if you're writing your own event loop.
+    pub fn end_frame(&mut self, time: &TimeContext) {
+        if let Mode::Recording { recording, events } = &mut self.mode {
+            recording.frames.push(RecordedFrame {
+                delta: time.real_delta(),
+                time: time.time_since_start(),
+                events: std::mem::take(events),
+            });
+        }
+    }
+
+    /// Takes the next frame to replay, if replaying. Once there are no frames left,
+    /// the replay stops and `None` is returned.
+    ///
+    /// [`event::run()`](crate::event::run) calls this for you; you only need to call it
+    /// if you're writing your own event loop.
+    pub fn next_frame(&mut self) -> Option<RecordedFrame> {
+        let Mode::Replaying(frames) = &mut self.mode else {
+            return None;
+        };
+        let frame = frames.pop_front();
+        if frame.is_none() {
+            self.mode = Mode::Idle;
+        }
+        frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        context::headless_ctx_for_tests,
+        event::{self, ControlFlow, EventHandler},
+        Context,
+    };
+
+    fn frame(delta_ms: u64, events: Vec<RecordedEvent>) -> RecordedFrame {
+        RecordedFrame {
+            delta: Duration::from_millis(delta_ms),
+            time: Duration::ZERO,
+            events,
+        }
+    }
+
+    #[test]
+    fn headless_test_record_and_replay() {
+        let mut recorder = InputRecorder::new();
+        let time = TimeContext::new();
+        let space = RecordedEvent::Key {
+            scancode: 57,
+            keycode: Some(KeyCode::Space),
+            pressed: true,
+        };
+
+        // Events are only kept while recording.
+        recorder.record(space);
+        recorder.start_recording();
+        recorder.record(space);
+        recorder.end_frame(&time);
+        recorder.end_frame(&time);
+        recorder.record(RecordedEvent::Focus(false));
+        let recording = recorder.stop_recording().unwrap();
+        assert!(!recorder.is_recording());
+        assert_eq!(recording.frames().len(), 2);
+        assert_eq!(recording.frames()[0].events, [space]);
+        assert!(recording.frames()[1].events.is_empty());
+
+        recorder.start_replay(recording.clone());
+        assert!(recorder.is_replaying());
+        assert_eq!(recorder.remaining_frames(), 2);
+        assert_eq!(recorder.next_frame().as_ref(), recording.frames().first());
+        assert_eq!(recorder.next_frame().as_ref(), recording.frames().get(1));
+        assert_eq!(recorder.next_frame(), None);
+        assert!(!recorder.is_replaying());
+    }
+
+    #[test]
+    fn headless_test_save_load() {
+        let ctx = headless_ctx_for_tests(8, 8);
+        let recording = InputRecording::new(vec![
+            frame(16, vec![RecordedEvent::MouseMove { x: 1.5, y: 2.0 }]),
+            frame(
+                17,
+                vec![
+                    RecordedEvent::MouseButton {
+                        button: MouseButton::Other(4),
+                        pressed: true,
+                    },
+                    RecordedEvent::Text('x'),
+                ],
+            ),
+        ]);
+        assert_eq!(recording.duration(), Duration::from_millis(33));
+
+        let path = "/headless_test_save_load.replay";
+        recording.save(&ctx.fs, path).unwrap();
+        let loaded = InputRecording::load(&ctx.fs, path);
+        ctx.fs.delete(path).unwrap();
+        assert_eq!(loaded.unwrap(), recording);
+    }
+
+    #[derive(Default)]
+    struct Handler {
+        keys_down: usize,
+        mouse_downs: Vec<(f32, f32)>,
+        resizes: Vec<(f32, f32)>,
+        quit_requests: usize,
+    }
+
+    impl EventHandler for Handler {
+        fn update(&mut self, _ctx: &mut Context) -> GameResult {
+            Ok(())
+        }
+
+        fn draw(&mut self, _ctx: &mut Context) -> GameResult {
+            Ok(())
+        }
+
+        fn key_down_event(
+            &mut self,
+            _ctx: &mut Context,
+            _input: crate::input::keyboard::KeyInput,
+            _repeated: bool,
+        ) -> GameResult {
+            self.keys_down += 1;
+            Ok(())
+        }
+
+        fn mouse_button_down_event(
+            &mut self,
+            _ctx: &mut Context,
+            _button: MouseButton,
+            x: f32,
+            y: f32,
+        ) -> GameResult {
+            self.mouse_downs.push((x, y));
+            Ok(())
+        }
+
+        fn resize_event(&mut self, _ctx: &mut Context, width: f32, height: f32) -> GameResult {
+            self.resizes.push((width, height));
+            Ok(())
+        }
+
+        fn quit_event(&mut self, _ctx: &mut Context) -> GameResult<bool> {
+            self.quit_requests += 1;
+            Ok(true)
+        }
+    }
+
+    #[test]
+    fn headless_test_replay_injects_events() {
+        let mut ctx = headless_ctx_for_tests(8, 8);
+        let mut handler = Handler::default();
+        let replayed = frame(
+            16,
+            vec![
+                RecordedEvent::Key {
+                    scancode: 57,
+                    keycode: Some(KeyCode::Space),
+                    pressed: true,
+                },
+                RecordedEvent::MouseMove { x: 3.0, y: 4.0 },
+                RecordedEvent::MouseButton {
+                    button: MouseButton::Left,
+                    pressed: true,
+                },
+                RecordedEvent::Resized {
+                    width: 32,
+                    height: 16,
+                },
+                RecordedEvent::CloseRequested,
+            ],
+        );
+        for input in &replayed.events {
+            let window_event = input.to_window_event().unwrap();
+            assert_eq!(
+                RecordedEvent::from_window_event(&window_event),
+                Some(*input)
+            );
+        }
+
+        let mut control_flow = ControlFlow::Poll;
+        assert!(!event::replay_window_events(
+            &mut ctx,
+            &mut handler,
+            &mut control_flow,
+            &replayed
+        ));
+        assert!(ctx.keyboard.is_key_pressed(KeyCode::Space));
+        assert!(ctx.keyboard.is_scancode_pressed(57));
+        assert!(ctx.mouse.button_pressed(MouseButton::Left));
+        assert_eq!(handler.keys_down, 1);
+        assert_eq!(handler.mouse_downs, [(3.0, 4.0)]);
+        assert_eq!(handler.resizes, [(32.0, 16.0)]);
+        assert_eq!(handler.quit_requests, 1);
+        // Only the handler sees the recorded size.
+        assert_eq!(ctx.gfx.drawable_size(), (8.0, 8.0));
+    }
+}
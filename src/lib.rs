@@ -202,6 +202,7 @@ pub mod event;
 pub mod filesystem;
 pub mod graphics;
 pub mod input;
+pub mod testing;
 pub mod timer;
 mod vfs;
 
@@ -0,0 +1,531 @@
+//! Driving an [`EventHandler`] from tests, without a window or a real event loop.
+//!
+//! A [`TestHarness`] owns a headless [`Context`] (see
+//! [`ContextBuilder::headless`](crate::ContextBuilder::headless)) and the game state.
+//! Tests push input events into it, which go through
+//! [`event::process_event()`](crate::event::process_event) and the `EventHandler`
+//! callbacks just like they would in [`event::run()`](crate::event::run), and then
+//! [`step`](TestHarness::step) the game a number of frames. The
+//! [`TimeContext`](crate::timer::TimeContext) runs on a [`ManualClock`] that moves
+//! forward by a fixed, configurable amount each frame, so `ctx.time.delta()`,
+//! `time_since_start`, `check_update_time` and fixed timesteps behave the same on every run.
+//!
+//! ```rust
+//! # use ggez::{event::EventHandler, graphics, input::keyboard::KeyCode, Context, ContextBuilder, GameResult};
+//! # use ggez::testing::TestHarness;
+//! struct Game {
+//!     x: f32,
+//! }
+//!
+//! impl EventHandler for Game {
+//!     fn update(&mut self, ctx: &mut Context) -> GameResult {
+//!         if ctx.keyboard.is_key_pressed(KeyCode::Right) {
+//!             self.x += 100.0 * ctx.time.delta().as_secs_f32();
+//!         }
+//!         Ok(())
+//!     }
+//!
+//!     fn draw(&mut self, ctx: &mut Context) -> GameResult {
+//!         graphics::Canvas::from_frame(ctx, graphics::Color::BLACK).finish(ctx)
+//!     }
+//! }
+//!
+//! # fn main() -> GameResult {
+//! let ctx = ContextBuilder::new("my_game", "me").headless(64, 64).build_headless()?;
+//! let mut harness = TestHarness::new(ctx, Game { x: 0.0 });
+//! harness.key_down(KeyCode::Right).step(60);
+//! assert!((harness.state.x - 100.0).abs() < 0.01);
+//! # Ok(()) }
+//! ```
+
+#[cfg(feature = "gamepad")]
+use crate::input::gamepad::{Axis, Button, GamepadId};
+use crate::{
+    event::{self, ControlFlow, EventHandler},
+    input::{
+        keyboard::{KeyCode, KeyMods, ScanCode},
+        recording::RecordedEvent,
+    },
+    timer::ManualClock,
+    Context,
+};
+use std::{marker::PhantomData, time::Duration};
+use winit::{
+    dpi::PhysicalSize,
+    event::{ModifiersState, MouseButton, MouseScrollDelta, WindowEvent},
+};
+
+/// Runs an [`EventHandler`] frame by frame on a fake clock, feeding it synthetic input.
+///
+/// Once the handler stops the game, by an error that [`EventHandler::on_error`] doesn't
+/// recover from or by quitting, events and frames are ignored and
+/// [`is_running`](Self::is_running) returns `false`.
+pub struct TestHarness<S, E = crate::GameError> {
+    /// The context the game runs in.
+    pub ctx: Context,
+    /// The game state under test.
+    pub state: S,
+    clock: ManualClock,
+    frame_time: Duration,
+    control_flow: ControlFlow,
+    _error: PhantomData<E>,
+}
+
+impl<S, E> std::fmt::Debug for TestHarness<S, E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TestHarness")
+            .field("ctx", &self.ctx)
+            .field("clock", &self.clock)
+            .field("frame_time", &self.frame_time)
+            .field("control_flow", &self.control_flow)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, E> TestHarness<S, E>
+where
+    S: EventHandler<E> + 'static,
+    E: std::fmt::Debug,
+{
+    /// Creates a new harness running `state` in `ctx`, which should usually be
+    /// [headless](crate::ContextBuilder::build_headless).
+    ///
+    /// This replaces the context's time source with a [`ManualClock`].
+    /// Frames last 1/60th of a second unless changed with [`set_frame_time`](Self::set_frame_time).
+    pub fn new(mut ctx: Context, state: S) -> Self {
+        let clock = ManualClock::new();
+        ctx.time.set_time_source(clock.clone());
+        TestHarness {
+            ctx,
+            state,
+            clock,
+            frame_time: Duration::from_secs(1) / 60,
+            control_flow: ControlFlow::Poll,
+            _error: PhantomData,
+        }
+    }
+
+    /// Returns `false` once the game has quit or has been stopped by an error.
+    pub fn is_running(&self) -> bool {
+        self.control_flow != ControlFlow::Exit && self.ctx.continuing
+    }
+
+    /// Returns how much the clock advances on each [`step`](Self::step).
+    pub fn frame_time(&self) -> Duration {
+        self.frame_time
+    }
+
+    /// Sets how much the clock advances on each [`step`](Self::step).
+    pub fn set_frame_time(&mut self, frame_time: Duration) -> &mut Self {
+        self.frame_time = frame_time;
+        self
+    }
+
+    /// Runs `frames` frames of [`frame_time`](Self::frame_time) each.
+    pub fn step(&mut self, frames: usize) -> &mut Self {
+        for _ in 0..frames {
+            let _ = self.step_by(self.frame_time);
+        }
+        self
+    }
+
+    /// Runs a single frame that lasts `delta`: ticks the clock, calls `update`
+    /// (as often as the fixed timestep requires, if it's enabled) and `draw`, and
+    /// moves the current input state into the previous frame's.
+    pub fn step_by(&mut self, delta: Duration) -> &mut Self {
+        let (ctx, state, control_flow) = (&mut self.ctx, &mut self.state, &mut self.control_flow);
+        if *control_flow == ControlFlow::Exit
+            || event::handle_quit_request(ctx, state, control_flow)
+        {
+            return self;
+        }
+        self.clock.advance(delta);
+        ctx.time.tick();
+        #[cfg(feature = "gamepad")]
+        if let Err(e) = ctx.gamepad.update_rumble(ctx.time.real_delta()) {
+            error!("Error on GamepadContext::update_rumble(): {e:?}");
+        }
+        let _ = event::run_frame(ctx, state, control_flow);
+        self
+    }
+
+    /// Feeds an input event to the game, as if it had come from the window or a gamepad.
+    pub fn input(&mut self, input: RecordedEvent) -> &mut Self {
+        if !self.is_running() {
+            return self;
+        }
+        #[cfg(feature = "gamepad")]
+        if let RecordedEvent::GamepadButton { .. } | RecordedEvent::GamepadAxis { .. } = input {
+            let _ = event::inject_gamepad_event(
+                &mut self.ctx,
+                &mut self.state,
+                &mut self.control_flow,
+                input,
+            );
+            return self;
+        }
+        match input.to_window_event() {
+            Some(window_event) => self.window_event(window_event),
+            None => self,
+        }
+    }
+
+    /// Feeds a window event to the game.
+    pub fn window_event(&mut self, window_event: WindowEvent<'static>) -> &mut Self {
+        if self.is_running() {
+            let _ = event::inject_window_event(
+                &mut self.ctx,
+                &mut self.state,
+                &mut self.control_flow,
+                window_event,
+            );
+        }
+        self
+    }
+
+    /// Presses a key. Its scan code is `0`; use [`input`](Self::input) to pick one.
+    pub fn key_down(&mut self, keycode: KeyCode) -> &mut Self {
+        self.key(0, Some(keycode), true)
+    }
+
+    /// Releases a key pressed with [`key_down`](Self::key_down).
+    pub fn key_up(&mut self, keycode: KeyCode) -> &mut Self {
+        self.key(0, Some(keycode), false)
+    }
+
+    fn key(&mut self, scancode: ScanCode, keycode: Option<KeyCode>, pressed: bool) -> &mut Self {
+        self.input(RecordedEvent::Key {
+            scancode,
+            keycode,
+            pressed,
+        })
+    }
+
+    /// Sets which modifier keys are held down.
+    pub fn modifiers(&mut self, mods: KeyMods) -> &mut Self {
+        let mut state = ModifiersState::empty();
+        state.set(ModifiersState::SHIFT, mods.contains(KeyMods::SHIFT));
+        state.set(ModifiersState::CTRL, mods.contains(KeyMods::CTRL));
+        state.set(ModifiersState::ALT, mods.contains(KeyMods::ALT));
+        state.set(ModifiersState::LOGO, mods.contains(KeyMods::LOGO));
+        self.input(RecordedEvent::Modifiers(state))
+    }
+
+    /// Types the characters of `text`, one text input event each.
+    pub fn text(&mut self, text: &str) -> &mut Self {
+        for ch in text.chars() {
+            let _ = self.input(RecordedEvent::Text(ch));
+        }
+        self
+    }
+
+    /// Moves the mouse to the given position, in physical pixels inside the window.
+    pub fn mouse_move(&mut self, x: f32, y: f32) -> &mut Self {
+        self.input(RecordedEvent::MouseMove {
+            x: x.into(),
+            y: y.into(),
+        })
+    }
+
+    /// Presses a mouse button at the current mouse position.
+    pub fn mouse_button_down(&mut self, button: MouseButton) -> &mut Self {
+        self.input(RecordedEvent::MouseButton {
+            button,
+            pressed: true,
+        })
+    }
+
+    /// Releases a mouse button at the current mouse position.
+    pub fn mouse_button_up(&mut self, button: MouseButton) -> &mut Self {
+        self.input(RecordedEvent::MouseButton {
+            button,
+            pressed: false,
+        })
+    }
+
+    /// Scrolls the mouse wheel by the given number of lines.
+    pub fn mouse_wheel(&mut self, x: f32, y: f32) -> &mut Self {
+        self.input(RecordedEvent::MouseWheel(MouseScrollDelta::LineDelta(x, y)))
+    }
+
+    /// Makes the window gain (`true`) or lose (`false`) focus.
+    pub fn focus(&mut self, gained: bool) -> &mut Self {
+        self.input(RecordedEvent::Focus(gained))
+    }
+
+    /// Resizes the window (or the offscreen frame of a headless context) to the given
+    /// size in physical pixels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the graphics context can't be resized to that size.
+    pub fn resize(&mut self, width: u32, height: u32) -> &mut Self {
+        if !self.is_running() {
+            return self;
+        }
+        self.ctx
+            .gfx
+            .set_drawable_size(width as f32, height as f32)
+            .expect("failed to resize the graphics context");
+        self.window_event(WindowEvent::Resized(PhysicalSize::new(width, height)))
+    }
+
+    /// Asks the game to quit, as if the window had been closed.
+    pub fn close(&mut self) -> &mut Self {
+        self.window_event(WindowEvent::CloseRequested)
+    }
+
+    /// Presses a button on the `gamepad`th gamepad, whose id is [`gamepad_id`]`(gamepad)`.
+    #[cfg(feature = "gamepad")]
+    pub fn gamepad_button_down(&mut self, gamepad: usize, button: Button) -> &mut Self {
+        self.input(RecordedEvent::GamepadButton {
+            id: gamepad_id(gamepad),
+            button,
+            pressed: true,
+        })
+    }
+
+    /// Releases a button on the `gamepad`th gamepad.
+    #[cfg(feature = "gamepad")]
+    pub fn gamepad_button_up(&mut self, gamepad: usize, button: Button) -> &mut Self {
+        self.input(RecordedEvent::GamepadButton {
+            id: gamepad_id(gamepad),
+            button,
+            pressed: false,
+        })
+    }
+
+    /// Moves an axis of the `gamepad`th gamepad to `value`.
+    #[cfg(feature = "gamepad")]
+    pub fn gamepad_axis(&mut self, gamepad: usize, axis: Axis, value: f32) -> &mut Self {
+        self.input(RecordedEvent::GamepadAxis {
+            id: gamepad_id(gamepad),
+            axis,
+            value,
+        })
+    }
+}
+
+/// Returns the id a [`TestHarness`] gives the `index`th gamepad, e.g. for checking
+/// [`GamepadContext::is_button_pressed`](crate::input::gamepad::GamepadContext::is_button_pressed).
+///
+/// These gamepads don't exist as far as gilrs is concerned, so they can't be passed to
+/// [`GamepadContext::gamepad`](crate::input::gamepad::GamepadContext::gamepad), and never
+/// rumble.
+#[cfg(feature = "gamepad")]
+pub fn gamepad_id(index: usize) -> GamepadId {
+    GamepadId::from_index(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{context::headless_ctx_for_tests, graphics, GameError, GameResult};
+
+    #[derive(Default)]
+    struct Recorder {
+        updates: usize,
+        draws: usize,
+        elapsed: Duration,
+        keys: Vec<(KeyCode, bool)>,
+        text: String,
+        clicks: Vec<(MouseButton, f32, f32)>,
+        wheel: (f32, f32),
+        resized: Option<(f32, f32)>,
+        fail_update: bool,
+        #[cfg(feature = "gamepad")]
+        gamepad: Vec<(Button, bool)>,
+    }
+
+    impl EventHandler for Recorder {
+        fn update(&mut self, ctx: &mut Context) -> GameResult {
+            self.updates += 1;
+            self.elapsed += ctx.time.delta();
+            if self.fail_update {
+                return Err(GameError::CustomError(String::from("update failed")));
+            }
+            Ok(())
+        }
+
+        fn draw(&mut self, ctx: &mut Context) -> GameResult {
+            self.draws += 1;
+            graphics::Canvas::from_frame(ctx, graphics::Color::BLACK).finish(ctx)
+        }
+
+        fn key_down_event(
+            &mut self,
+            _ctx: &mut Context,
+            input: crate::input::keyboard::KeyInput,
+            _repeated: bool,
+        ) -> GameResult {
+            self.keys.push((input.keycode.unwrap(), true));
+            Ok(())
+        }
+
+        fn key_up_event(
+            &mut self,
+            _ctx: &mut Context,
+            input: crate::input::keyboard::KeyInput,
+        ) -> GameResult {
+            self.keys.push((input.keycode.unwrap(), false));
+            Ok(())
+        }
+
+        fn text_input_event(&mut self, _ctx: &mut Context, character: char) -> GameResult {
+            self.text.push(character);
+            Ok(())
+        }
+
+        fn mouse_button_down_event(
+            &mut self,
+            _ctx: &mut Context,
+            button: MouseButton,
+            x: f32,
+            y: f32,
+        ) -> GameResult {
+            self.clicks.push((button, x, y));
+            Ok(())
+        }
+
+        fn mouse_wheel_event(&mut self, _ctx: &mut Context, x: f32, y: f32) -> GameResult {
+            self.wheel = (x, y);
+            Ok(())
+        }
+
+        fn resize_event(&mut self, _ctx: &mut Context, width: f32, height: f32) -> GameResult {
+            self.resized = Some((width, height));
+            Ok(())
+        }
+
+        #[cfg(feature = "gamepad")]
+        fn gamepad_button_down_event(
+            &mut self,
+            _ctx: &mut Context,
+            btn: Button,
+            _id: GamepadId,
+        ) -> GameResult {
+            self.gamepad.push((btn, true));
+            Ok(())
+        }
+    }
+
+    fn harness() -> TestHarness<Recorder> {
+        let ctx = headless_ctx_for_tests(16, 16);
+        TestHarness::new(ctx, Recorder::default())
+    }
+
+    #[test]
+    fn headless_test_harness_input() {
+        let mut harness = harness();
+        let _ = harness
+            .key_down(KeyCode::A)
+            .text("hi")
+            .mouse_move(4.0, 5.0)
+            .mouse_button_down(MouseButton::Right)
+            .mouse_wheel(0.0, -2.0)
+            .modifiers(KeyMods::SHIFT);
+        assert!(harness.ctx.keyboard.is_key_pressed(KeyCode::A));
+        assert!(harness.ctx.keyboard.is_key_just_pressed(KeyCode::A));
+        assert!(harness.ctx.keyboard.is_mod_active(KeyMods::SHIFT));
+        assert!(harness.ctx.mouse.button_pressed(MouseButton::Right));
+
+        let _ = harness.step(1).key_up(KeyCode::A);
+        assert!(!harness.ctx.keyboard.is_key_pressed(KeyCode::A));
+        assert!(harness.ctx.keyboard.is_key_just_released(KeyCode::A));
+
+        let state = &harness.state;
+        assert_eq!(state.keys, [(KeyCode::A, true), (KeyCode::A, false)]);
+        assert_eq!(state.text, "hi");
+        assert_eq!(state.clicks, [(MouseButton::Right, 4.0, 5.0)]);
+        assert_eq!(state.wheel, (0.0, -2.0));
+    }
+
+    #[test]
+    fn headless_test_harness_step() {
+        let mut harness = harness();
+        let _ = harness.set_frame_time(Duration::from_millis(10)).step(5);
+        assert_eq!(harness.state.updates, 5);
+        assert_eq!(harness.state.draws, 5);
+        assert_eq!(harness.state.elapsed, Duration::from_millis(50));
+        assert_eq!(
+            harness.ctx.time.time_since_start(),
+            Duration::from_millis(50)
+        );
+        assert_eq!(harness.ctx.time.ticks(), 5);
+        assert!(harness.ctx.time.check_update_time(100));
+    }
+
+    #[test]
+    fn headless_test_harness_fixed_timestep() {
+        // With a fixed timestep, a long frame runs several updates.
+        let mut harness = harness();
+        harness.ctx.time.set_fixed_timestep(
+            crate::conf::FixedTimestep::default()
+                .enabled(true)
+                .updates_per_second(100),
+        );
+        let _ = harness.step_by(Duration::from_millis(35));
+        assert_eq!(harness.state.updates, 3);
+        assert_eq!(harness.state.draws, 1);
+        assert_eq!(
+            harness.ctx.time.remaining_update_time(),
+            Duration::from_millis(5)
+        );
+    }
+
+    #[test]
+    fn headless_test_harness_resize() {
+        let mut harness = harness();
+        let _ = harness.resize(32, 24);
+        assert_eq!(harness.state.resized, Some((32.0, 24.0)));
+        assert_eq!(harness.ctx.gfx.drawable_size(), (32.0, 24.0));
+        let _ = harness.step(1);
+        assert_eq!(harness.ctx.gfx.frame().width(), 32);
+    }
+
+    #[test]
+    fn headless_test_harness_stops() {
+        let mut harness = harness();
+        harness.state.fail_update = true;
+        let _ = harness.step(3);
+        assert!(!harness.is_running());
+        assert_eq!(harness.state.updates, 1);
+        assert_eq!(harness.state.draws, 0);
+        // Only one context can be alive at a time on some backends.
+        drop(harness);
+
+        let mut harness = self::harness();
+        let _ = harness.close().step(1);
+        assert!(!harness.is_running());
+        assert_eq!(harness.state.updates, 0);
+    }
+
+    #[cfg(feature = "gamepad")]
+    #[test]
+    fn headless_test_harness_gamepad() {
+        let mut harness = harness();
+        let pad = gamepad_id(3);
+        let _ =
+            harness
+                .gamepad_button_down(3, Button::South)
+                .gamepad_axis(3, Axis::LeftStickX, 0.5);
+        assert!(harness
+            .ctx
+            .gamepad
+            .is_button_just_pressed(pad, Button::South));
+        assert_eq!(harness.ctx.gamepad.axis_value(pad, Axis::LeftStickX), 0.5);
+        assert_eq!(harness.state.gamepad, [(Button::South, true)]);
+
+        let _ = harness.step(1).gamepad_button_up(3, Button::South);
+        assert!(harness
+            .ctx
+            .gamepad
+            .is_button_just_released(pad, Button::South));
+        assert!(!harness.ctx.gamepad.is_button_pressed(pad, Button::South));
+
+        // made up gamepads never rumble
+        let rumble = crate::input::gamepad::Rumble::new(1.0, 1.0, Duration::from_secs(1));
+        assert!(!harness.ctx.gamepad.supports_rumble(pad));
+        assert!(!harness.ctx.gamepad.rumble(pad, rumble).unwrap());
+    }
+}
@@ -0,0 +1,651 @@
+//! Timing and measurement functions.
+//!
+//! ggez does not try to do any framerate limitation by default. If
+//! you want to run at anything other than full-bore max speed all the
+//! time, call [`thread::yield_now()`](https://doc.rust-lang.org/std/thread/fn.yield_now.html)
+//! (or [`timer::yield_now()`](fn.yield_now.html) which does the same
+//! thing) to yield to the OS so it has a chance to breathe before continuing
+//! with your game. This should prevent it from using 100% CPU as much unless it
+//! really needs to.  Enabling vsync by setting
+//! [`conf.window_setup.vsync`](../conf/struct.WindowSetup.html#structfield.vsync)
+//! in your [`Conf`](../conf/struct.Conf.html) object is generally the best
+//! way to cap your displayed framerate.
+//!
+//! For a more detailed tutorial in how to handle frame timings in games,
+//! see <http://gafferongames.com/game-physics/fix-your-timestep/>
+//!
+//! The [`TimeContext`] reads the current time from a [`TimeSource`], which is the
+//! [`SystemClock`] unless replaced with [`TimeContext::set_time_source`]. A
+//! [`ManualClock`] only moves forward when told to, which is handy for testing
+//! time-dependent code. The game can also be slowed down, sped up or paused with
+//! [`TimeContext::set_time_scale`] and [`TimeContext::pause`].
+//!
+//! For cooldowns, delayed actions and animations, a [`Scheduler`] runs callbacks
+//! and [`Tween`]s as time passes.
+
+mod scheduler;
+mod tween;
+
+pub use self::scheduler::{Scheduler, TimerHandle};
+pub use self::tween::{Easing, Lerp, Tween};
+
+use std::{
+    cmp,
+    convert::TryFrom,
+    f64, fmt,
+    sync::{Arc, Mutex},
+    thread, time,
+};
+
+use crate::{conf::FixedTimestep, Context};
+
+/// A simple buffer that fills
+/// up to a limit and then holds the last
+/// N items that have been inserted into it,
+/// overwriting old ones in a round-robin fashion.
+///
+/// It's not quite a ring buffer 'cause you can't
+/// remove items from it, it just holds the last N
+/// things.
+#[derive(Debug, Clone)]
+struct LogBuffer<T>
+where
+    T: Clone,
+{
+    head: usize,
+    size: usize,
+    /// The number of actual samples inserted, used for
+    /// smarter averaging.
+    samples: usize,
+    contents: Vec<T>,
+}
+
+impl<T> LogBuffer<T>
+where
+    T: Clone + Copy,
+{
+    fn new(size: usize, init_val: T) -> LogBuffer<T> {
+        LogBuffer {
+            head: 0,
+            size,
+            contents: vec![init_val; size],
+            // Never divide by 0
+            samples: 1,
+        }
+    }
+
+    /// Pushes a new item into the `LogBuffer`, overwriting
+    /// the oldest item in it.
+    fn push(&mut self, item: T) {
+        self.head = (self.head + 1) % self.contents.len();
+        self.contents[self.head] = item;
+        self.size = cmp::min(self.size + 1, self.contents.len());
+        self.samples += 1;
+    }
+
+    /// Returns a slice pointing at the contents of the buffer.
+    /// They are in *no particular order*, and if not all the
+    /// slots are filled, the empty slots will be present but
+    /// contain the initial value given to [`new()`](#method.new).
+    ///
+    /// We're only using this to log FPS for a short time,
+    /// so we don't care for the second or so when it's inaccurate.
+    fn contents(&self) -> &[T] {
+        if self.samples > self.size {
+            &self.contents
+        } else {
+            &self.contents[..self.samples]
+        }
+    }
+
+    /// Returns the most recent value in the buffer.
+    fn latest(&self) -> T {
+        self.contents[self.head]
+    }
+}
+
+/// Where a [`TimeContext`] gets the current time from.
+pub trait TimeSource: fmt::Debug + Send {
+    /// Returns the current time.
+    fn now(&self) -> time::Instant;
+}
+
+/// A [`TimeSource`] that reads the system clock. This is the default.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct SystemClock;
+
+impl TimeSource for SystemClock {
+    fn now(&self) -> time::Instant {
+        time::Instant::now()
+    }
+}
+
+/// A [`TimeSource`] whose time only moves when [`advance()`](#method.advance) is called.
+///
+/// Clones share the same time, so keep one around to control the clock after
+/// handing it to [`TimeContext::set_time_source`]:
+///
+/// ```rust
+/// # use ggez::timer::{ManualClock, TimeContext};
+/// # use std::time::Duration;
+/// let clock = ManualClock::new();
+/// let mut time = TimeContext::new();
+/// time.set_time_source(clock.clone());
+///
+/// clock.advance(Duration::from_millis(20));
+/// time.tick();
+/// assert_eq!(time.delta(), Duration::from_millis(20));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<time::Instant>>,
+}
+
+impl ManualClock {
+    /// Creates a new clock, stopped at the current time.
+    pub fn new() -> Self {
+        ManualClock {
+            now: Arc::new(Mutex::new(time::Instant::now())),
+        }
+    }
+
+    /// Moves the clock forward by `duration`.
+    pub fn advance(&self, duration: time::Duration) {
+        *self.now.lock().unwrap(/* never poisoned */) += duration;
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimeSource for ManualClock {
+    fn now(&self) -> time::Instant {
+        *self.now.lock().unwrap(/* never poisoned */)
+    }
+}
+
+/// A structure that contains our time-tracking state.
+#[derive(Debug)]
+pub struct TimeContext {
+    source: Box<dyn TimeSource>,
+    init_instant: time::Instant,
+    last_instant: time::Instant,
+    /// The time since start of a replayed frame, reported instead of the source's.
+    replayed_time: Option<time::Duration>,
+    /// Real (unscaled) frame durations.
+    frame_durations: LogBuffer<time::Duration>,
+    /// The length of the last frame, scaled by the time scale.
+    scaled_delta: time::Duration,
+    residual_update_dt: time::Duration,
+    frame_count: usize,
+    fixed_timestep: FixedTimestep,
+    time_scale: f64,
+    paused: bool,
+}
+
+/// How many frames we log update times for.
+const TIME_LOG_FRAMES: usize = 200;
+
+impl TimeContext {
+    /// Creates a new `TimeContext` and initializes the start to this instant.
+    pub fn new() -> TimeContext {
+        let initial_dt = time::Duration::from_millis(16);
+        TimeContext {
+            source: Box::new(SystemClock),
+            init_instant: time::Instant::now(),
+            last_instant: time::Instant::now(),
+            replayed_time: None,
+            frame_durations: LogBuffer::new(TIME_LOG_FRAMES, initial_dt),
+            scaled_delta: initial_dt,
+            residual_update_dt: time::Duration::from_secs(0),
+            frame_count: 0,
+            fixed_timestep: FixedTimestep::default(),
+            time_scale: 1.0,
+            paused: false,
+        }
+    }
+
+    /// Replaces the [`TimeSource`] the current time is read from, e.g. with a
+    /// [`ManualClock`] in tests.
+    ///
+    /// This restarts [`time_since_start()`](#method.time_since_start) and the current frame
+    /// at the source's current time.
+    pub fn set_time_source(&mut self, source: impl TimeSource + 'static) {
+        let now = source.now();
+        self.source = Box::new(source);
+        self.init_instant = now;
+        self.last_instant = now;
+    }
+
+    /// Get the time between the start of the last frame and the current one;
+    /// in other words, the length of the last frame.
+    ///
+    /// This is scaled by the [time scale](#method.time_scale), and zero while paused.
+    /// See [`real_delta()`](#method.real_delta) for the actual length of the frame.
+    pub fn delta(&self) -> time::Duration {
+        self.scaled_delta
+    }
+
+    /// Get the actual length of the last frame, not affected by the
+    /// [time scale](#method.time_scale) or pausing.
+    pub fn real_delta(&self) -> time::Duration {
+        self.frame_durations.latest()
+    }
+
+    /// Returns how fast game time passes compared to real time.
+    pub fn time_scale(&self) -> f64 {
+        self.time_scale
+    }
+
+    /// Sets how fast game time passes compared to real time: `0.5` for slow motion,
+    /// `2.0` for fast-forward and so on. Negative and NaN scales are treated as `0.0`, and
+    /// infinite ones are ignored; frames scaled to more than [`Duration::MAX`](time::Duration::MAX)
+    /// last that long.
+    ///
+    /// This applies to [`delta()`](#method.delta), [`check_update_time()`](#method.check_update_time)
+    /// and the [fixed timestep](#method.fixed_update_steps) from the next frame on, but not to
+    /// [`time_since_start()`](#method.time_since_start), [`average_delta()`](#method.average_delta)
+    /// and [`fps()`](#method.fps), which keep reporting real time.
+    pub fn set_time_scale(&mut self, time_scale: f64) {
+        if time_scale == f64::INFINITY {
+            warn!("Ignoring an infinite time scale");
+            return;
+        }
+        self.time_scale = time_scale.max(0.0);
+    }
+
+    /// Pauses game time, as if the [time scale](#method.set_time_scale) was `0.0`,
+    /// without forgetting the time scale.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Resumes game time after [`pause()`](#method.pause).
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Returns `true` if game time is [paused](#method.pause).
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Gets the average real time of a frame, averaged
+    /// over the last 200 frames.
+    pub fn average_delta(&self) -> time::Duration {
+        let sum: time::Duration = self.frame_durations.contents().iter().sum();
+
+        // If our buffer is actually full, divide by its size.
+        // Otherwise divide by the number of samples we've added
+        // Both these unwraps should be fine as frame_durations should always fit in a u32
+        if self.frame_durations.samples > self.frame_durations.size {
+            sum / u32::try_from(self.frame_durations.size).unwrap()
+        } else {
+            sum / u32::try_from(self.frame_durations.samples).unwrap()
+        }
+    }
+
+    /// Gets the FPS of the game, averaged over the last
+    /// 200 frames.
+    pub fn fps(&self) -> f64 {
+        let duration_per_frame = self.average_delta();
+        let seconds_per_frame = duration_per_frame.as_secs_f64();
+        1.0 / seconds_per_frame
+    }
+
+    /// Gets the number of times the game has gone through its event loop.
+    ///
+    /// Specifically, the number of times that [`TimeContext::tick()`](struct.TimeContext.html#method.tick)
+    /// has been called by it.
+    pub fn ticks(&self) -> usize {
+        self.frame_count
+    }
+
+    /// Returns the time since the game was initialized,
+    /// as reported by the [`TimeSource`] (the system clock by default).
+    ///
+    /// This is real time, not affected by the [time scale](#method.set_time_scale).
+    /// While a recording is replayed, this is the time recorded for the current frame.
+    pub fn time_since_start(&self) -> time::Duration {
+        self.replayed_time
+            .unwrap_or_else(|| self.source.now() - self.init_instant)
+    }
+
+    /// Check whether or not the desired amount of time has elapsed
+    /// since the last frame.
+    ///
+    /// The intention is to use this in your `update` call to control
+    /// how often game logic is updated per frame (see [the astroblasto example](https://github.com/ggez/ggez/blob/30ea4a4ead67557d2ebb39550e17339323fc9c58/examples/05_astroblasto.rs#L438-L442)).
+    ///
+    /// Calling this decreases a timer inside the context if the function returns true.
+    /// If called in a loop it may therefore return true once, twice or not at all, depending on
+    /// how much time elapsed since the last frame.
+    ///
+    /// For more info on the idea behind this see <http://gafferongames.com/game-physics/fix-your-timestep/>.
+    ///
+    /// Due to the global nature of this timer it's desirable to only use this function at one point
+    /// of your code. If you want to limit the frame rate in both game logic and drawing consider writing
+    /// your own event loop, or using a dirty bit for when to redraw graphics, which is set whenever the game
+    /// logic runs.
+    pub fn check_update_time(&mut self, target_fps: u32) -> bool {
+        let target_dt = fps_as_duration(target_fps);
+        if self.residual_update_dt > target_dt {
+            self.residual_update_dt -= target_dt;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the fractional amount of a frame not consumed
+    /// by  [`check_update_time()`](fn.check_update_time.html).
+    /// For example, if the desired
+    /// update frame time is 40 ms (25 fps), and 45 ms have
+    /// passed since the last frame, [`check_update_time()`](fn.check_update_time.html)
+    /// will return `true` and `remaining_update_time()` will
+    /// return 5 ms -- the amount of time "overflowing" from one
+    /// frame to the next.
+    ///
+    /// The intention is for it to be called in your
+    /// [`draw()`](../event/trait.EventHandler.html#tymethod.draw) callback
+    /// to interpolate physics states for smooth rendering.
+    /// (see <http://gafferongames.com/game-physics/fix-your-timestep/>)
+    pub fn remaining_update_time(&self) -> time::Duration {
+        self.residual_update_dt
+    }
+
+    /// Returns the fixed timestep settings used by [`event::run()`](../event/fn.run.html).
+    pub fn fixed_timestep(&self) -> FixedTimestep {
+        self.fixed_timestep
+    }
+
+    /// Changes the fixed timestep settings used by [`event::run()`](../event/fn.run.html).
+    /// These are initially taken from [`Conf::fixed_timestep`](../conf/struct.Conf.html#structfield.fixed_timestep).
+    pub fn set_fixed_timestep(&mut self, fixed_timestep: FixedTimestep) {
+        self.fixed_timestep = fixed_timestep;
+    }
+
+    /// Returns how many fixed steps [`update()`](../event/trait.EventHandler.html#tymethod.update)
+    /// should be called for in this frame, consuming the time accumulated by
+    /// [`tick()`](#method.tick). Like [`check_update_time()`](#method.check_update_time), this
+    /// consumes the same timer, so only use one of the two.
+    ///
+    /// Frames longer than [`FixedTimestep::max_frame_time`](../conf/struct.FixedTimestep.html#structfield.max_frame_time)
+    /// are clamped, and if more than [`FixedTimestep::max_steps_per_frame`](../conf/struct.FixedTimestep.html#structfield.max_steps_per_frame)
+    /// steps are due, the rest is dropped.
+    ///
+    /// [`event::run()`](../event/fn.run.html) calls this for you if the fixed timestep is enabled.
+    /// You only need to call this function if you're writing your own custom event loop.
+    pub fn fixed_update_steps(&mut self) -> u32 {
+        let step = self.fixed_timestep.step_duration();
+        let max_residual =
+            time::Duration::from_secs_f32(self.fixed_timestep.max_frame_time.max(0.0)).max(step);
+        self.residual_update_dt = self.residual_update_dt.min(max_residual);
+
+        let max_steps = self.fixed_timestep.max_steps_per_frame.max(1);
+        let mut steps = 0;
+        while self.residual_update_dt >= step && steps < max_steps {
+            self.residual_update_dt -= step;
+            steps += 1;
+        }
+        if self.residual_update_dt >= step {
+            // We can't catch up, so drop the whole steps we're behind and only keep the fraction.
+            let remainder = self.residual_update_dt.as_nanos() % step.as_nanos();
+            self.residual_update_dt = time::Duration::from_nanos(remainder as u64);
+        }
+        steps
+    }
+
+    /// Returns how far the current frame is between the last fixed update and the next one,
+    /// from `0.0` to `1.0`.
+    ///
+    /// Use this in your [`draw()`](../event/trait.EventHandler.html#tymethod.draw) callback
+    /// to interpolate between the previous and current state when the fixed timestep is
+    /// enabled (see [`FixedTimestep`](../conf/struct.FixedTimestep.html)).
+    /// Always returns `1.0` if it's disabled.
+    pub fn alpha(&self) -> f32 {
+        if self.fixed_timestep.enabled {
+            let step = self.fixed_timestep.step_duration();
+            (self.residual_update_dt.as_secs_f64() / step.as_secs_f64()).min(1.0) as f32
+        } else {
+            1.0
+        }
+    }
+
+    /// Update the state of the `TimeContext` to record that
+    /// another frame has taken place.  Necessary for the FPS
+    /// tracking and [`check_update_time()`](fn.check_update_time.html)
+    /// functions to work.
+    ///
+    /// It's usually not necessary to call this function yourself,
+    /// [`event::run()`](../event/fn.run.html) will do it for you.
+    /// You only need to call this function if you're writing your
+    /// own custom event loop.
+    pub fn tick(&mut self) {
+        let now = self.source.now();
+        let time_since_last = now - self.last_instant;
+        self.advance_frame(time_since_last);
+        self.last_instant = now;
+        self.replayed_time = None;
+    }
+
+    /// Like [`tick()`](#method.tick), but records the frame as having lasted `delta`
+    /// in real time instead of measuring it, and makes
+    /// [`time_since_start()`](#method.time_since_start) return `time_since_start` until
+    /// the next tick. The time scale still applies.
+    ///
+    /// [`event::run()`](../event/fn.run.html) uses this to replay a recorded session
+    /// (see [`InputRecorder`](../input/recording/struct.InputRecorder.html)) with the
+    /// original frame timings.
+    pub fn tick_by(&mut self, delta: time::Duration, time_since_start: time::Duration) {
+        self.advance_frame(delta);
+        self.last_instant = self.source.now();
+        self.replayed_time = Some(time_since_start);
+    }
+
+    fn advance_frame(&mut self, real_delta: time::Duration) {
+        self.frame_durations.push(real_delta);
+        self.frame_count += 1;
+
+        self.scaled_delta = if self.paused {
+            time::Duration::ZERO
+        } else {
+            // `Duration::mul_f64` panics on overflow
+            time::Duration::try_from_secs_f64(real_delta.as_secs_f64() * self.time_scale)
+                .unwrap_or(time::Duration::MAX)
+        };
+        self.residual_update_dt = self.residual_update_dt.saturating_add(self.scaled_delta);
+    }
+}
+
+impl Default for TimeContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Get the time between the start of the last frame and the current one;
+/// in other words, the length of the last frame.
+#[deprecated(note = "Use `ctx.time.delta` instead")]
+pub fn delta(ctx: &Context) -> time::Duration {
+    ctx.time.delta()
+}
+
+/// Gets the average time of a frame, averaged
+/// over the last 200 frames.
+#[deprecated(note = "Use `ctx.time.average_delta` instead")]
+pub fn average_delta(ctx: &Context) -> time::Duration {
+    ctx.time.average_delta()
+}
+
+/// Returns a `Duration` representing how long each
+/// frame should be to match the given fps.
+///
+/// Approximately.
+fn fps_as_duration(fps: u32) -> time::Duration {
+    let target_dt_seconds = 1.0 / f64::from(fps);
+    time::Duration::from_secs_f64(target_dt_seconds)
+}
+
+/// Gets the FPS of the game, averaged over the last
+/// 200 frames.
+#[deprecated(note = "Use `ctx.time.fps` instead")]
+pub fn fps(ctx: &Context) -> f64 {
+    ctx.time.fps()
+}
+
+/// Returns the time since the game was initialized,
+/// as reported by the system clock.
+#[deprecated(note = "Use `ctx.time.time_since_start` instead")]
+pub fn time_since_start(ctx: &Context) -> time::Duration {
+    let tc = &ctx.time;
+    tc.init_instant.elapsed()
+}
+
+/// Check whether or not the desired amount of time has elapsed
+/// since the last frame.
+///
+/// The intention is to use this in your `update` call to control
+/// how often game logic is updated per frame (see [the astroblasto example](https://github.com/ggez/ggez/blob/30ea4a4ead67557d2ebb39550e17339323fc9c58/examples/05_astroblasto.rs#L438-L442)).
+///
+/// Calling this decreases a timer inside the context if the function returns true.
+/// If called in a loop it may therefore return true once, twice or not at all, depending on
+/// how much time elapsed since the last frame.
+///
+/// For more info on the idea behind this see <http://gafferongames.com/game-physics/fix-your-timestep/>.
+///
+/// Due to the global nature of this timer it's desirable to only use this function at one point
+/// of your code. If you want to limit the frame rate in both game logic and drawing consider writing
+/// your own event loop, or using a dirty bit for when to redraw graphics, which is set whenever the game
+/// logic runs.
+#[deprecated(note = "Use `ctx.time.check_update_time` instead")]
+pub fn check_update_time(ctx: &mut Context, target_fps: u32) -> bool {
+    let timedata = &mut ctx.time;
+
+    let target_dt = fps_as_duration(target_fps);
+    if timedata.residual_update_dt > target_dt {
+        timedata.residual_update_dt -= target_dt;
+        true
+    } else {
+        false
+    }
+}
+
+/// Returns the fractional amount of a frame not consumed
+/// by  [`check_update_time()`](fn.check_update_time.html).
+/// For example, if the desired
+/// update frame time is 40 ms (25 fps), and 45 ms have
+/// passed since the last frame, [`check_update_time()`](fn.check_update_time.html)
+/// will return `true` and `remaining_update_time()` will
+/// return 5 ms -- the amount of time "overflowing" from one
+/// frame to the next.
+///
+/// The intention is for it to be called in your
+/// [`draw()`](../event/trait.EventHandler.html#tymethod.draw) callback
+/// to interpolate physics states for smooth rendering.
+/// (see <http://gafferongames.com/game-physics/fix-your-timestep/>)
+#[deprecated(note = "Use `ctx.time.remaining_update_time` instead")]
+pub fn remaining_update_time(ctx: &Context) -> time::Duration {
+    ctx.time.residual_update_dt
+}
+
+/// Pauses the current thread for the target duration.
+/// Just calls [`std::thread::sleep()`](https://doc.rust-lang.org/std/thread/fn.sleep.html)
+/// so it's as accurate as that is (which is usually not very).
+pub fn sleep(duration: time::Duration) {
+    thread::sleep(duration);
+}
+
+/// Yields the current timeslice to the OS.
+///
+/// This just calls [`std::thread::yield_now()`](https://doc.rust-lang.org/std/thread/fn.yield_now.html)
+/// but it's handy to have here.
+pub fn yield_now() {
+    thread::yield_now();
+}
+
+/// Gets the number of times the game has gone through its event loop.
+///
+/// Specifically, the number of times that [`TimeContext::tick()`](struct.TimeContext.html#method.tick)
+/// has been called by it.
+#[deprecated(note = "Use `ctx.time.ticks` instead")]
+pub fn ticks(ctx: &Context) -> usize {
+    ctx.time.frame_count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{time::Duration, FixedTimestep, ManualClock, TimeContext};
+
+    #[test]
+    fn headless_test_fixed_update_steps() {
+        let mut tc = TimeContext::new();
+        tc.set_fixed_timestep(
+            FixedTimestep::default()
+                .enabled(true)
+                .updates_per_second(10)
+                .max_steps_per_frame(3)
+                .max_frame_time(1.0),
+        );
+
+        tc.residual_update_dt = Duration::from_millis(250);
+        assert_eq!(tc.fixed_update_steps(), 2);
+        assert!((tc.alpha() - 0.5).abs() < 1e-4);
+
+        // Falling too far behind drops the backlog but keeps the fraction.
+        tc.residual_update_dt = Duration::from_millis(750);
+        assert_eq!(tc.fixed_update_steps(), 3);
+        assert_eq!(tc.remaining_update_time(), Duration::from_millis(50));
+
+        // Very long frames are clamped to `max_frame_time`.
+        tc.residual_update_dt = Duration::from_secs(10);
+        assert_eq!(tc.fixed_update_steps(), 3);
+        assert_eq!(tc.remaining_update_time(), Duration::ZERO);
+    }
+
+    #[test]
+    fn headless_test_time_scale() {
+        let clock = ManualClock::new();
+        let mut tc = TimeContext::new();
+        tc.set_time_source(clock.clone());
+
+        clock.advance(Duration::from_millis(100));
+        tc.tick();
+        assert_eq!(tc.delta(), Duration::from_millis(100));
+        assert!(tc.check_update_time(20));
+
+        tc.set_time_scale(0.5);
+        clock.advance(Duration::from_millis(100));
+        tc.tick();
+        assert_eq!(tc.delta(), Duration::from_millis(50));
+        assert_eq!(tc.real_delta(), Duration::from_millis(100));
+        assert_eq!(tc.remaining_update_time(), Duration::from_millis(100));
+
+        tc.pause();
+        clock.advance(Duration::from_millis(100));
+        tc.tick();
+        assert_eq!(tc.delta(), Duration::ZERO);
+        assert_eq!(tc.remaining_update_time(), Duration::from_millis(100));
+
+        tc.resume();
+        tc.set_time_scale(2.0);
+        // Time since start and FPS stay in real time.
+        assert_eq!(tc.time_since_start(), Duration::from_millis(300));
+
+        tc.tick_by(Duration::from_millis(10), Duration::from_secs(5));
+        assert_eq!(tc.delta(), Duration::from_millis(20));
+        assert_eq!(tc.time_since_start(), Duration::from_secs(5));
+        assert_eq!(tc.ticks(), 4);
+        // The average includes the initial 16 ms estimate.
+        assert_eq!(tc.average_delta(), Duration::from_micros(65_200));
+
+        tc.set_time_scale(f64::INFINITY);
+        assert_eq!(tc.time_scale(), 2.0);
+        tc.set_time_scale(f64::MAX);
+        tc.tick_by(Duration::from_millis(10), Duration::from_secs(5));
+        assert_eq!(tc.delta(), Duration::MAX);
+    }
+}
@@ -0,0 +1,240 @@
+//! Timers and tweens that run callbacks as game time passes.
+
+use std::{fmt, time::Duration};
+
+use super::tween::{Lerp, Tween};
+
+/// Identifies something scheduled on a [`Scheduler`], for cancelling it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TimerHandle(u64);
+
+/// A scheduled task; returns `true` once it's done.
+type Task<T> = Box<dyn FnMut(&mut T, Duration) -> bool>;
+
+/// Runs delayed and repeating callbacks and applies [`Tween`]s as time passes.
+///
+/// The callbacks get mutable access to a target of type `T`, such as the part of
+/// the game state that cooldowns and animations affect, which is passed to
+/// [`update()`](Self::update) along with how much time has passed. Usually that's
+/// [`ctx.time.delta()`](super::TimeContext::delta), so that the scheduler follows the
+/// time scale and stops while the game is paused.
+///
+/// ```rust
+/// # use ggez::graphics::Color;
+/// # use ggez::timer::{Easing, Scheduler, Tween};
+/// # use std::time::Duration;
+/// struct Player {
+///     can_shoot: bool,
+///     color: Color,
+///     score: u32,
+/// }
+///
+/// let mut player = Player { can_shoot: false, color: Color::WHITE, score: 0 };
+/// let mut scheduler = Scheduler::new();
+///
+/// // A cooldown,
+/// scheduler.after(Duration::from_millis(250), |player: &mut Player| player.can_shoot = true);
+/// // some points every second,
+/// let bonus = scheduler.every(Duration::from_secs(1), |player: &mut Player| player.score += 10);
+/// // and a fade out.
+/// scheduler.tween(
+///     Tween::new(Color::WHITE, Color::BLACK, Duration::from_secs(2), Easing::SineInOut),
+///     |player: &mut Player, color| player.color = color,
+/// );
+///
+/// // In `update`, this would be `scheduler.update(ctx.time.delta(), &mut self.player)`.
+/// scheduler.update(Duration::from_secs(2), &mut player);
+/// assert!(player.can_shoot);
+/// assert_eq!(player.score, 20);
+/// assert_eq!(player.color, Color::BLACK);
+///
+/// scheduler.cancel(bonus);
+/// assert!(scheduler.is_empty());
+/// ```
+pub struct Scheduler<T> {
+    next_handle: u64,
+    tasks: Vec<(TimerHandle, Task<T>)>,
+}
+
+impl<T> Scheduler<T> {
+    /// Creates an empty scheduler.
+    pub fn new() -> Self {
+        Scheduler {
+            next_handle: 0,
+            tasks: Vec::new(),
+        }
+    }
+
+    fn schedule(&mut self, task: Task<T>) -> TimerHandle {
+        let handle = TimerHandle(self.next_handle);
+        self.next_handle += 1;
+        self.tasks.push((handle, task));
+        handle
+    }
+
+    /// Calls `callback` once, after `delay` has passed.
+    pub fn after(
+        &mut self,
+        delay: Duration,
+        mut callback: impl FnMut(&mut T) + 'static,
+    ) -> TimerHandle {
+        let mut remaining = delay;
+        self.schedule(Box::new(move |target, delta| {
+            if delta < remaining {
+                remaining -= delta;
+                false
+            } else {
+                callback(target);
+                true
+            }
+        }))
+    }
+
+    /// Calls `callback` every `interval`, until cancelled.
+    ///
+    /// If more than one interval passes in a single [`update()`](Self::update),
+    /// `callback` is called once for each of them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    pub fn every(
+        &mut self,
+        interval: Duration,
+        mut callback: impl FnMut(&mut T) + 'static,
+    ) -> TimerHandle {
+        assert!(
+            !interval.is_zero(),
+            "a repeating timer needs a non-zero interval"
+        );
+        let mut remaining = interval;
+        self.schedule(Box::new(move |target, mut delta| {
+            while delta >= remaining {
+                delta -= remaining;
+                remaining = interval;
+                callback(target);
+            }
+            remaining -= delta;
+            false
+        }))
+    }
+
+    /// Advances `tween` on every [`update()`](Self::update) and passes its value to
+    /// `apply`, until it's finished.
+    pub fn tween<V: Lerp + 'static>(
+        &mut self,
+        mut tween: Tween<V>,
+        mut apply: impl FnMut(&mut T, V) + 'static,
+    ) -> TimerHandle {
+        self.schedule(Box::new(move |target, delta| {
+            apply(target, tween.update(delta));
+            tween.is_finished()
+        }))
+    }
+
+    /// Cancels a timer or tween. Returns `false` if it had already finished or been cancelled.
+    pub fn cancel(&mut self, handle: TimerHandle) -> bool {
+        let len = self.tasks.len();
+        self.tasks.retain(|(h, _)| *h != handle);
+        self.tasks.len() != len
+    }
+
+    /// Returns `true` if the timer or tween hasn't finished or been cancelled yet.
+    pub fn is_active(&self, handle: TimerHandle) -> bool {
+        self.tasks.iter().any(|(h, _)| *h == handle)
+    }
+
+    /// Cancels everything.
+    pub fn clear(&mut self) {
+        self.tasks.clear();
+    }
+
+    /// Returns the number of active timers and tweens.
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Returns `true` if there are no active timers or tweens.
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Advances all timers and tweens by `delta`, in the order they were scheduled,
+    /// calling their callbacks with `target` as needed.
+    pub fn update(&mut self, delta: Duration, target: &mut T) {
+        self.tasks.retain_mut(|(_, task)| !task(target, delta));
+    }
+}
+
+impl<T> Default for Scheduler<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for Scheduler<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scheduler")
+            .field("tasks", &self.tasks.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timer::Easing;
+
+    #[test]
+    fn headless_test_timers() {
+        let mut scheduler = Scheduler::new();
+        let mut log = Vec::new();
+        let once = scheduler.after(Duration::from_millis(30), |log: &mut Vec<&str>| {
+            log.push("once");
+        });
+        let repeat = scheduler.every(Duration::from_millis(20), |log: &mut Vec<&str>| {
+            log.push("repeat");
+        });
+        let cancelled = scheduler.after(Duration::from_millis(10), |log: &mut Vec<&str>| {
+            log.push("cancelled");
+        });
+        assert!(scheduler.cancel(cancelled));
+        assert!(!scheduler.cancel(cancelled));
+        assert_eq!(scheduler.len(), 2);
+
+        scheduler.update(Duration::from_millis(10), &mut log);
+        assert!(log.is_empty());
+        scheduler.update(Duration::from_millis(20), &mut log);
+        assert_eq!(log, ["once", "repeat"]);
+        assert!(!scheduler.is_active(once));
+        assert!(scheduler.is_active(repeat));
+
+        // Long frames fire a repeating timer once per interval.
+        log.clear();
+        scheduler.update(Duration::from_millis(50), &mut log);
+        assert_eq!(log, ["repeat", "repeat", "repeat"]);
+        scheduler.update(Duration::from_millis(19), &mut log);
+        assert_eq!(log.len(), 3);
+        scheduler.update(Duration::from_millis(1), &mut log);
+        assert_eq!(log.len(), 4);
+
+        scheduler.clear();
+        assert!(scheduler.is_empty());
+    }
+
+    #[test]
+    fn headless_test_scheduled_tween() {
+        let mut scheduler = Scheduler::new();
+        let mut x = 0.0;
+        let handle = scheduler.tween(
+            Tween::new(0.0, 100.0, Duration::from_millis(100), Easing::Linear),
+            |x: &mut f32, value| *x = value,
+        );
+        scheduler.update(Duration::from_millis(25), &mut x);
+        assert_eq!(x, 25.0);
+        assert!(scheduler.is_active(handle));
+        scheduler.update(Duration::from_millis(100), &mut x);
+        assert_eq!(x, 100.0);
+        assert!(!scheduler.is_active(handle));
+    }
+}
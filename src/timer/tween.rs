@@ -0,0 +1,414 @@
+//! Easing curves and tweens for animating values over time.
+
+use std::{f32::consts::PI, time::Duration};
+
+use crate::graphics::{Color, DrawParam, Rect, Transform};
+
+/// An easing curve, mapping the linear progress of a [`Tween`] to how far along
+/// its value is.
+///
+/// The curves follow the usual names (see <https://easings.net/>): `In` curves start
+/// slowly, `Out` curves end slowly and `InOut` curves do both.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    QuartIn,
+    QuartOut,
+    QuartInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    ExpoIn,
+    ExpoOut,
+    ExpoInOut,
+    BackIn,
+    BackOut,
+    BackInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+}
+
+impl Easing {
+    /// Applies the curve to `t`, which is clamped to `0.0..=1.0`.
+    ///
+    /// All curves map `0.0` to `0.0` and `1.0` to `1.0`, but the `Back` and `Elastic`
+    /// curves overshoot in between.
+    pub fn apply(self, t: f32) -> f32 {
+        const BACK: f32 = 1.70158;
+        const BACK_IN_OUT: f32 = BACK * 1.525;
+
+        let t = t.clamp(0.0, 1.0);
+        if t == 0.0 || t == 1.0 {
+            return t;
+        }
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t).powi(2),
+            Easing::QuadInOut => in_out(t, |t| t * t),
+            Easing::CubicIn => t.powi(3),
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut => in_out(t, |t| t.powi(3)),
+            Easing::QuartIn => t.powi(4),
+            Easing::QuartOut => 1.0 - (1.0 - t).powi(4),
+            Easing::QuartInOut => in_out(t, |t| t.powi(4)),
+            Easing::SineIn => 1.0 - (t * PI / 2.0).cos(),
+            Easing::SineOut => (t * PI / 2.0).sin(),
+            Easing::SineInOut => -((PI * t).cos() - 1.0) / 2.0,
+            Easing::ExpoIn => 2f32.powf(10.0 * t - 10.0),
+            Easing::ExpoOut => 1.0 - 2f32.powf(-10.0 * t),
+            Easing::ExpoInOut => in_out(t, |t| 2f32.powf(10.0 * t - 10.0)),
+            Easing::BackIn => (BACK + 1.0) * t.powi(3) - BACK * t * t,
+            Easing::BackOut => 1.0 + (BACK + 1.0) * (t - 1.0).powi(3) + BACK * (t - 1.0).powi(2),
+            Easing::BackInOut => {
+                in_out(t, |t| (BACK_IN_OUT + 1.0) * t.powi(3) - BACK_IN_OUT * t * t)
+            }
+            Easing::ElasticIn => 1.0 - elastic_out(1.0 - t),
+            Easing::ElasticOut => elastic_out(t),
+            Easing::ElasticInOut => in_out(t, |t| 1.0 - elastic_out(1.0 - t)),
+            Easing::BounceIn => 1.0 - bounce_out(1.0 - t),
+            Easing::BounceOut => bounce_out(t),
+            Easing::BounceInOut => in_out(t, |t| 1.0 - bounce_out(1.0 - t)),
+        }
+    }
+}
+
+/// Builds an `InOut` curve out of an `In` curve, running it forwards over the first
+/// half and mirrored over the second.
+fn in_out(t: f32, ease_in: impl Fn(f32) -> f32) -> f32 {
+    if t < 0.5 {
+        ease_in(t * 2.0) / 2.0
+    } else {
+        1.0 - ease_in((1.0 - t) * 2.0) / 2.0
+    }
+}
+
+fn elastic_out(t: f32) -> f32 {
+    if t <= 0.0 || t >= 1.0 {
+        return t.clamp(0.0, 1.0);
+    }
+    2f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * (2.0 * PI / 3.0)).sin() + 1.0
+}
+
+fn bounce_out(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+/// Values that can be interpolated by a [`Tween`].
+pub trait Lerp {
+    /// Returns the value `t` of the way from `self` to `other`, where `t` is usually
+    /// between `0.0` and `1.0` but may overshoot with some [`Easing`] curves.
+    fn lerp(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for f64 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * f64::from(t)
+    }
+}
+
+impl Lerp for glam::Vec2 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        glam::Vec2::lerp(*self, *other, t)
+    }
+}
+
+impl Lerp for mint::Point2<f32> {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        mint::Point2 {
+            x: self.x.lerp(&other.x, t),
+            y: self.y.lerp(&other.y, t),
+        }
+    }
+}
+
+impl Lerp for mint::Vector2<f32> {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        mint::Vector2 {
+            x: self.x.lerp(&other.x, t),
+            y: self.y.lerp(&other.y, t),
+        }
+    }
+}
+
+impl Lerp for Color {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Color::new(
+            self.r.lerp(&other.r, t),
+            self.g.lerp(&other.g, t),
+            self.b.lerp(&other.b, t),
+            self.a.lerp(&other.a, t),
+        )
+    }
+}
+
+impl Lerp for Rect {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Rect::new(
+            self.x.lerp(&other.x, t),
+            self.y.lerp(&other.y, t),
+            self.w.lerp(&other.w, t),
+            self.h.lerp(&other.h, t),
+        )
+    }
+}
+
+/// Transforms made of values are interpolated value by value; if either one is a
+/// [`Transform::Matrix`], the result jumps from `self` to `other` once `t` reaches `1.0`.
+impl Lerp for Transform {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        match (self, other) {
+            (
+                Transform::Values {
+                    dest,
+                    rotation,
+                    scale,
+                    offset,
+                },
+                Transform::Values {
+                    dest: other_dest,
+                    rotation: other_rotation,
+                    scale: other_scale,
+                    offset: other_offset,
+                },
+            ) => Transform::Values {
+                dest: dest.lerp(other_dest, t),
+                rotation: rotation.lerp(other_rotation, t),
+                scale: scale.lerp(other_scale, t),
+                offset: offset.lerp(other_offset, t),
+            },
+            _ if t < 1.0 => *self,
+            _ => *other,
+        }
+    }
+}
+
+/// Interpolates the source rect, color and transform; the Z index jumps from `self`
+/// to `other` once `t` reaches `1.0`.
+impl Lerp for DrawParam {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        DrawParam {
+            src: self.src.lerp(&other.src, t),
+            color: self.color.lerp(&other.color, t),
+            transform: self.transform.lerp(&other.transform, t),
+            z: if t < 1.0 { self.z } else { other.z },
+        }
+    }
+}
+
+/// Animates a value from one state to another over a set duration, following an
+/// [`Easing`] curve.
+///
+/// Advance it with [`update()`](Self::update) every frame, e.g. by
+/// [`ctx.time.delta()`](crate::timer::TimeContext::delta), or hand it to a
+/// [`Scheduler`](super::Scheduler) to have it applied for you.
+///
+/// ```rust
+/// # use ggez::graphics::{Color, DrawParam};
+/// # use ggez::timer::{Easing, Tween};
+/// # use std::time::Duration;
+/// let mut fade = Tween::new(Color::WHITE, Color::new(1.0, 1.0, 1.0, 0.0), Duration::from_secs(1), Easing::QuadOut);
+/// let color = fade.update(Duration::from_millis(500));
+/// assert_eq!(color.a, 0.25);
+///
+/// let mut slide = Tween::new(
+///     DrawParam::new().dest([0.0, 0.0]),
+///     DrawParam::new().dest([100.0, 0.0]),
+///     Duration::from_secs(2),
+///     Easing::Linear,
+/// );
+/// let param = slide.update(Duration::from_secs(1));
+/// assert_eq!(param, DrawParam::new().dest([50.0, 0.0]));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tween<V> {
+    from: V,
+    to: V,
+    duration: Duration,
+    elapsed: Duration,
+    easing: Easing,
+}
+
+impl<V: Lerp> Tween<V> {
+    /// Creates a new tween going from `from` to `to` over `duration`.
+    pub fn new(from: V, to: V, duration: Duration, easing: Easing) -> Self {
+        Tween {
+            from,
+            to,
+            duration,
+            elapsed: Duration::ZERO,
+            easing,
+        }
+    }
+
+    /// Advances the tween by `delta` and returns its new value.
+    pub fn update(&mut self, delta: Duration) -> V {
+        self.elapsed = (self.elapsed + delta).min(self.duration);
+        self.value()
+    }
+
+    /// Returns the current value.
+    pub fn value(&self) -> V {
+        self.from.lerp(&self.to, self.easing.apply(self.progress()))
+    }
+
+    /// Returns how much of the duration has passed, from `0.0` to `1.0`.
+    pub fn progress(&self) -> f32 {
+        if self.duration.is_zero() {
+            1.0
+        } else {
+            (self.elapsed.as_secs_f64() / self.duration.as_secs_f64()) as f32
+        }
+    }
+
+    /// Returns `true` once the whole duration has passed.
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// Restarts the tween from the beginning.
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+    }
+
+    /// Returns the value the tween starts at.
+    pub fn from(&self) -> &V {
+        &self.from
+    }
+
+    /// Returns the value the tween ends at.
+    pub fn to(&self) -> &V {
+        &self.to
+    }
+
+    /// Returns how long the tween takes.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Returns the easing curve.
+    pub fn easing(&self) -> Easing {
+        self.easing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Easing; 25] = [
+        Easing::Linear,
+        Easing::QuadIn,
+        Easing::QuadOut,
+        Easing::QuadInOut,
+        Easing::CubicIn,
+        Easing::CubicOut,
+        Easing::CubicInOut,
+        Easing::QuartIn,
+        Easing::QuartOut,
+        Easing::QuartInOut,
+        Easing::SineIn,
+        Easing::SineOut,
+        Easing::SineInOut,
+        Easing::ExpoIn,
+        Easing::ExpoOut,
+        Easing::ExpoInOut,
+        Easing::BackIn,
+        Easing::BackOut,
+        Easing::BackInOut,
+        Easing::ElasticIn,
+        Easing::ElasticOut,
+        Easing::ElasticInOut,
+        Easing::BounceIn,
+        Easing::BounceOut,
+        Easing::BounceInOut,
+    ];
+
+    #[test]
+    fn headless_test_easing_endpoints() {
+        for easing in ALL {
+            assert_eq!(easing.apply(0.0), 0.0, "{easing:?}");
+            assert_eq!(easing.apply(1.0), 1.0, "{easing:?}");
+            assert_eq!(easing.apply(-1.0), 0.0, "{easing:?}");
+            assert_eq!(easing.apply(2.0), 1.0, "{easing:?}");
+            // The curves are continuous, so tiny steps only make tiny changes.
+            for i in 0..1000 {
+                let (a, b) = (i as f32 / 1000.0, (i + 1) as f32 / 1000.0);
+                assert!(
+                    (easing.apply(a) - easing.apply(b)).abs() < 0.05,
+                    "{easing:?} at {a}"
+                );
+            }
+        }
+        for easing in [Easing::QuadInOut, Easing::SineInOut, Easing::BounceInOut] {
+            assert!((easing.apply(0.5) - 0.5).abs() < 1e-5, "{easing:?}");
+        }
+        assert_eq!(Easing::QuadIn.apply(0.5), 0.25);
+        assert_eq!(Easing::QuadOut.apply(0.5), 0.75);
+        assert!(Easing::BackIn.apply(0.2) < 0.0);
+        assert!(Easing::ElasticOut.apply(0.2) > 1.0);
+    }
+
+    #[test]
+    fn headless_test_tween() {
+        let mut tween = Tween::new(10.0, 20.0, Duration::from_secs(2), Easing::Linear);
+        assert_eq!(tween.value(), 10.0);
+        assert_eq!(tween.update(Duration::from_millis(500)), 12.5);
+        assert!(!tween.is_finished());
+        assert_eq!(tween.update(Duration::from_secs(5)), 20.0);
+        assert!(tween.is_finished());
+        tween.reset();
+        assert_eq!(tween.progress(), 0.0);
+
+        let instant = Tween::new(0.0, 1.0, Duration::ZERO, Easing::CubicIn);
+        assert!(instant.is_finished());
+        assert_eq!(instant.value(), 1.0);
+
+        let from = DrawParam::new().dest([0.0, 10.0]).rotation(1.0).z(1);
+        let to = DrawParam::new()
+            .dest([10.0, 0.0])
+            .rotation(3.0)
+            .color(Color::BLACK)
+            .z(2);
+        let halfway = from.lerp(&to, 0.5);
+        assert_eq!(
+            halfway,
+            DrawParam {
+                color: Color::new(0.5, 0.5, 0.5, 1.0),
+                ..DrawParam::new().dest([5.0, 5.0]).rotation(2.0).z(1)
+            }
+        );
+        assert_eq!(from.lerp(&to, 1.0), to);
+    }
+}
@@ -15,6 +15,7 @@ use std::fmt::{self, Debug};
 use std::fs;
 use std::io::{self, Read, Seek, Write};
 use std::path::{self, Path, PathBuf};
+use std::time::SystemTime;
 
 use crate::error::{GameError, GameResult};
 
@@ -147,6 +148,9 @@ pub trait VMetadata {
     /// Returns the length of the thing.  If it is a directory,
     /// the result of this is undefined/platform dependent.
     fn len(&self) -> u64;
+    /// Returns when the thing was last modified, if that's known.
+    /// Zip files are never modified, so they don't say.
+    fn modified(&self) -> Option<SystemTime>;
 }
 
 /// A VFS that points to a directory and uses it as the root of its
@@ -174,6 +178,9 @@ impl VMetadata for PhysicalMetadata {
     fn len(&self) -> u64 {
         self.0.len()
     }
+    fn modified(&self) -> Option<SystemTime> {
+        self.0.modified().ok()
+    }
 }
 
 /// This takes an absolute path and returns either a sanitized relative
@@ -728,6 +735,9 @@ impl VMetadata for ZipMetadata {
     fn len(&self) -> u64 {
         self.len
     }
+    fn modified(&self) -> Option<SystemTime> {
+        None
+    }
 }
 
 impl VFS for ZipFS {